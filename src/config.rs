@@ -0,0 +1,62 @@
+use crate::state::{keymap::Keymap, theme::Theme};
+use anyhow::{bail, Context, Result};
+use std::{env, fs, path::PathBuf};
+
+// Settings read from ~/.config/tooni/config.toml (or under
+// $XDG_CONFIG_HOME when it's set). The file is optional; anything
+// it leaves out keeps its default.
+#[derive(Clone)]
+pub struct Config {
+    pub keymap: Keymap,
+    pub theme: Theme,
+    // Whether the mouse can be used to click and scroll, which
+    // stops the terminal selecting text the usual way:
+    //
+    //     mouse = false
+    pub mouse: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            mouse: true,
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Config> {
+        // Without a file everything is left as the default,
+        // other than the theme when NO_COLOR is set.
+        let path = match Config::path() {
+            Some(path) if path.exists() => path,
+            _ => return Config::parse(""),
+        };
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Couldn't read {}", path.display()))?;
+        Config::parse(&text).with_context(|| format!("Error in {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Config> {
+        let value = text.parse::<toml::Value>()?;
+        Ok(Config {
+            keymap: Keymap::from_config(value.get("keys"))?,
+            theme: Theme::from_config(value.get("theme"))?,
+            mouse: match value.get("mouse") {
+                Some(toml::Value::Boolean(mouse)) => *mouse,
+                Some(_) => bail!("mouse should be true or false"),
+                None => true,
+            },
+        })
+    }
+
+    fn path() -> Option<PathBuf> {
+        let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(config_dir.join("tooni").join("config.toml"))
+    }
+}
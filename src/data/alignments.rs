@@ -1,7 +1,11 @@
-use rusqlite::types::{FromSql, FromSqlResult, ValueRef};
+use enum_iterator::IntoEnumIterator;
+use rusqlite::{
+    types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+    Result,
+};
 use std::{fmt, str::FromStr};
 
-#[derive(Clone)]
+#[derive(Clone, Copy, PartialEq, IntoEnumIterator)]
 pub enum Alignment {
     LawfulGood,
     NeutralGood,
@@ -14,12 +18,24 @@ pub enum Alignment {
     ChaoticEvil,
 }
 
+impl Alignment {
+    pub fn get_all_alignments() -> Vec<Alignment> {
+        Alignment::into_enum_iter().collect()
+    }
+}
+
 impl FromSql for Alignment {
     fn column_result(value: ValueRef<'_>) -> FromSqlResult<Alignment> {
         Ok(Alignment::from_str(value.as_str()?).unwrap())
     }
 }
 
+impl ToSql for Alignment {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
 impl Default for Alignment {
     fn default() -> Self {
         Alignment::Neutral
@@ -35,7 +51,7 @@ impl fmt::Display for Alignment {
             &Alignment::LawfulNeutral => write!(f, "Lawful Neutral"),
             &Alignment::Neutral => write!(f, "Neutral"),
             &Alignment::ChaoticNeutral => write!(f, "Chaotic Neutral"),
-            &Alignment::LawfulEvil => write!(f, "Lawful Neutral"),
+            &Alignment::LawfulEvil => write!(f, "Lawful Evil"),
             &Alignment::NeutralEvil => write!(f, "Neutral Evil"),
             &Alignment::ChaoticEvil => write!(f, "Chaotic Evil"),
         }
@@ -1,5 +1,7 @@
 use crate::data::character::Model;
-use crate::data::{feature::Feature, items::Item, language::Language, proficiency::Proficiency};
+use crate::data::{
+    equipment::StartingEquipment, feature::Feature, language::Language, proficiency::Proficiency,
+};
 use rusqlite::{Result, Row, ToSql};
 use std::fmt;
 
@@ -12,17 +14,13 @@ pub struct Background {
     pub flaws: Option<Vec<String>>,
     pub proficiencies: Option<Vec<Proficiency>>,
     pub languages: Option<Vec<Language>>,
-    pub starting_equipment: Option<Vec<Item>>,
+    // Languages of the player's choice, ie. the Acolyte's two.
+    pub language_choices: u8,
+    pub starting_equipment: StartingEquipment,
     pub features: Option<Vec<Feature>>,
     pub personality_traits: Option<Vec<String>>,
 }
 
-impl Background {
-    pub fn new() -> Self {
-        Self::default()
-    }
-}
-
 impl fmt::Display for Background {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(
@@ -59,6 +57,8 @@ impl Model for Background {
         for flaw in &self.flaws.clone().unwrap_or(vec![String::new(); 6]) {
             params.push(Box::new(flaw.clone()));
         }
+        params.push(Box::new(self.starting_equipment.clone()));
+        params.push(Box::new(self.language_choices));
 
         params
     }
@@ -104,7 +104,8 @@ impl Model for Background {
             proficiencies: None,
             languages: None,
             features: None,
-            starting_equipment: None,
+            starting_equipment: row.get(28)?,
+            language_choices: row.get(29)?,
         })
     }
 
@@ -128,15 +129,6 @@ impl Model for Background {
                     .collect::<Vec<_>>(),
             ),
 
-            "background_inventory" => Some(
-                self.starting_equipment
-                    .clone()
-                    .unwrap_or(vec![])
-                    .into_iter()
-                    .map(|item| item.id.unwrap())
-                    .collect::<Vec<_>>(),
-            ),
-
             "background_features" => Some(
                 self.features
                     .clone()
@@ -153,7 +145,6 @@ impl Model for Background {
         Some(vec![
             "background_proficiencies".to_string(),
             "background_languages".to_string(),
-            "background_inventory".to_string(),
             "background_features".to_string(),
         ])
     }
@@ -164,7 +155,6 @@ impl Model for Background {
                 Some(("backgrounds".to_string(), "proficiencies".to_string()))
             }
             "background_languages" => Some(("backgrounds".to_string(), "languages".to_string())),
-            "background_inventory" => Some(("backgrounds".to_string(), "items".to_string())),
             "background_features" => Some(("backgrounds".to_string(), "features".to_string())),
             _ => Some((String::new(), String::new())),
         }
@@ -176,7 +166,6 @@ impl Model for Background {
                 Some(("background".to_string(), "proficiency".to_string()))
             }
             "background_languages" => Some(("background".to_string(), "language".to_string())),
-            "background_inventory" => Some(("background".to_string(), "item".to_string())),
             "background_features" => Some(("background".to_string(), "feature".to_string())),
             _ => Some((String::new(), String::new())),
         }
@@ -186,11 +175,9 @@ impl Model for Background {
         match table {
             "background_proficiencies" => Some("id, name, class".to_string()),
             "background_languages" => Some("id, name, description".to_string()),
-            "background_inventory" => Some(
-                "id, name, class, quantity, rarity, value, weight, properties, description"
-                    .to_string(),
-            ),
-            "background_features" => Some("id, class, name, description".to_string()),
+            "background_features" => {
+                Some("id, name, class, description, resource_max, recharge".to_string())
+            }
             _ => None,
         }
     }
@@ -207,11 +194,6 @@ impl Model for Background {
                     langs.push(Language::build(row)?);
                 }
             }
-            "background_inventory" => {
-                if let Some(items) = self.starting_equipment.as_mut() {
-                    items.push(Item::build(row)?);
-                }
-            }
             "background_features" => {
                 if let Some(feats) = self.features.as_mut() {
                     feats.push(Feature::build(row)?);
@@ -226,22 +208,36 @@ impl Model for Background {
         "backgrounds".to_string()
     }
 
+    // Each personality trait, ideal, bond and flaw gets a column
+    // of its own, matching the order they're pushed in parameters().
     fn columns() -> String {
-        "id INTEGER, 
-        name TEXT NOT NULL, 
-        personality_traits TEXT NOT NULL, 
-        ideals TEXT NOT NULL, 
-        bonds TEXT NOT NULL, 
-        flaws TEXT NOT NULL"
-            .to_string()
+        let mut columns = vec![
+            "id INTEGER PRIMARY KEY".to_string(),
+            "name TEXT NOT NULL".to_string(),
+        ];
+        columns.extend(
+            numbered_columns()
+                .into_iter()
+                .map(|column| format!("{} TEXT NOT NULL", column)),
+        );
+        columns.push("starting_equipment TEXT NOT NULL".to_string());
+        columns.push("language_choices INTEGER NOT NULL".to_string());
+        columns.join(",\n")
     }
 
     fn queries() -> String {
-        "id, name, personality_traits, ideals, bonds, flaws".to_string()
+        let mut queries = vec!["id".to_string(), "name".to_string()];
+        queries.extend(numbered_columns());
+        queries.push("starting_equipment".to_string());
+        queries.push("language_choices".to_string());
+        queries.join(", ")
     }
 
     fn values() -> String {
-        "?1, ?2, ?3, ?4, ?5, ?6".to_string()
+        (1..=30)
+            .map(|i| format!("?{}", i))
+            .collect::<Vec<_>>()
+            .join(", ")
     }
 
     fn id(&self) -> Option<i64> {
@@ -252,3 +248,17 @@ impl Model for Background {
         true
     }
 }
+
+// personality_trait_1 through personality_trait_8, then the
+// six each of ideals, bonds and flaws.
+fn numbered_columns() -> Vec<String> {
+    [
+        ("personality_trait", 8),
+        ("ideal", 6),
+        ("bond", 6),
+        ("flaw", 6),
+    ]
+    .iter()
+    .flat_map(|(name, count)| (1..=*count).map(move |i| format!("{}_{}", name, i)))
+    .collect()
+}
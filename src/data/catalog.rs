@@ -0,0 +1,179 @@
+use crate::data::{
+    character::SavedCharacter, feat::Feat, feature::Feature, items::Item, language::Language,
+    spells::Spell,
+};
+use rusqlite::{
+    types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+    Result,
+};
+
+// Anything saved in the database that can be searched for by
+// name; see Database::list_catalog.
+#[derive(Clone)]
+pub enum CatalogEntry {
+    Spell(Spell),
+    Item(Box<Item>),
+    Feature(Feature),
+    Feat(Feat),
+    Language(Language),
+    Character(SavedCharacter),
+}
+
+impl CatalogEntry {
+    pub fn name(&self) -> &str {
+        match self {
+            CatalogEntry::Spell(spell) => &spell.name,
+            CatalogEntry::Item(item) => &item.name,
+            CatalogEntry::Feature(feature) => &feature.name,
+            CatalogEntry::Feat(feat) => &feat.name,
+            CatalogEntry::Language(language) => &language.name,
+            CatalogEntry::Character(character) => &character.name,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CatalogEntry::Spell(_) => "Spell",
+            CatalogEntry::Item(_) => "Item",
+            CatalogEntry::Feature(_) => "Feature",
+            CatalogEntry::Feat(_) => "Feat",
+            CatalogEntry::Language(_) => "Language",
+            CatalogEntry::Character(_) => "Character",
+        }
+    }
+
+    // A line about the entry to go above its description,
+    // ie. "Level 3 Evocation" for a spell.
+    pub fn summary(&self) -> String {
+        match self {
+            CatalogEntry::Spell(spell) => {
+                let level = match spell.level {
+                    0 => "Cantrip".to_string(),
+                    level => format!("Level {}", level),
+                };
+                match &spell.school {
+                    Some(school) => format!("{} {}", level, school),
+                    None => level,
+                }
+            }
+            CatalogEntry::Item(item) => match &item.rarity {
+                Some(rarity) => format!("{}, {}", item.class, rarity),
+                None => item.class.clone(),
+            },
+            CatalogEntry::Feature(feature) => match &feature.class {
+                Some(class) => format!("{} feature", class),
+                None => "Feature".to_string(),
+            },
+            CatalogEntry::Feat(feat) => match feat.prerequisites.is_empty() {
+                true => "Feat".to_string(),
+                false => format!(
+                    "Feat, requires {}",
+                    feat.prerequisites
+                        .iter()
+                        .map(|prerequisite| prerequisite.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            },
+            CatalogEntry::Language(_) => "Language".to_string(),
+            CatalogEntry::Character(character) => format!(
+                "Level {} {} {}",
+                character.level, character.race, character.class
+            ),
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        match self {
+            CatalogEntry::Spell(spell) => &spell.description,
+            CatalogEntry::Item(item) => &item.description,
+            CatalogEntry::Feature(feature) => &feature.description,
+            CatalogEntry::Feat(feat) => &feat.description,
+            CatalogEntry::Language(language) => &language.description,
+            CatalogEntry::Character(_) => "",
+        }
+    }
+
+    // Which kind of model the entry is to Database::search, and its id
+    // there, for matching search hits back up with the catalog.
+    pub fn search_key(&self) -> Option<(SearchKind, i64)> {
+        match self {
+            CatalogEntry::Spell(spell) => Some((SearchKind::Spell, spell.id?)),
+            CatalogEntry::Item(item) => Some((SearchKind::Item, item.id?)),
+            CatalogEntry::Feature(feature) => Some((SearchKind::Feature, feature.id?)),
+            CatalogEntry::Feat(_) | CatalogEntry::Language(_) | CatalogEntry::Character(_) => None,
+        }
+    }
+}
+
+// The models whose descriptions are indexed for full-text search;
+// see Database::search.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchKind {
+    Spell,
+    Item,
+    Feature,
+}
+
+impl SearchKind {
+    pub const ALL: [SearchKind; 3] = [SearchKind::Spell, SearchKind::Item, SearchKind::Feature];
+
+    // How the kind is written in the search table.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SearchKind::Spell => "spell",
+            SearchKind::Item => "item",
+            SearchKind::Feature => "feature",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SearchKind> {
+        SearchKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+impl FromSql for SearchKind {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<SearchKind> {
+        SearchKind::from_name(value.as_str()?).ok_or(FromSqlError::InvalidType)
+    }
+}
+
+impl ToSql for SearchKind {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.name()))
+    }
+}
+
+// Marks either side of each matched word in a SearchHit's snippet.
+pub const MATCH_START: char = '\u{2}';
+pub const MATCH_END: char = '\u{3}';
+
+// A model found by Database::search, best matches first.
+#[derive(Clone, Debug)]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    pub id: i64,
+    // The part of the description around the match, with each
+    // matched word between MATCH_START and MATCH_END.
+    pub snippet: String,
+}
+
+impl SearchHit {
+    // The snippet split up into runs of text, each paired
+    // with whether it's part of the match.
+    pub fn snippet_parts(&self) -> Vec<(&str, bool)> {
+        let mut parts = Vec::new();
+        let mut rest = self.snippet.as_str();
+        while let Some(start) = rest.find(MATCH_START) {
+            let (before, matched) = rest.split_at(start);
+            let matched = &matched[MATCH_START.len_utf8()..];
+            let end = matched.find(MATCH_END).unwrap_or(matched.len());
+            parts.push((before, false));
+            parts.push((&matched[..end], true));
+            rest = matched.get(end + MATCH_END.len_utf8()..).unwrap_or("");
+        }
+        parts.push((rest, false));
+        parts.retain(|(text, _)| !text.is_empty());
+        parts
+    }
+}
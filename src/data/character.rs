@@ -1,12 +1,25 @@
 use crate::data::{
     alignments::Alignment,
+    catalog::SearchKind,
+    conditions::{ActiveCondition, Condition, Conditions},
+    currency::{CoinPurse, Price},
+    feat::Feat,
+    feature::{Feature, Recharge},
+    inventory::InventoryEntry,
     items::Item,
     language::Language,
     proficiency::Proficiency,
+    rules::derived_stats,
     spells::Spell,
     stats::Stats,
     stats::Stats::{CHA, CON, DEX, INT, STR, WIS},
 };
+use crate::{
+    data::rules::{
+        can_attune, can_take_feat, spell_slot_recharge, spell_slots, AttunementError, FeatError,
+    },
+    dice::Roller,
+};
 use rusqlite::{Result, Row, ToSql};
 use std::collections::HashMap;
 use std::fmt;
@@ -14,18 +27,29 @@ use std::fmt;
 #[derive(Default, Clone)]
 // TODO: Consider sets instead of Vecs for data structures
 // like languages, proficiencies, ect.
-// TODO: DON'T FORGET FEATS!
 pub struct Character {
     pub id: Option<i64>,
     pub name: String,
-    //    pub race: Race,
-    //    pub class: Class,
+    // The name of the character's race, ie. "Hill Dwarf".
+    pub race: String,
+    // The name of the character's class, ie. "Wizard".
+    pub class: String,
     //    pub background: Option<String>,
-    //    pub personality_trait: String,
-    //    pub ideal: String,
-    //    pub bond: String,
-    //    pub flaw: String,
-    //    pub features: Vec<Feature>,
+    // The Biography tab's sections; free-form text
+    // the player writes for the character.
+    pub appearance: String,
+    pub personality_traits: String,
+    pub ideals: String,
+    pub bonds: String,
+    pub flaws: String,
+    pub backstory: String,
+    pub notes: String,
+    pub features: Vec<Feature>,
+    pub feats: Vec<Feat>,
+    // Uses spent of each limited-use feature, keyed by feature name.
+    // Features missing from the map have all of their uses available.
+    pub expended_uses: HashMap<String, u8>,
+    pub conditions: Vec<ActiveCondition>,
     pub alignment: Alignment,
     pub stats: HashMap<Stats, u8>,
     pub proficiencies: Vec<Proficiency>,
@@ -38,8 +62,14 @@ pub struct Character {
     pub passive_perception: u8,
     pub inspiration: bool,
     pub languages: Vec<Language>,
-    pub invintory: Vec<Item>,
+    // Stacks of items the character is holding. These are saved
+    // in their own table rather than through a junction, see
+    // Database::save_character.
+    pub inventory: Vec<InventoryEntry>,
+    pub purse: CoinPurse,
     pub spells: Vec<Spell>,
+    // Spell slots spent of each level, 1st to 9th.
+    pub expended_slots: [u8; 9],
     // TODO: Calculated from Race and/or class
     pub speed: u8,
     pub gender: String,
@@ -49,12 +79,16 @@ pub struct Character {
     pub armor_class: u8,
     pub initiative: u8,
     pub hit_points: u16,
+    pub max_hit_points: u16,
     pub temp_hit_points: u16,
     // TODO: The xp value can be adjusted manually by the user,
     // level can auto adjust from xp value via the experience
     // table.
     pub level: u8,
     pub xp: u64,
+    // When the character was last written, in seconds since the
+    // Unix epoch; set by Database::save_character.
+    pub modified: i64,
 }
 
 impl fmt::Display for Character {
@@ -131,6 +165,786 @@ impl Character {
     pub fn new() -> Self {
         Self::default()
     }
+
+    // The character's score in the given stat with any increases
+    // from feats, which can't take it above 20. A stat that hasn't
+    // been set yet is treated as a score of 10.
+    pub fn ability_score(&self, stat: &Stats) -> u8 {
+        let base = *self.stats.get(stat).unwrap_or(&10);
+        let increase: u8 = self
+            .feats
+            .iter()
+            .map(|feat| feat.ability_increase(stat))
+            .sum();
+        base.max(base.saturating_add(increase).min(20))
+    }
+
+    // Ability modifier for the given stat.
+    pub fn modifier(&self, stat: &Stats) -> i8 {
+        (self.ability_score(stat) as i8 - 10).div_euclid(2)
+    }
+
+    // The character's own proficiencies along with
+    // those granted by their feats.
+    pub fn all_proficiencies(&self) -> Vec<Proficiency> {
+        let mut proficiencies = self.proficiencies.clone();
+        proficiencies.extend(self.feats.iter().flat_map(|feat| feat.proficiencies()));
+        proficiencies
+    }
+
+    // The character's features along with their feats,
+    // so feats with limited uses recharge like any other.
+    pub fn all_features(&self) -> Vec<Feature> {
+        let mut features = self.features.clone();
+        features.extend(self.feats.iter().map(|feat| feat.feature()));
+        features
+    }
+
+    // Takes the feat if the character meets its prerequisites
+    // and doesn't already have it.
+    pub fn take_feat(&mut self, feat: Feat) -> Result<(), FeatError> {
+        can_take_feat(self, &feat)?;
+        self.feats.push(feat);
+        Ok(())
+    }
+
+    // Maximum uses of a feature for this character, or None
+    // if the feature isn't limited-use.
+    pub fn max_uses(&self, feature: &Feature) -> Option<u8> {
+        feature
+            .resource
+            .as_ref()
+            .map(|resource| resource.max.evaluate(self))
+    }
+
+    pub fn current_uses(&self, feature: &Feature) -> Option<u8> {
+        self.max_uses(feature)
+            .map(|max| max.saturating_sub(*self.expended_uses.get(&feature.name).unwrap_or(&0)))
+    }
+
+    // Spends one use of the feature. Returns false, and changes
+    // nothing, if the feature has no uses left or isn't limited-use.
+    pub fn spend_use(&mut self, feature: &Feature) -> bool {
+        match self.current_uses(feature) {
+            Some(uses) if uses > 0 => {
+                *self.expended_uses.entry(feature.name.clone()).or_insert(0) += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Gives back one spent use of the feature, ie. when a use
+    // was spent by mistake or something restores a single use.
+    pub fn restore_use(&mut self, feature: &Feature) {
+        if let Some(spent) = self.expended_uses.get_mut(&feature.name) {
+            *spent = spent.saturating_sub(1);
+            if *spent == 0 {
+                self.expended_uses.remove(&feature.name);
+            }
+        }
+    }
+
+    // Current exhaustion level, 0 if the character isn't exhausted.
+    pub fn exhaustion(&self) -> u8 {
+        self.conditions
+            .iter()
+            .find_map(|active| match active.condition {
+                Condition::Exhaustion(level) => Some(level),
+                _ => None,
+            })
+            .unwrap_or(0)
+    }
+
+    pub fn has_condition(&self, condition: &Condition) -> bool {
+        self.conditions
+            .iter()
+            .any(|active| active.condition.same_kind(condition))
+    }
+
+    // Adds a condition, replacing the existing one of the same kind;
+    // re-applying a condition resets its duration, and adding
+    // exhaustion sets the exhaustion level.
+    pub fn add_condition(&mut self, condition: Condition, rounds: Option<u16>) {
+        self.remove_condition(&condition);
+        match condition {
+            Condition::Exhaustion(0) => {}
+            Condition::Exhaustion(level) => self.conditions.push(ActiveCondition {
+                condition: Condition::Exhaustion(level.min(6)),
+                rounds,
+            }),
+            _ => self.conditions.push(ActiveCondition { condition, rounds }),
+        }
+    }
+
+    pub fn remove_condition(&mut self, condition: &Condition) {
+        self.conditions
+            .retain(|active| !active.condition.same_kind(condition));
+    }
+
+    // Counts down the duration of every timed condition by one round,
+    // removing any that have run out.
+    pub fn end_round(&mut self) {
+        for active in self.conditions.iter_mut() {
+            if let Some(rounds) = active.rounds.as_mut() {
+                *rounds = rounds.saturating_sub(1);
+            }
+        }
+        self.conditions.retain(|active| active.rounds != Some(0));
+    }
+
+    pub fn entry(&self, key: u32) -> Option<&InventoryEntry> {
+        self.inventory.iter().find(|entry| entry.key == key)
+    }
+
+    pub fn entry_mut(&mut self, key: u32) -> Option<&mut InventoryEntry> {
+        self.inventory.iter_mut().find(|entry| entry.key == key)
+    }
+
+    fn next_key(&self) -> u32 {
+        self.inventory
+            .iter()
+            .map(|entry| entry.key + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Adds the item to the inventory, returning the key of the stack
+    // it ended up in. Items are added to an existing loose stack of
+    // the same item when there is one, otherwise a new stack is made.
+    pub fn add_item(&mut self, item: Item, quantity: u16) -> u32 {
+        let new = InventoryEntry {
+            item,
+            quantity,
+            ..InventoryEntry::new()
+        };
+        let existing = self.inventory.iter_mut().find(|entry| {
+            entry.same_item(&new)
+                && entry.container.is_none()
+                && !entry.equipped
+                && !entry.attuned
+                && entry.notes.is_empty()
+        });
+        match existing {
+            Some(entry) => {
+                entry.quantity = entry.quantity.saturating_add(quantity);
+                entry.key
+            }
+            None => {
+                let key = self.next_key();
+                self.inventory.push(InventoryEntry {
+                    key,
+                    character: self.id,
+                    ..new
+                });
+                key
+            }
+        }
+    }
+
+    // Removes up to the given quantity from the stack, removing the
+    // stack entirely once it's empty. Anything stored inside a removed
+    // stack is moved out into whatever held the stack.
+    pub fn remove_item(&mut self, key: u32, quantity: u16) {
+        let (remaining, container) = match self.entry_mut(key) {
+            Some(entry) => {
+                entry.quantity = entry.quantity.saturating_sub(quantity);
+                (entry.quantity, entry.container)
+            }
+            None => return,
+        };
+        if remaining == 0 {
+            self.inventory.retain(|entry| entry.key != key);
+            for entry in self.inventory.iter_mut() {
+                if entry.container == Some(key) {
+                    entry.container = container;
+                }
+            }
+        }
+    }
+
+    // Moves the given quantity out of the stack into a new stack in
+    // the same place, returning the new stack's key. Returns None,
+    // and changes nothing, unless some but not all of the stack is split.
+    pub fn split_stack(&mut self, key: u32, quantity: u16) -> Option<u32> {
+        let new_key = self.next_key();
+        let entry = self.entry_mut(key)?;
+        if quantity == 0 || quantity >= entry.quantity {
+            return None;
+        }
+        entry.quantity -= quantity;
+        let split = InventoryEntry {
+            id: None,
+            key: new_key,
+            quantity,
+            equipped: false,
+            attuned: false,
+            ..entry.clone()
+        };
+        self.inventory.push(split);
+        Some(new_key)
+    }
+
+    // Merges the first stack into the second. Returns false, and
+    // changes nothing, if they aren't stacks of the same item.
+    pub fn merge_stacks(&mut self, from: u32, into: u32) -> bool {
+        let (quantity, container) = match (self.entry(from), self.entry(into)) {
+            (Some(from_entry), Some(into_entry))
+                if from != into && from_entry.same_item(into_entry) =>
+            {
+                (from_entry.quantity, from_entry.container)
+            }
+            _ => return false,
+        };
+        // What's inside the merged stack moves into the other one,
+        // which is checked the same way as moving it there would be.
+        let moved = self
+            .contents(from)
+            .iter()
+            .map(|entry| entry.key)
+            .filter(|&key| key != into)
+            .collect::<Vec<_>>();
+        if !moved.is_empty() {
+            let capacity = match self.entry(into).and_then(|entry| entry.item.container) {
+                Some(profile) => profile.capacity,
+                None => return false,
+            };
+            let holds_into = self
+                .containers_of(into)
+                .iter()
+                .any(|holder| moved.contains(&holder.key));
+            let weight = self.contents_weight(into)
+                + moved.iter().map(|&key| self.stack_weight(key)).sum::<u64>();
+            if holds_into || weight > capacity {
+                return false;
+            }
+        }
+        if let Some(entry) = self.entry_mut(into) {
+            entry.quantity = entry.quantity.saturating_add(quantity);
+        }
+        self.inventory.retain(|entry| entry.key != from);
+        // The merged stack's contents follow it, unless the stack it
+        // was merged into was one of them, which takes its place.
+        for entry in self.inventory.iter_mut() {
+            if entry.container == Some(from) {
+                entry.container = match entry.key == into {
+                    true => container,
+                    false => Some(into),
+                };
+            }
+        }
+        true
+    }
+
+    // Stacks stored directly inside the given container.
+    pub fn contents(&self, container: u32) -> Vec<&InventoryEntry> {
+        self.inventory
+            .iter()
+            .filter(|entry| entry.container == Some(container))
+            .collect()
+    }
+
+    // The containers the entry is inside, innermost first. The
+    // walk stops after as many steps as there are entries, in case
+    // a bad save has left two containers inside each other.
+    fn containers_of(&self, key: u32) -> Vec<&InventoryEntry> {
+        let mut containers = Vec::new();
+        let mut current = self.entry(key).and_then(|entry| entry.container);
+        while let Some(container) = current.and_then(|key| self.entry(key)) {
+            if containers.len() >= self.inventory.len() {
+                break;
+            }
+            containers.push(container);
+            current = container.container;
+        }
+        containers
+    }
+
+    // Weight of the stack itself and everything inside it.
+    pub fn stack_weight(&self, key: u32) -> u64 {
+        let own = self
+            .entry(key)
+            .map(|entry| entry.item.weight * entry.quantity as u64)
+            .unwrap_or(0);
+        own + self.contents_weight(key)
+    }
+
+    // Weight of everything inside the container, whether or not
+    // the container makes it weightless; this is what counts
+    // toward the container's own capacity.
+    pub fn contents_weight(&self, container: u32) -> u64 {
+        self.inventory
+            .iter()
+            .filter(|entry| {
+                entry.key != container
+                    && self
+                        .containers_of(entry.key)
+                        .iter()
+                        .any(|holder| holder.key == container)
+            })
+            .map(|entry| entry.item.weight * entry.quantity as u64)
+            .sum()
+    }
+
+    // Total weight the character is carrying, in pounds. Anything
+    // inside a weightless container, at any depth, doesn't count.
+    pub fn carried_weight(&self) -> u64 {
+        self.inventory
+            .iter()
+            .filter(|entry| {
+                !self
+                    .containers_of(entry.key)
+                    .iter()
+                    .any(|container| matches!(container.item.container, Some(c) if c.weightless))
+            })
+            .map(|entry| entry.item.weight * entry.quantity as u64)
+            .sum::<u64>()
+            + self.purse.weight()
+    }
+
+    // Moves the stack into the container, or out of any container
+    // if None. Returns false, and changes nothing, if the target
+    // isn't a container, is the stack itself or something inside
+    // it, or doesn't have room for it.
+    pub fn move_to_container(&mut self, key: u32, container: Option<u32>) -> bool {
+        if self.entry(key).is_none() {
+            return false;
+        }
+        if let Some(container) = container {
+            let capacity = match self.entry(container).and_then(|entry| entry.item.container) {
+                Some(profile) => profile.capacity,
+                None => return false,
+            };
+            let inside_stack = container == key
+                || self
+                    .containers_of(container)
+                    .iter()
+                    .any(|entry| entry.key == key);
+            let already_inside =
+                self.entry(key).and_then(|entry| entry.container) == Some(container);
+            let weight = match already_inside {
+                true => self.contents_weight(container),
+                false => self.contents_weight(container) + self.stack_weight(key),
+            };
+            if inside_stack || weight > capacity {
+                return false;
+            }
+        }
+        if let Some(entry) = self.entry_mut(key) {
+            entry.container = container;
+        }
+        true
+    }
+
+    // Buys the item at its listed value, paying out of the purse.
+    // Returns the key of the stack the item ended up in, or None,
+    // and changes nothing, if the character can't afford it.
+    // Items without a value are free.
+    pub fn buy(&mut self, item: Item, quantity: u16) -> Option<u32> {
+        if let Some(value) = item.value {
+            let price = Price {
+                amount: value.amount * quantity as u64,
+                ..value
+            };
+            if !self.purse.pay(price) {
+                return None;
+            }
+        }
+        Some(self.add_item(item, quantity))
+    }
+
+    // Sells some of the stack for half its value, as equipment
+    // usually sells for; see PHB p.144.
+    pub fn sell(&mut self, key: u32, quantity: u16) {
+        let (value, quantity) = match self.entry(key) {
+            Some(entry) => (entry.item.value, quantity.min(entry.quantity)),
+            None => return,
+        };
+        if let Some(value) = value {
+            self.purse
+                .add_copper(value.in_copper() * quantity as u64 / 2);
+        }
+        self.remove_item(key, quantity);
+    }
+
+    // Attunes the character to the item, if the rules allow it.
+    pub fn attune(&mut self, key: u32) -> Result<(), AttunementError> {
+        let entry = self.entry(key).ok_or(AttunementError::NotRequired)?;
+        can_attune(self, entry)?;
+        if let Some(entry) = self.entry_mut(key) {
+            entry.attuned = true;
+        }
+        Ok(())
+    }
+
+    pub fn end_attunement(&mut self, key: u32) {
+        if let Some(entry) = self.entry_mut(key) {
+            entry.attuned = false;
+        }
+    }
+
+    // Spends charges from the item. Returns false, and changes
+    // nothing, if it doesn't have that many charges left.
+    pub fn spend_charges(&mut self, key: u32, charges: u8) -> bool {
+        match self.entry_mut(key) {
+            Some(entry) if entry.current_charges().unwrap_or(0) >= charges => {
+                entry.expended_charges += charges;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Recharges every item that regains charges on the given rest
+    // or at dawn, rolling for how many are regained where the item
+    // says so. Returns the key of each item recharged along with the
+    // number of charges it regained.
+    pub fn recharge_items(&mut self, rest: &Recharge, roller: &mut Roller) -> Vec<(u32, u8)> {
+        let mut regained = Vec::new();
+        for entry in self.inventory.iter_mut() {
+            let charges = match &entry.item.charges {
+                Some(charges) if charges.recharge.restored_by(rest) => charges,
+                _ => continue,
+            };
+            let amount = match &charges.regain {
+                Some(regain) => roller.roll(regain).total.clamp(0, u8::MAX as i32) as u8,
+                None => charges.max,
+            };
+            let amount = amount.min(entry.expended_charges);
+            entry.expended_charges -= amount;
+            regained.push((entry.key, amount));
+        }
+        regained
+    }
+
+    // Spell slots of the given level, 1 to 9, the character
+    // has left to cast with.
+    pub fn available_slots(&self, level: u8) -> u8 {
+        match level {
+            1..=9 => {
+                let i = level as usize - 1;
+                spell_slots(self)[i].saturating_sub(self.expended_slots[i])
+            }
+            _ => 0,
+        }
+    }
+
+    // Spends a slot of the given level. Returns false, and
+    // changes nothing, if there are none of that level left.
+    pub fn spend_slot(&mut self, level: u8) -> bool {
+        match self.available_slots(level) {
+            0 => false,
+            _ => {
+                self.expended_slots[level as usize - 1] += 1;
+                true
+            }
+        }
+    }
+
+    pub fn restore_slot(&mut self, level: u8) {
+        if let 1..=9 = level {
+            let spent = &mut self.expended_slots[level as usize - 1];
+            *spent = spent.saturating_sub(1);
+        }
+    }
+
+    // Restores every feature, and the spell slots,
+    // that recharge on the given rest.
+    pub fn recharge(&mut self, rest: &Recharge) {
+        if spell_slot_recharge(self).restored_by(rest) {
+            self.expended_slots = [0; 9];
+        }
+        for feature in &self.all_features() {
+            if let Some(resource) = &feature.resource {
+                if resource.recharge.restored_by(rest) {
+                    self.expended_uses.remove(&feature.name);
+                }
+            }
+        }
+    }
+
+    // Takes a short or long rest; see PHB p.186. Whatever recharges
+    // on the rest is restored, and after a long rest the character
+    // is back to their hit point maximum, their temporary hit points
+    // are gone and they're a level less exhausted. A long rest is
+    // taken to see a dawn in too, for what recharges then.
+    //
+    // Returns the key of each item recharged along with the
+    // number of charges it regained; see recharge_items.
+    pub fn rest(&mut self, rest: &Recharge, roller: &mut Roller) -> Vec<(u32, u8)> {
+        self.recharge(rest);
+        let mut regained = self.recharge_items(rest, roller);
+        if *rest == Recharge::LongRest {
+            self.recharge(&Recharge::Dawn);
+            regained.extend(self.recharge_items(&Recharge::Dawn, roller));
+            self.add_condition(
+                Condition::Exhaustion(self.exhaustion().saturating_sub(1)),
+                None,
+            );
+            self.hit_points = derived_stats(self)
+                .hit_point_max
+                .unwrap_or(self.max_hit_points);
+            self.temp_hit_points = 0;
+        }
+        regained
+    }
+}
+
+impl Model for Character {
+    fn parameters(&self) -> Vec<Box<dyn ToSql>> {
+        let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+        params.push(Box::new(self.id));
+        params.push(Box::new(self.name.clone()));
+        params.push(Box::new(self.alignment));
+        for stat in [STR, DEX, CON, INT, WIS, CHA] {
+            params.push(Box::new(*self.stats.get(&stat).unwrap_or(&10)));
+        }
+        for stat in [STR, DEX, CON, INT, WIS, CHA] {
+            params.push(Box::new(*self.saving_throws.get(&stat).unwrap_or(&false)));
+        }
+        params.push(Box::new(self.proficiency_bonus));
+        params.push(Box::new(self.passive_perception));
+        params.push(Box::new(self.inspiration));
+        params.push(Box::new(self.speed));
+        params.push(Box::new(self.gender.clone()));
+        params.push(Box::new(self.height));
+        params.push(Box::new(self.weight));
+        params.push(Box::new(self.age));
+        params.push(Box::new(self.armor_class));
+        params.push(Box::new(self.initiative));
+        params.push(Box::new(self.hit_points));
+        params.push(Box::new(self.temp_hit_points));
+        params.push(Box::new(self.level));
+        params.push(Box::new(self.xp as i64));
+        params.push(Box::new(Conditions(self.conditions.clone())));
+        params.push(Box::new(self.purse.clone()));
+        params.push(Box::new(self.class.clone()));
+        params.push(Box::new(self.race.clone()));
+        params.push(Box::new(
+            self.expended_slots
+                .iter()
+                .map(|spent| spent.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        ));
+        params.push(Box::new(self.appearance.clone()));
+        params.push(Box::new(self.personality_traits.clone()));
+        params.push(Box::new(self.ideals.clone()));
+        params.push(Box::new(self.bonds.clone()));
+        params.push(Box::new(self.flaws.clone()));
+        params.push(Box::new(self.backstory.clone()));
+        params.push(Box::new(self.notes.clone()));
+        params.push(Box::new(self.modified));
+        params.push(Box::new(self.max_hit_points));
+        params
+    }
+
+    fn build(row: &Row) -> Result<Character> {
+        let mut stats = HashMap::new();
+        let mut saving_throws = HashMap::new();
+        for (i, stat) in [STR, DEX, CON, INT, WIS, CHA].into_iter().enumerate() {
+            stats.insert(stat, row.get(3 + i)?);
+            saving_throws.insert(stat, row.get(9 + i)?);
+        }
+        let xp: i64 = row.get(28)?;
+        let conditions: Conditions = row.get(29)?;
+        let purse: CoinPurse = row.get(30)?;
+        let class: Option<String> = row.get(31)?;
+        let race: Option<String> = row.get(32)?;
+        let slots: String = row.get(33)?;
+        let mut expended_slots = [0; 9];
+        for (spent, slot) in expended_slots.iter_mut().zip(slots.split(',')) {
+            *spent = slot.parse().unwrap_or(0);
+        }
+
+        Ok(Character {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            alignment: row.get(2)?,
+            stats,
+            saving_throws,
+            proficiency_bonus: row.get(15)?,
+            passive_perception: row.get(16)?,
+            inspiration: row.get(17)?,
+            speed: row.get(18)?,
+            gender: row.get(19)?,
+            height: row.get(20)?,
+            weight: row.get(21)?,
+            age: row.get(22)?,
+            armor_class: row.get(23)?,
+            initiative: row.get(24)?,
+            hit_points: row.get(25)?,
+            temp_hit_points: row.get(26)?,
+            level: row.get(27)?,
+            xp: xp as u64,
+            expended_uses: HashMap::new(),
+            conditions: conditions.0,
+            purse,
+            class: class.unwrap_or_default(),
+            race: race.unwrap_or_default(),
+            expended_slots,
+            appearance: row.get(34)?,
+            personality_traits: row.get(35)?,
+            ideals: row.get(36)?,
+            bonds: row.get(37)?,
+            flaws: row.get(38)?,
+            backstory: row.get(39)?,
+            notes: row.get(40)?,
+            modified: row.get(41)?,
+            max_hit_points: row.get(42)?,
+            features: Vec::new(),
+            feats: Vec::new(),
+            proficiencies: Vec::new(),
+            languages: Vec::new(),
+            inventory: Vec::new(),
+            spells: Vec::new(),
+        })
+    }
+
+    fn junction_ids(&self, table: &str) -> Option<Vec<i64>> {
+        match table {
+            "character_features" => Some(self.features.iter().filter_map(|f| f.id).collect()),
+            "character_feats" => Some(self.feats.iter().filter_map(|f| f.id).collect()),
+            "character_proficiencies" => {
+                Some(self.proficiencies.iter().filter_map(|p| p.id).collect())
+            }
+            "character_languages" => Some(self.languages.iter().filter_map(|l| l.id).collect()),
+            "character_spells" => Some(self.spells.iter().filter_map(|s| s.id).collect()),
+            _ => Some(vec![]),
+        }
+    }
+
+    fn junct_tables() -> Option<Vec<String>> {
+        Some(vec![
+            "character_features".to_string(),
+            "character_feats".to_string(),
+            "character_proficiencies".to_string(),
+            "character_languages".to_string(),
+            "character_spells".to_string(),
+        ])
+    }
+
+    fn junct_references(table: &str) -> Option<(String, String)> {
+        match table {
+            "character_features" => Some(("characters".to_string(), Feature::table())),
+            "character_feats" => Some(("characters".to_string(), Feat::table())),
+            "character_proficiencies" => Some(("characters".to_string(), Proficiency::table())),
+            "character_languages" => Some(("characters".to_string(), Language::table())),
+            "character_spells" => Some(("characters".to_string(), Spell::table())),
+            _ => Some((String::new(), String::new())),
+        }
+    }
+
+    fn junct_columns(table: &str) -> Option<(String, String)> {
+        match table {
+            "character_features" => Some(("character".to_string(), "feature".to_string())),
+            "character_feats" => Some(("character".to_string(), "feat".to_string())),
+            "character_proficiencies" => Some(("character".to_string(), "proficiency".to_string())),
+            "character_languages" => Some(("character".to_string(), "language".to_string())),
+            "character_spells" => Some(("character".to_string(), "spell".to_string())),
+            _ => Some((String::new(), String::new())),
+        }
+    }
+
+    fn junct_qeries(table: &str) -> Option<String> {
+        match table {
+            "character_features" => Some(Feature::queries()),
+            "character_feats" => Some(Feat::queries()),
+            "character_proficiencies" => Some(Proficiency::queries()),
+            "character_languages" => Some(Language::queries()),
+            "character_spells" => Some(Spell::queries()),
+            _ => None,
+        }
+    }
+
+    fn build_junction(&mut self, table: &str, row: &Row) -> Result<()> {
+        match table {
+            "character_features" => self.features.push(Feature::build(row)?),
+            "character_feats" => self.feats.push(Feat::build(row)?),
+            "character_proficiencies" => self.proficiencies.push(Proficiency::build(row)?),
+            "character_languages" => self.languages.push(Language::build(row)?),
+            "character_spells" => self.spells.push(Spell::build(row)?),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn table() -> String {
+        "characters".to_string()
+    }
+
+    fn columns() -> String {
+        "id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        alignment TEXT NOT NULL,
+        str INTEGER,
+        dex INTEGER,
+        con INTEGER,
+        int INTEGER,
+        wis INTEGER,
+        cha INTEGER,
+        str_save INTEGER,
+        dex_save INTEGER,
+        con_save INTEGER,
+        int_save INTEGER,
+        wis_save INTEGER,
+        cha_save INTEGER,
+        proficiency_bonus INTEGER,
+        passive_perception INTEGER,
+        inspiration INTEGER,
+        speed INTEGER,
+        gender TEXT NOT NULL,
+        height INTEGER,
+        weight INTEGER,
+        age INTEGER,
+        armor_class INTEGER,
+        initiative INTEGER,
+        hit_points INTEGER,
+        temp_hit_points INTEGER,
+        level INTEGER,
+        xp INTEGER,
+        conditions TEXT NOT NULL,
+        purse TEXT NOT NULL,
+        class TEXT,
+        race TEXT,
+        expended_slots TEXT NOT NULL,
+        appearance TEXT NOT NULL,
+        personality_traits TEXT NOT NULL,
+        ideals TEXT NOT NULL,
+        bonds TEXT NOT NULL,
+        flaws TEXT NOT NULL,
+        backstory TEXT NOT NULL,
+        notes TEXT NOT NULL,
+        modified INTEGER NOT NULL DEFAULT 0,
+        max_hit_points INTEGER NOT NULL DEFAULT 0"
+            .to_string()
+    }
+
+    fn queries() -> String {
+        "id, name, alignment, str, dex, con, int, wis, cha, \
+        str_save, dex_save, con_save, int_save, wis_save, cha_save, \
+        proficiency_bonus, passive_perception, inspiration, speed, gender, \
+        height, weight, age, armor_class, initiative, hit_points, temp_hit_points, \
+        level, xp, conditions, purse, class, race, expended_slots, \
+        appearance, personality_traits, ideals, bonds, flaws, backstory, notes, modified, \
+        max_hit_points"
+            .to_string()
+    }
+
+    fn values() -> String {
+        (1..=43)
+            .map(|i| format!("?{}", i))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn id(&self) -> Option<i64> {
+        self.id
+    }
+
+    fn has_junctions() -> bool {
+        true
+    }
 }
 
 #[derive(Default, Clone)]
@@ -145,6 +959,9 @@ pub struct SavedCharacter {
     pub name: String,
     pub race: String,
     pub class: String,
+    pub level: u8,
+    // See Character::modified.
+    pub modified: i64,
 }
 
 impl SavedCharacter {
@@ -185,4 +1002,506 @@ pub trait Model {
     fn junct_qeries(table: &str) -> Option<String> {
         None
     }
+    // Models found by Database::search give their kind, along
+    // with the name and description they're found by.
+    fn search_kind() -> Option<SearchKind> {
+        None
+    }
+    fn search_text(&self) -> Option<(&str, &str)> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{
+        feature::{FeatureResource, ResourceMax},
+        items::{Attunement, Container, ItemCharges},
+    };
+    use std::str::FromStr;
+
+    fn feature(name: &str, max: ResourceMax, recharge: Recharge) -> Feature {
+        Feature {
+            name: name.to_string(),
+            resource: Some(FeatureResource { max, recharge }),
+            ..Feature::default()
+        }
+    }
+
+    #[test]
+    fn resource_maximums_follow_level_and_abilities() {
+        let mut character = Character {
+            level: 6,
+            ..Character::new()
+        };
+        character.stats.insert(CHA, 16);
+        let uses = |character: &Character, max: ResourceMax| {
+            character.max_uses(&feature("Feature", max, Recharge::LongRest))
+        };
+        assert_eq!(uses(&character, ResourceMax::Fixed(2)), Some(2));
+        assert_eq!(uses(&character, ResourceMax::Level(5)), Some(30));
+        let rage = ResourceMax::Scaling(vec![(1, 2), (3, 3), (6, 4), (12, 5)]);
+        assert_eq!(uses(&character, rage.clone()), Some(4));
+        assert_eq!(uses(&character, ResourceMax::Modifier(CHA)), Some(3));
+        // A low ability still gives the one use.
+        assert_eq!(uses(&character, ResourceMax::Modifier(STR)), Some(1));
+        assert_eq!(character.max_uses(&Feature::default()), None);
+
+        // Formulas are saved as text and read back the same.
+        for max in [rage, ResourceMax::Modifier(CHA), ResourceMax::Level(5)] {
+            assert_eq!(ResourceMax::from_str(&max.to_string()), Ok(max));
+        }
+    }
+
+    #[test]
+    fn uses_are_spent_and_restored() {
+        let mut character = Character::new();
+        let second_wind = feature("Second Wind", ResourceMax::Fixed(1), Recharge::ShortRest);
+        assert!(character.spend_use(&second_wind));
+        assert_eq!(character.current_uses(&second_wind), Some(0));
+        // There's nothing left to spend, and features
+        // without a resource have nothing to spend at all.
+        assert!(!character.spend_use(&second_wind));
+        assert!(!character.spend_use(&Feature::default()));
+
+        character.restore_use(&second_wind);
+        assert_eq!(character.current_uses(&second_wind), Some(1));
+        assert!(character.expended_uses.is_empty());
+    }
+
+    #[test]
+    fn rests_restore_what_recharges_on_them() {
+        let mut roller = Roller::seeded(1);
+        let mut character = Character {
+            temp_hit_points: 5,
+            ..Character::new()
+        };
+        for (name, recharge) in [
+            ("Second Wind", Recharge::ShortRest),
+            ("Indomitable", Recharge::LongRest),
+        ] {
+            let feature = feature(name, ResourceMax::Fixed(1), recharge);
+            character.spend_use(&feature);
+            character.features.push(feature);
+        }
+
+        character.rest(&Recharge::ShortRest, &mut roller);
+        assert_eq!(character.expended_uses.get("Second Wind"), None);
+        assert_eq!(character.expended_uses.get("Indomitable"), Some(&1));
+        assert_eq!(character.temp_hit_points, 5);
+
+        // A long rest restores what a short rest does too.
+        character.rest(&Recharge::LongRest, &mut roller);
+        assert!(character.expended_uses.is_empty());
+        assert_eq!(character.temp_hit_points, 0);
+    }
+
+    #[test]
+    fn long_rests_ease_exhaustion_before_restoring_hit_points() {
+        let mut roller = Roller::seeded(1);
+        let mut character = Character {
+            hit_points: 4,
+            max_hit_points: 24,
+            ..Character::new()
+        };
+        character.add_condition(Condition::Exhaustion(5), None);
+
+        // From level 5 the maximum is still halved at level 4.
+        character.rest(&Recharge::LongRest, &mut roller);
+        assert_eq!(character.exhaustion(), 4);
+        assert_eq!(character.hit_points, 12);
+        character.rest(&Recharge::LongRest, &mut roller);
+        assert_eq!(character.exhaustion(), 3);
+        assert_eq!(character.hit_points, 24);
+
+        // Short rests don't do either.
+        character.hit_points = 4;
+        character.rest(&Recharge::ShortRest, &mut roller);
+        assert_eq!((character.exhaustion(), character.hit_points), (3, 4));
+        for _ in 0..3 {
+            character.rest(&Recharge::LongRest, &mut roller);
+        }
+        assert!(character.conditions.is_empty());
+    }
+
+    fn item(name: &str, weight: u64) -> Item {
+        Item {
+            name: name.to_string(),
+            weight,
+            ..Item::new()
+        }
+    }
+
+    fn quantities(character: &Character) -> Vec<(String, u16)> {
+        character
+            .inventory
+            .iter()
+            .map(|entry| (entry.item.name.clone(), entry.quantity))
+            .collect()
+    }
+
+    #[test]
+    fn stacks_split_in_place() {
+        let mut character = Character::new();
+        let arrows = character.add_item(item("Arrow", 0), 20);
+        character.entry_mut(arrows).unwrap().equipped = true;
+        character.entry_mut(arrows).unwrap().notes = "Silvered".to_string();
+
+        let split = character.split_stack(arrows, 5).unwrap();
+        assert_ne!(split, arrows);
+        assert_eq!(
+            quantities(&character),
+            vec![("Arrow".to_string(), 15), ("Arrow".to_string(), 5)]
+        );
+        // The split keeps what's written on the stack, but
+        // isn't equipped just because the stack was.
+        let split = character.entry(split).unwrap();
+        assert_eq!(split.notes, "Silvered");
+        assert!(!split.equipped);
+
+        // Splitting off none or all of the stack does nothing.
+        assert_eq!(character.split_stack(arrows, 0), None);
+        assert_eq!(character.split_stack(arrows, 15), None);
+        assert_eq!(character.split_stack(99, 1), None);
+        assert_eq!(character.inventory.len(), 2);
+    }
+
+    #[test]
+    fn stacks_of_the_same_item_merge() {
+        let mut character = Character::new();
+        let arrows = character.add_item(item("Arrow", 0), 20);
+        let split = character.split_stack(arrows, 8).unwrap();
+        let rope = character.add_item(item("Rope", 10), 1);
+
+        assert!(!character.merge_stacks(split, rope));
+        assert!(!character.merge_stacks(split, split));
+        assert!(character.merge_stacks(split, arrows));
+        assert_eq!(
+            quantities(&character),
+            vec![("Arrow".to_string(), 20), ("Rope".to_string(), 1)]
+        );
+        assert!(character.entry(split).is_none());
+
+        // More of an item joins its loose stack rather than
+        // starting a new one.
+        assert_eq!(character.add_item(item("Rope", 10), 2), rope);
+        character.remove_item(arrows, 20);
+        assert_eq!(quantities(&character), vec![("Rope".to_string(), 3)]);
+    }
+
+    #[test]
+    fn merged_containers_keep_their_contents() {
+        let mut character = Character::new();
+        let sacks = character.add_item(
+            Item {
+                container: Some(Container {
+                    capacity: 30,
+                    weightless: false,
+                }),
+                ..item("Sack", 1)
+            },
+            2,
+        );
+        let other_sack = character.split_stack(sacks, 1).unwrap();
+        let rations = character.add_item(item("Rations", 2), 3);
+        assert!(character.move_to_container(rations, Some(other_sack)));
+
+        assert!(character.merge_stacks(other_sack, sacks));
+        assert_eq!(character.entry(rations).unwrap().container, Some(sacks));
+        assert_eq!(character.stack_weight(sacks), 2 + 6);
+
+        // Merging a stack whose contents wouldn't fit does nothing.
+        let other_sack = character.split_stack(sacks, 1).unwrap();
+        let anvil = character.add_item(item("Anvil", 25), 1);
+        character.entry_mut(anvil).unwrap().container = Some(other_sack);
+        assert!(!character.merge_stacks(other_sack, sacks));
+        assert_eq!(character.entry(other_sack).unwrap().quantity, 1);
+        assert_eq!(character.entry(anvil).unwrap().container, Some(other_sack));
+
+        // Nor does merging a stack into one inside something it holds,
+        // which would leave that inside the stack it holds.
+        character.entry_mut(anvil).unwrap().container = None;
+        let chest = character.add_item(container("Chest", 25, 300, false), 1);
+        character.entry_mut(chest).unwrap().container = Some(other_sack);
+        character.entry_mut(sacks).unwrap().container = Some(chest);
+        assert!(!character.merge_stacks(other_sack, sacks));
+        assert_eq!(character.entry(chest).unwrap().container, Some(other_sack));
+    }
+
+    fn container(name: &str, weight: u64, capacity: u64, weightless: bool) -> Item {
+        Item {
+            container: Some(Container {
+                capacity,
+                weightless,
+            }),
+            ..item(name, weight)
+        }
+    }
+
+    #[test]
+    fn containers_weigh_what_is_inside_them() {
+        let mut character = Character::new();
+        let backpack = character.add_item(container("Backpack", 5, 30, false), 1);
+        let pouch = character.add_item(container("Pouch", 1, 6, false), 1);
+        let rations = character.add_item(item("Rations", 2), 2);
+        assert!(character.move_to_container(pouch, Some(backpack)));
+        assert!(character.move_to_container(rations, Some(pouch)));
+
+        assert_eq!(character.stack_weight(pouch), 1 + 4);
+        assert_eq!(character.stack_weight(backpack), 5 + 1 + 4);
+        assert_eq!(character.contents_weight(backpack), 1 + 4);
+        assert_eq!(character.carried_weight(), 10);
+    }
+
+    #[test]
+    fn weightless_containers_hide_their_contents_weight() {
+        let mut character = Character::new();
+        let bag = character.add_item(container("Bag of Holding", 15, 500, true), 1);
+        let sack = character.add_item(container("Sack", 1, 30, false), 1);
+        let rope = character.add_item(item("Rope", 10), 2);
+        assert!(character.move_to_container(sack, Some(bag)));
+        assert!(character.move_to_container(rope, Some(sack)));
+
+        // The bag itself still weighs what it does, and what's in it
+        // counts toward its capacity, at any depth.
+        assert_eq!(character.carried_weight(), 15);
+        assert_eq!(character.contents_weight(bag), 1 + 20);
+        assert_eq!(character.stack_weight(bag), 15 + 1 + 20);
+
+        assert!(character.move_to_container(rope, None));
+        assert_eq!(character.carried_weight(), 15 + 20);
+    }
+
+    #[test]
+    fn stacks_only_move_where_they_fit() {
+        let mut character = Character::new();
+        let pouch = character.add_item(container("Pouch", 1, 6, false), 1);
+        let sack = character.add_item(container("Sack", 1, 30, false), 1);
+        let rope = character.add_item(item("Rope", 10), 1);
+        let rations = character.add_item(item("Rations", 2), 3);
+
+        // Too heavy, not a container, or the stack itself.
+        assert!(!character.move_to_container(rope, Some(pouch)));
+        assert!(!character.move_to_container(rations, Some(rope)));
+        assert!(!character.move_to_container(sack, Some(sack)));
+        assert!(!character.move_to_container(99, Some(sack)));
+        assert!(character.move_to_container(rations, Some(pouch)));
+        // A full pouch still has room for what's already in it.
+        assert!(character.move_to_container(rations, Some(pouch)));
+
+        // Containers can't end up inside what they hold.
+        assert!(character.move_to_container(pouch, Some(sack)));
+        assert!(!character.move_to_container(sack, Some(pouch)));
+        assert_eq!(character.entry(sack).unwrap().container, None);
+    }
+
+    fn priced(name: &str, value: &str) -> Item {
+        Item {
+            value: Some(value.parse().unwrap()),
+            ..item(name, 1)
+        }
+    }
+
+    #[test]
+    fn buying_pays_for_every_item() {
+        let mut character = Character::new();
+        character.purse = "1 gp".parse().unwrap();
+
+        let torches = character.buy(priced("Torch", "1 cp"), 10).unwrap();
+        assert_eq!(character.entry(torches).unwrap().quantity, 10);
+        assert_eq!(character.purse.to_string(), "9 sp");
+
+        // Nothing changes when the character can't afford it.
+        assert_eq!(character.buy(priced("Rope", "1 gp"), 1), None);
+        assert_eq!(character.purse.to_string(), "9 sp");
+        assert_eq!(character.inventory.len(), 1);
+
+        // Items without a value are free.
+        assert!(character.buy(item("Pebble", 0), 1).is_some());
+        assert_eq!(character.purse.to_string(), "9 sp");
+    }
+
+    #[test]
+    fn selling_gets_half_the_value() {
+        let mut character = Character::new();
+        let rope = character.add_item(priced("Rope", "1 gp"), 3);
+        let torch = character.add_item(priced("Torch", "1 cp"), 1);
+
+        character.sell(rope, 2);
+        assert_eq!(character.entry(rope).unwrap().quantity, 1);
+        assert_eq!(character.purse.to_string(), "1 gp");
+        // Selling more than the stack has sells it all.
+        character.sell(rope, 5);
+        assert!(character.entry(rope).is_none());
+        assert_eq!(character.purse.to_string(), "1 gp, 5 sp");
+        // Half a copper is rounded down.
+        character.sell(torch, 1);
+        assert_eq!(character.purse.to_string(), "1 gp, 5 sp");
+        assert!(character.inventory.is_empty());
+    }
+
+    fn charged(name: &str, max: u8, recharge: Recharge, regain: Option<&str>) -> Item {
+        Item {
+            charges: Some(ItemCharges {
+                max,
+                recharge,
+                regain: regain.map(|regain| regain.parse().unwrap()),
+            }),
+            ..item(name, 1)
+        }
+    }
+
+    #[test]
+    fn charges_are_only_spent_when_there_are_enough() {
+        let mut character = Character::new();
+        let wand = character.add_item(charged("Wand", 3, Recharge::Dawn, None), 1);
+        let rope = character.add_item(item("Rope", 10), 1);
+
+        assert!(character.spend_charges(wand, 2));
+        assert!(!character.spend_charges(wand, 2));
+        assert_eq!(character.entry(wand).unwrap().current_charges(), Some(1));
+        assert!(character.spend_charges(wand, 1));
+        assert!(!character.spend_charges(wand, 1));
+        assert_eq!(character.entry(wand).unwrap().current_charges(), Some(0));
+        // Items without charges have none to spend.
+        assert!(!character.spend_charges(rope, 1));
+    }
+
+    #[test]
+    fn items_recharge_on_their_own_rest() {
+        let mut character = Character::new();
+        let ring = character.add_item(charged("Ring", 2, Recharge::ShortRest, None), 1);
+        let wand = character.add_item(charged("Wand", 7, Recharge::Dawn, Some("1d6+1")), 1);
+        character.spend_charges(ring, 2);
+        character.spend_charges(wand, 7);
+
+        let mut roller = Roller::seeded(7);
+        let regained = character.recharge_items(&Recharge::ShortRest, &mut roller);
+        assert_eq!(regained, vec![(ring, 2)]);
+        assert_eq!(character.entry(wand).unwrap().current_charges(), Some(0));
+
+        // Rolled regains are within the dice range, and the
+        // same every time for the same seed.
+        let mut other = character.clone();
+        let regained = character.recharge_items(&Recharge::Dawn, &mut roller);
+        assert_eq!(regained.len(), 1);
+        assert!((2..=7).contains(&regained[0].1));
+        let current = character.entry(wand).unwrap().current_charges();
+        assert_eq!(current, Some(regained[0].1));
+        let mut roller = Roller::seeded(7);
+        other.recharge_items(&Recharge::ShortRest, &mut roller);
+        assert_eq!(other.recharge_items(&Recharge::Dawn, &mut roller), regained);
+    }
+
+    #[test]
+    fn items_never_regain_more_than_was_spent() {
+        let mut character = Character::new();
+        let wand = character.add_item(charged("Wand", 7, Recharge::Dawn, Some("1d6+6")), 1);
+        character.spend_charges(wand, 3);
+
+        let regained = character.recharge_items(&Recharge::Dawn, &mut Roller::seeded(1));
+        assert_eq!(regained, vec![(wand, 3)]);
+        assert_eq!(character.entry(wand).unwrap().current_charges(), Some(7));
+    }
+
+    #[test]
+    fn long_rests_recharge_what_recharges_at_dawn() {
+        let mut character = Character::new();
+        let ring = character.add_item(charged("Ring", 2, Recharge::ShortRest, None), 1);
+        let wand = character.add_item(charged("Wand", 3, Recharge::Dawn, None), 1);
+        character.spend_charges(ring, 1);
+        character.spend_charges(wand, 3);
+
+        let mut roller = Roller::seeded(1);
+        assert_eq!(
+            character.rest(&Recharge::ShortRest, &mut roller),
+            vec![(ring, 1)]
+        );
+        character.spend_charges(ring, 2);
+        let regained = character.rest(&Recharge::LongRest, &mut roller);
+        assert_eq!(regained, vec![(ring, 2), (wand, 3)]);
+    }
+
+    fn attuned(name: &str, classes: &[&str]) -> Item {
+        Item {
+            attunement: Some(Attunement {
+                classes: classes.iter().map(|class| class.to_string()).collect(),
+            }),
+            ..item(name, 1)
+        }
+    }
+
+    #[test]
+    fn characters_attune_to_at_most_three_items() {
+        let mut character = Character::new();
+        let keys: Vec<u32> = (1..=4)
+            .map(|i| character.add_item(attuned(&format!("Ring {}", i), &[]), 1))
+            .collect();
+
+        for key in &keys[..3] {
+            assert_eq!(character.attune(*key), Ok(()));
+        }
+        assert_eq!(
+            character.attune(keys[3]),
+            Err(AttunementError::TooManyItems)
+        );
+        assert!(!character.entry(keys[3]).unwrap().attuned);
+
+        // Ending an attunement makes room for another.
+        character.end_attunement(keys[0]);
+        assert_eq!(character.attune(keys[3]), Ok(()));
+        assert_eq!(
+            character.attune(keys[3]),
+            Err(AttunementError::AlreadyAttuned)
+        );
+    }
+
+    #[test]
+    fn only_some_items_can_be_attuned_to() {
+        let mut character = Character::new();
+        character.class = "Fighter".to_string();
+        let rope = character.add_item(item("Rope", 10), 1);
+        let staff = character.add_item(attuned("Staff", &["Wizard", "Sorcerer"]), 1);
+        let sword = character.add_item(attuned("Sword", &["fighter"]), 1);
+
+        assert_eq!(character.attune(rope), Err(AttunementError::NotRequired));
+        assert_eq!(
+            character.attune(staff),
+            Err(AttunementError::WrongClass(vec![
+                "Wizard".to_string(),
+                "Sorcerer".to_string()
+            ]))
+        );
+        // Classes are matched whatever their case.
+        assert_eq!(character.attune(sword), Ok(()));
+    }
+
+    #[test]
+    fn spell_slots_are_spent_and_recharged() {
+        let mut character = Character {
+            class: "Wizard".to_string(),
+            level: 3,
+            ..Character::new()
+        };
+        assert!(character.spend_slot(2));
+        assert!(character.spend_slot(2));
+        assert!(!character.spend_slot(2));
+        assert!(!character.spend_slot(3));
+        assert_eq!(character.available_slots(2), 0);
+        assert_eq!(character.available_slots(1), 4);
+        character.restore_slot(2);
+        assert_eq!(character.available_slots(2), 1);
+
+        // Wizards get theirs back on a long rest, warlocks on a short one.
+        let mut roller = Roller::seeded(1);
+        character.rest(&Recharge::ShortRest, &mut roller);
+        assert_eq!(character.available_slots(2), 1);
+        character.rest(&Recharge::LongRest, &mut roller);
+        assert_eq!(character.available_slots(2), 2);
+        character.class = "Warlock".to_string();
+        assert!(character.spend_slot(2));
+        character.rest(&Recharge::ShortRest, &mut roller);
+        assert_eq!(character.available_slots(2), 2);
+    }
 }
@@ -1,4 +1,11 @@
-use crate::data::{feature::Feature, language::Language, proficiency::Proficiency, stats::Stats};
+use crate::data::{
+    equipment::{StartingEquipment, StartingGold},
+    feature::Feature,
+    language::Language,
+    proficiency::Proficiency,
+    skills::Skill,
+    stats::Stats,
+};
 use std::collections::HashMap;
 
 #[derive(Default, Clone)]
@@ -9,8 +16,16 @@ pub struct Class {
     pub features: Vec<Feature>,
     pub proficiencies: Vec<Proficiency>,
     pub saving_throws: HashMap<Stats, bool>,
+    // The number of hit dice at first level and their size, ie. (1, 12).
     pub hit_dice: (u8, u8),
+    // How many skills are picked from the options at first level.
+    pub skill_choices: u8,
+    pub skill_options: Vec<Skill>,
     pub spells_known: Option<u8>,
     pub spell_slots: Option<u8>,
     pub spell_slot_level: Option<u8>,
+    pub starting_equipment: StartingEquipment,
+    // Rolled instead of taking the starting equipment; falls back
+    // to the PHB's gold for the class when not set.
+    pub starting_gold: Option<StartingGold>,
 }
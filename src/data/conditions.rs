@@ -0,0 +1,170 @@
+use rusqlite::{
+    types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+    Result,
+};
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+// Exhaustion is the only condition with levels (1-6);
+// every other condition is either on or off.
+pub enum Condition {
+    Blinded,
+    Charmed,
+    Deafened,
+    Frightened,
+    Grappled,
+    Incapacitated,
+    Invisible,
+    Paralyzed,
+    Petrified,
+    Poisoned,
+    Prone,
+    Restrained,
+    Stunned,
+    Unconscious,
+    Exhaustion(u8),
+}
+
+impl Condition {
+    // Every condition a character can have, in the order they're
+    // listed in the PHB. Exhaustion is listed once at level 1.
+    pub fn get_all_conditions() -> Vec<Condition> {
+        vec![
+            Condition::Blinded,
+            Condition::Charmed,
+            Condition::Deafened,
+            Condition::Exhaustion(1),
+            Condition::Frightened,
+            Condition::Grappled,
+            Condition::Incapacitated,
+            Condition::Invisible,
+            Condition::Paralyzed,
+            Condition::Petrified,
+            Condition::Poisoned,
+            Condition::Prone,
+            Condition::Restrained,
+            Condition::Stunned,
+            Condition::Unconscious,
+        ]
+    }
+
+    // Whether two conditions are the same kind of condition,
+    // ignoring exhaustion levels.
+    pub fn same_kind(&self, other: &Condition) -> bool {
+        matches!(
+            (self, other),
+            (Condition::Exhaustion(_), Condition::Exhaustion(_))
+        ) || self == other
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Blinded => write!(f, "Blinded"),
+            Condition::Charmed => write!(f, "Charmed"),
+            Condition::Deafened => write!(f, "Deafened"),
+            Condition::Frightened => write!(f, "Frightened"),
+            Condition::Grappled => write!(f, "Grappled"),
+            Condition::Incapacitated => write!(f, "Incapacitated"),
+            Condition::Invisible => write!(f, "Invisible"),
+            Condition::Paralyzed => write!(f, "Paralyzed"),
+            Condition::Petrified => write!(f, "Petrified"),
+            Condition::Poisoned => write!(f, "Poisoned"),
+            Condition::Prone => write!(f, "Prone"),
+            Condition::Restrained => write!(f, "Restrained"),
+            Condition::Stunned => write!(f, "Stunned"),
+            Condition::Unconscious => write!(f, "Unconscious"),
+            Condition::Exhaustion(level) => write!(f, "Exhaustion {}", level),
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "Blinded" => Ok(Condition::Blinded),
+            "Charmed" => Ok(Condition::Charmed),
+            "Deafened" => Ok(Condition::Deafened),
+            "Frightened" => Ok(Condition::Frightened),
+            "Grappled" => Ok(Condition::Grappled),
+            "Incapacitated" => Ok(Condition::Incapacitated),
+            "Invisible" => Ok(Condition::Invisible),
+            "Paralyzed" => Ok(Condition::Paralyzed),
+            "Petrified" => Ok(Condition::Petrified),
+            "Poisoned" => Ok(Condition::Poisoned),
+            "Prone" => Ok(Condition::Prone),
+            "Restrained" => Ok(Condition::Restrained),
+            "Stunned" => Ok(Condition::Stunned),
+            "Unconscious" => Ok(Condition::Unconscious),
+            _ => match input.strip_prefix("Exhaustion ").map(str::parse) {
+                Some(Ok(level)) if (1..=6).contains(&level) => Ok(Condition::Exhaustion(level)),
+                _ => Err(()),
+            },
+        }
+    }
+}
+
+// A condition currently affecting a character. A condition
+// with no duration lasts until it's removed by hand.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveCondition {
+    pub condition: Condition,
+    pub rounds: Option<u16>,
+}
+
+impl fmt::Display for ActiveCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rounds {
+            Some(rounds) => write!(f, "{} ({} rds)", self.condition, rounds),
+            None => write!(f, "{}", self.condition),
+        }
+    }
+}
+
+impl FromStr for ActiveCondition {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.strip_suffix(" rds)").and_then(|s| s.split_once(" (")) {
+            Some((condition, rounds)) => Ok(ActiveCondition {
+                condition: Condition::from_str(condition)?,
+                rounds: Some(rounds.parse().map_err(|_| ())?),
+            }),
+            None => Ok(ActiveCondition {
+                condition: Condition::from_str(input)?,
+                rounds: None,
+            }),
+        }
+    }
+}
+
+// The full list of a character's conditions, stored in a single
+// column as a comma separated list, ie. "Poisoned (3 rds), Prone".
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Conditions(pub Vec<ActiveCondition>);
+
+impl FromSql for Conditions {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Conditions> {
+        Ok(Conditions(
+            value
+                .as_str()?
+                .split(", ")
+                .filter(|condition| !condition.is_empty())
+                .map(|condition| ActiveCondition::from_str(condition).unwrap())
+                .collect(),
+        ))
+    }
+}
+
+impl ToSql for Conditions {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(
+            self.0
+                .iter()
+                .map(|condition| condition.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ))
+    }
+}
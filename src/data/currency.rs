@@ -0,0 +1,319 @@
+use enum_iterator::IntoEnumIterator;
+use rusqlite::{
+    types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+    Result,
+};
+use std::{fmt, str::FromStr};
+
+// 50 coins of any kind weigh a pound; see PHB p.143.
+const COINS_PER_POUND: u64 = 50;
+
+// Coin denominations, smallest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, IntoEnumIterator)]
+pub enum Denomination {
+    Copper,
+    Silver,
+    Electrum,
+    Gold,
+    Platinum,
+}
+
+impl Denomination {
+    pub fn get_all_denominations() -> Vec<Denomination> {
+        Denomination::into_enum_iter().collect()
+    }
+
+    pub fn in_copper(&self) -> u64 {
+        match self {
+            Denomination::Copper => 1,
+            Denomination::Silver => 10,
+            Denomination::Electrum => 50,
+            Denomination::Gold => 100,
+            Denomination::Platinum => 1000,
+        }
+    }
+}
+
+impl FromStr for Denomination {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "cp" => Ok(Denomination::Copper),
+            "sp" => Ok(Denomination::Silver),
+            "ep" => Ok(Denomination::Electrum),
+            "gp" => Ok(Denomination::Gold),
+            "pp" => Ok(Denomination::Platinum),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Denomination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Denomination::Copper => write!(f, "cp"),
+            Denomination::Silver => write!(f, "sp"),
+            Denomination::Electrum => write!(f, "ep"),
+            Denomination::Gold => write!(f, "gp"),
+            Denomination::Platinum => write!(f, "pp"),
+        }
+    }
+}
+
+// An amount of a single denomination, ie. an item's value
+// written the way the PHB lists it, "15 gp".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Price {
+    pub amount: u64,
+    pub denomination: Denomination,
+}
+
+impl Price {
+    pub fn in_copper(&self) -> u64 {
+        self.amount * self.denomination.in_copper()
+    }
+}
+
+impl FromSql for Price {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Price> {
+        Ok(Price::from_str(value.as_str()?).unwrap())
+    }
+}
+
+impl ToSql for Price {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromStr for Price {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (amount, denomination) = input.trim().split_once(' ').ok_or(())?;
+        Ok(Price {
+            amount: amount.parse().map_err(|_| ())?,
+            denomination: Denomination::from_str(denomination.trim())?,
+        })
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.amount, self.denomination)
+    }
+}
+
+// The coins a character is carrying, by denomination.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct CoinPurse {
+    pub cp: u64,
+    pub sp: u64,
+    pub ep: u64,
+    pub gp: u64,
+    pub pp: u64,
+}
+
+impl CoinPurse {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn coins(&self, denomination: Denomination) -> u64 {
+        match denomination {
+            Denomination::Copper => self.cp,
+            Denomination::Silver => self.sp,
+            Denomination::Electrum => self.ep,
+            Denomination::Gold => self.gp,
+            Denomination::Platinum => self.pp,
+        }
+    }
+
+    fn coins_mut(&mut self, denomination: Denomination) -> &mut u64 {
+        match denomination {
+            Denomination::Copper => &mut self.cp,
+            Denomination::Silver => &mut self.sp,
+            Denomination::Electrum => &mut self.ep,
+            Denomination::Gold => &mut self.gp,
+            Denomination::Platinum => &mut self.pp,
+        }
+    }
+
+    // The worth of every coin in the purse, in copper.
+    pub fn in_copper(&self) -> u64 {
+        Denomination::get_all_denominations()
+            .iter()
+            .map(|denomination| self.coins(*denomination) * denomination.in_copper())
+            .sum()
+    }
+
+    pub fn coin_count(&self) -> u64 {
+        self.cp + self.sp + self.ep + self.gp + self.pp
+    }
+
+    // Weight of the coins in whole pounds.
+    pub fn weight(&self) -> u64 {
+        self.coin_count() / COINS_PER_POUND
+    }
+
+    pub fn add(&mut self, price: Price) {
+        *self.coins_mut(price.denomination) += price.amount;
+    }
+
+    // Adds an amount of copper as the fewest gold, silver and copper
+    // coins. Electrum and platinum aren't handed out as change.
+    pub fn add_copper(&mut self, copper: u64) {
+        let mut copper = copper;
+        for denomination in [
+            Denomination::Gold,
+            Denomination::Silver,
+            Denomination::Copper,
+        ] {
+            *self.coins_mut(denomination) += copper / denomination.in_copper();
+            copper %= denomination.in_copper();
+        }
+    }
+
+    // Pays the price out of the purse, making change where the
+    // coins don't add up exactly. Returns false, and changes nothing,
+    // if the purse doesn't hold enough.
+    //
+    // The smallest coins are spent first, as many of each as fit
+    // in what's still owed. Whatever's left is then less than any
+    // remaining coin, so a single one of the smallest remaining
+    // coins covers it and the difference is given back as change.
+    pub fn pay(&mut self, price: Price) -> bool {
+        let cost = price.in_copper();
+        if self.in_copper() < cost {
+            return false;
+        }
+
+        let mut owed = cost;
+        for denomination in Denomination::get_all_denominations() {
+            let coins = self.coins_mut(denomination);
+            let spent = (*coins).min(owed / denomination.in_copper());
+            *coins -= spent;
+            owed -= spent * denomination.in_copper();
+        }
+        if owed > 0 {
+            if let Some(denomination) = Denomination::get_all_denominations()
+                .into_iter()
+                .find(|denomination| self.coins(*denomination) > 0)
+            {
+                *self.coins_mut(denomination) -= 1;
+                self.add_copper(denomination.in_copper() - owed);
+            }
+        }
+        true
+    }
+}
+
+impl FromSql for CoinPurse {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<CoinPurse> {
+        Ok(CoinPurse::from_str(value.as_str()?).unwrap_or_default())
+    }
+}
+
+impl ToSql for CoinPurse {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromStr for CoinPurse {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut purse = CoinPurse::new();
+        for coins in input.split(", ").filter(|coins| !coins.is_empty()) {
+            purse.add(Price::from_str(coins)?);
+        }
+        Ok(purse)
+    }
+}
+
+// Only the denominations the purse holds any of are shown,
+// largest first, ie. "12 gp, 5 sp".
+impl fmt::Display for CoinPurse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let coins = Denomination::get_all_denominations()
+            .into_iter()
+            .rev()
+            .filter(|denomination| self.coins(*denomination) > 0)
+            .map(|denomination| {
+                Price {
+                    amount: self.coins(denomination),
+                    denomination,
+                }
+                .to_string()
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", coins.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn purse(coins: &str) -> CoinPurse {
+        CoinPurse::from_str(coins).unwrap()
+    }
+
+    fn price(price: &str) -> Price {
+        Price::from_str(price).unwrap()
+    }
+
+    #[test]
+    fn coins_are_counted_in_copper() {
+        assert_eq!(price("15 gp").in_copper(), 1500);
+        assert_eq!(price("2 ep").in_copper(), 100);
+        assert_eq!(purse("1 pp, 2 gp, 3 ep, 4 sp, 5 cp").in_copper(), 1395);
+        assert_eq!(purse("").in_copper(), 0);
+        assert_eq!(
+            purse("1 pp, 2 gp, 3 ep, 4 sp, 5 cp").to_string(),
+            "1 pp, 2 gp, 3 ep, 4 sp, 5 cp"
+        );
+        assert!(Price::from_str("15").is_err());
+        assert!(Price::from_str("15 dollars").is_err());
+    }
+
+    #[test]
+    fn copper_is_added_as_the_fewest_coins() {
+        let mut coins = purse("1 ep");
+        coins.add_copper(1234);
+        assert_eq!(coins.to_string(), "12 gp, 1 ep, 3 sp, 4 cp");
+        coins.add_copper(0);
+        assert_eq!(coins.to_string(), "12 gp, 1 ep, 3 sp, 4 cp");
+    }
+
+    #[test]
+    fn exact_payments_spend_the_smallest_coins_first() {
+        let mut coins = purse("2 gp, 10 sp, 5 cp");
+        assert!(coins.pay(price("1 gp")));
+        assert_eq!(coins.to_string(), "2 gp, 5 cp");
+        assert!(coins.pay(price("5 cp")));
+        assert_eq!(coins.to_string(), "2 gp");
+    }
+
+    #[test]
+    fn payments_are_given_change() {
+        let mut coins = purse("1 pp");
+        assert!(coins.pay(price("3 sp")));
+        assert_eq!(coins.to_string(), "9 gp, 7 sp");
+        assert_eq!(coins.in_copper(), 1000 - 30);
+
+        // Coppers that don't cover it are spent before a coin is broken.
+        let mut coins = purse("1 gp, 5 cp");
+        assert!(coins.pay(price("8 cp")));
+        assert_eq!(coins.to_string(), "9 sp, 7 cp");
+    }
+
+    #[test]
+    fn payments_need_enough_coins() {
+        let mut coins = purse("1 gp, 9 sp, 9 cp");
+        assert!(!coins.pay(price("2 gp")));
+        assert_eq!(coins, purse("1 gp, 9 sp, 9 cp"));
+        assert!(coins.pay(price("199 cp")));
+        assert_eq!(coins, CoinPurse::new());
+        assert!(!coins.pay(price("1 cp")));
+    }
+}
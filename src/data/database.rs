@@ -1,12 +1,40 @@
 use super::character::Model;
-use crate::data::character::SavedCharacter;
-use rusqlite::{params, params_from_iter, Connection, Result};
+use crate::data::{
+    background::Background,
+    catalog::{CatalogEntry, SearchHit, SearchKind, MATCH_END, MATCH_START},
+    character::{Character, SavedCharacter},
+    feat::Feat,
+    feature::Feature,
+    inventory::InventoryEntry,
+    items::Item,
+    language::Language,
+    proficiency::Proficiency,
+    query::{Filter, Order, Query},
+    rolls::{Roll, RollStatistics},
+    spells::Spell,
+};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Result};
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 // TODO: Consider PRAGMA SQLite statement at connection open
 pub struct Database {
     connection: Connection,
 }
 
+// The layout of the tables, kept in a database's PRAGMA user_version.
+// Tables are only created when they don't exist, so changing the
+// columns of a saved model means bumping this and bringing databases
+// from before up to date in migrate.
+const SCHEMA_VERSION: i64 = 1;
+
+// Where the uses spent of each character's limited-use features are
+// saved, a row per feature. Features are referred to by name, since
+// the uses are kept by name on the character.
+const EXPENDED_USES: &str = "expended_uses";
+
 impl Database {
     pub fn new() -> Result<Self> {
         Ok(Self {
@@ -14,14 +42,22 @@ impl Database {
         })
     }
 
+    // A database that's gone once it's dropped, for tests
+    // to fill with just the rows they need.
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Ok(Self {
+            connection: Connection::open_in_memory()?,
+        })
+    }
+
     pub fn load<T: Model>(&self, id: i64) -> Result<T> {
+        self.create_table::<T>()?;
         let mut stmt = self
             .connection
             .prepare(format!("SELECT {} FROM {} WHERE id=?1", T::queries(), T::table()).as_str())?;
 
-        let mut loaded_model = stmt
-            .query_row(params![id], |row| Ok(T::build(&row)))?
-            .unwrap();
+        let mut loaded_model = stmt.query_row(params![id], T::build)?;
 
         if T::has_junctions() {
             for table in T::junct_tables().unwrap_or(vec![]) {
@@ -30,7 +66,7 @@ impl Database {
                 for junct_id in junction_ids {
                     self.load_junction(
                         &table,
-                        &T::junct_columns(&table).unwrap().1,
+                        &T::junct_references(&table).unwrap().1,
                         &T::junct_qeries(&table).unwrap(),
                         junct_id,
                         &mut loaded_model,
@@ -41,7 +77,9 @@ impl Database {
         Ok(loaded_model)
     }
 
-    pub fn save<T: Model>(&self, model: &T) -> Result<()> {
+    // Creates the model's table, along with any of its junction
+    // tables, if they don't already exist.
+    pub fn create_table<T: Model>(&self) -> Result<()> {
         self.connection.execute(
             format!(
                 "CREATE TABLE IF NOT EXISTS {} ({})",
@@ -52,6 +90,189 @@ impl Database {
             [],
         )?;
 
+        if T::has_junctions() {
+            for table in T::junct_tables().unwrap_or(vec![]) {
+                self.connection.execute(
+                    format!(
+                        "CREATE TABLE IF NOT EXISTS {} (
+                            {} INTEGER REFERENCES {}(id),
+                            {} INTEGER REFERENCES {}(id),
+                            PRIMARY KEY ({}, {})
+                        )",
+                        table,
+                        T::junct_columns(&table).unwrap().0,
+                        T::junct_references(&table).unwrap().0,
+                        T::junct_columns(&table).unwrap().1,
+                        T::junct_references(&table).unwrap().1,
+                        T::junct_columns(&table).unwrap().0,
+                        T::junct_columns(&table).unwrap().1
+                    )
+                    .as_str(),
+                    [],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    // Creates every table the app uses, after bringing those the
+    // database already has up to date. The catalog
+    // tables come first, since the character's junction tables
+    // reference them.
+    pub fn create_tables(&self) -> Result<()> {
+        self.migrate()?;
+        self.create_table::<Feature>()?;
+        self.create_table::<Feat>()?;
+        self.create_table::<Proficiency>()?;
+        self.create_table::<Language>()?;
+        self.create_table::<Item>()?;
+        self.create_table::<Spell>()?;
+        self.create_table::<Background>()?;
+        self.create_table::<Character>()?;
+        self.create_table::<InventoryEntry>()?;
+        self.create_expended_uses_table()?;
+        self.create_table::<Roll>()?;
+        self.create_search_index()?;
+        Ok(())
+    }
+
+    // Records which layout a new database's tables have. Nothing's
+    // changed since the first, so there's nothing else to do yet.
+    fn migrate(&self) -> Result<()> {
+        let version: i64 = self
+            .connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version < SCHEMA_VERSION {
+            self.connection
+                .execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION))?;
+        }
+        Ok(())
+    }
+
+    // The full-text index over the names and descriptions of the
+    // models Database::search finds, which save and delete keep up
+    // to date. The index is filled from what's already saved if
+    // it's empty, ie. in a database from before there was one.
+    fn create_search_index(&self) -> Result<()> {
+        self.create_search_table()?;
+        let indexed: i64 = self
+            .connection
+            .query_row("SELECT count(*) FROM search", [], |row| row.get(0))?;
+        if indexed == 0 {
+            self.index_all::<Spell>()?;
+            self.index_all::<Item>()?;
+            self.index_all::<Feature>()?;
+        }
+        Ok(())
+    }
+
+    fn create_search_table(&self) -> Result<()> {
+        self.connection.execute(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS search USING fts5(
+                kind UNINDEXED,
+                id UNINDEXED,
+                name,
+                description
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn index_all<T: Model>(&self) -> Result<()> {
+        if let Some(kind) = T::search_kind() {
+            self.connection.execute(
+                format!(
+                    "INSERT INTO search (kind, id, name, description) \
+                    SELECT ?1, id, name, description FROM {}",
+                    T::table()
+                )
+                .as_str(),
+                params![kind],
+            )?;
+        }
+        Ok(())
+    }
+
+    // Replaces whatever was indexed for the model with its current
+    // name and description.
+    fn index<T: Model>(&self, model: &T, id: i64) -> Result<()> {
+        if let (Some(kind), Some((name, description))) = (T::search_kind(), model.search_text()) {
+            self.unindex::<T>(id)?;
+            self.connection.execute(
+                "INSERT INTO search (kind, id, name, description) VALUES (?1, ?2, ?3, ?4)",
+                params![kind, id, name, description],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn unindex<T: Model>(&self, id: i64) -> Result<()> {
+        if let Some(kind) = T::search_kind() {
+            self.create_search_table()?;
+            self.connection.execute(
+                "DELETE FROM search WHERE kind=?1 AND id=?2",
+                params![kind, id],
+            )?;
+        }
+        Ok(())
+    }
+
+    // The spells, items and features with every word of the query
+    // in their name or description, best matches first. Words match
+    // the start of words, so "fir" finds "fireball", and a word in
+    // the name counts for more than one in the description. Only
+    // the kinds given are searched, or every kind if none are.
+    pub fn search(&self, query: &str, kinds: &[SearchKind]) -> Result<Vec<SearchHit>> {
+        // Each word is quoted so that anything typed is searched
+        // for as it is, rather than read as FTS5 query syntax.
+        let query = query
+            .split_whitespace()
+            .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let kinds = match kinds.is_empty() {
+            true => &SearchKind::ALL[..],
+            false => kinds,
+        };
+        let kinds = kinds
+            .iter()
+            .map(|kind| format!("'{}'", kind.name()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        self.create_search_table()?;
+        let mut stmt = self.connection.prepare(
+            format!(
+                "SELECT kind, id, snippet(search, 3, ?2, ?3, '...', 12) \
+                FROM search WHERE search MATCH ?1 AND kind IN ({}) \
+                ORDER BY bm25(search, 0.0, 0.0, 10.0, 1.0) LIMIT 50",
+                kinds
+            )
+            .as_str(),
+        )?;
+        let rows = stmt.query_map(
+            params![query, MATCH_START.to_string(), MATCH_END.to_string()],
+            |row| {
+                Ok(SearchHit {
+                    kind: row.get(0)?,
+                    id: row.get(1)?,
+                    snippet: row.get(2)?,
+                })
+            },
+        )?;
+        rows.into_iter().collect()
+    }
+
+    // Saves the model, returning its id. A model without an id
+    // is given a new one by SQLite, so callers saving a new
+    // model should hold on to the returned id.
+    pub fn save<T: Model>(&self, model: &T) -> Result<i64> {
+        self.create_table::<T>()?;
+
         let mut stmt = self.connection.prepare(
             format!(
                 "REPLACE INTO {} ({}) VALUES ({})",
@@ -61,13 +282,15 @@ impl Database {
             )
             .as_str(),
         )?;
-        stmt.execute(params_from_iter(model.parameters().into_iter()))?;
+        stmt.execute(params_from_iter(model.parameters()))?;
+        let id = self.connection.last_insert_rowid();
 
         if T::has_junctions() {
-            self.save_junctions(model)?;
+            self.save_junctions(model, id)?;
         }
+        self.index(model, id)?;
 
-        Ok(())
+        Ok(id)
     }
 
     pub fn delete<T: Model>(&self, id: i64) -> Result<()> {
@@ -76,28 +299,22 @@ impl Database {
             .prepare(format!("DELETE FROM {} WHERE id=?1", T::table()).as_str())?;
 
         stmt.execute(params![id])?;
+        self.unindex::<T>(id)?;
         Ok(())
     }
 
-    pub fn save_junctions<T: Model>(&self, model: &T) -> Result<()> {
+    pub fn save_junctions<T: Model>(&self, model: &T, id: i64) -> Result<()> {
         for table in T::junct_tables().unwrap_or(vec![]) {
+            // Junctions removed from the model since it was last
+            // saved are cleared out before the current ones are written.
             self.connection.execute(
                 format!(
-                    "CREATE TABLE IF NOT EXISTS {} (
-                        {} INTEGER REFERENCES {}(id),
-                        {} INTEGER REFERENCES {}(id),
-                        PRIMARY KEY ({}, {})
-                    )",
+                    "DELETE FROM {} WHERE {}=?1",
                     table,
-                    T::junct_columns(&table).unwrap().0,
-                    T::junct_references(&table).unwrap().0,
-                    T::junct_columns(&table).unwrap().1,
-                    T::junct_references(&table).unwrap().1,
-                    T::junct_columns(&table).unwrap().0,
-                    T::junct_columns(&table).unwrap().1
+                    T::junct_columns(&table).unwrap().0
                 )
                 .as_str(),
-                [],
+                [id],
             )?;
 
             for junct in model.junction_ids(&table).unwrap() {
@@ -109,7 +326,7 @@ impl Database {
                         T::junct_columns(&table).unwrap().1,
                     )
                     .as_str(),
-                    [model.id(), Some(junct)],
+                    [id, junct],
                 )?;
             }
         }
@@ -124,13 +341,13 @@ impl Database {
     ) -> Result<Vec<i64>> {
         let mut stmt = self.connection.prepare(
             format!(
-                "SELECT {}, {} FROM {} WHERE id=?1",
-                columns.0, columns.1, table,
+                "SELECT {}, {} FROM {} WHERE {}=?1",
+                columns.0, columns.1, table, columns.0,
             )
             .as_str(),
         )?;
 
-        let ids = stmt.query_map([id], |row| Ok(row.get(1)?))?;
+        let ids = stmt.query_map([id], |row| row.get(1))?;
         ids.into_iter().collect()
     }
 
@@ -145,34 +362,1044 @@ impl Database {
         let mut stmt = self
             .connection
             .prepare(format!("SELECT {} FROM {} WHERE id=?1", queries, table).as_str())?;
-        stmt.query_row([id], |row| Ok(model.build_junction(&junct_table, &row)?))?;
+        stmt.query_row([id], |row| model.build_junction(junct_table, row))?;
 
         Ok(())
     }
 
-    pub fn get_all_models<T: Model>(&self) -> Result<Vec<T>> {
-        let mut stmt = self
+    // The models the query matches, in its order and within its
+    // limit and offset. Junctions aren't loaded; see load.
+    pub fn query<T: Model>(&self, query: &Query<T>) -> Result<Vec<T>> {
+        self.create_table::<T>()?;
+        let mut stmt = self.connection.prepare(
+            format!(
+                "SELECT {} FROM {}{}",
+                T::queries(),
+                T::table(),
+                query.clauses()?
+            )
+            .as_str(),
+        )?;
+        let rows = stmt.query_map(params_from_iter(query.values()), T::build)?;
+        rows.into_iter().collect()
+    }
+
+    // How many models the query matches, ignoring its limit and
+    // offset, ie. to know how many pages of them there are.
+    pub fn count<T: Model>(&self, query: &Query<T>) -> Result<usize> {
+        self.create_table::<T>()?;
+        let count: i64 = self.connection.query_row(
+            format!("SELECT count(*) FROM {}{}", T::table(), query.conditions()?).as_str(),
+            params_from_iter(query.values()),
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    // The column added up over every model the query
+    // matches, ignoring its limit and offset.
+    pub fn sum<T: Model>(&self, query: &Query<T>, column: &str) -> Result<i64> {
+        self.create_table::<T>()?;
+        Query::<T>::check_column(column)?;
+        self.connection.query_row(
+            format!(
+                "SELECT coalesce(sum({}), 0) FROM {}{}",
+                column,
+                T::table(),
+                query.conditions()?
+            )
+            .as_str(),
+            params_from_iter(query.values()),
+            |row| row.get(0),
+        )
+    }
+
+    // Loads the character along with their inventory and feature
+    // uses, which aren't saved through junction tables.
+    pub fn load_character(&self, id: i64) -> Result<Character> {
+        let mut character = self.load::<Character>(id)?;
+        character.inventory = self.load_inventory(id)?;
+        character.expended_uses = self.load_expended_uses(id)?;
+        Ok(character)
+    }
+
+    // Saves the character along with their inventory and feature
+    // uses. A new character, and any new items, are given their ids
+    // here.
+    //
+    // Proficiencies and languages the character was given without
+    // ids, ie. during character creation, are matched by name to
+    // ones already in the database, or saved as new ones, so the
+    // junction tables can refer to them.
+    //
+    // It's all saved in one transaction, and the character only
+    // given their new ids once it's committed, so a save that fails
+    // part way leaves both the database and the character as they were.
+    pub fn save_character(&self, character: &mut Character) -> Result<i64> {
+        let transaction = self.connection.unchecked_transaction()?;
+        let mut saving = character.clone();
+        for proficiency in saving
+            .proficiencies
+            .iter_mut()
+            .filter(|proficiency| proficiency.id.is_none())
+        {
+            let name = proficiency.name.clone().unwrap_or_default();
+            proficiency.id = Some(self.find_or_save(&*proficiency, &name)?);
+        }
+        for language in saving
+            .languages
+            .iter_mut()
+            .filter(|language| language.id.is_none())
+        {
+            let name = language.name.clone();
+            language.id = Some(self.find_or_save(&*language, &name)?);
+        }
+
+        saving.modified = now();
+        let id = self.save(&saving)?;
+        saving.id = Some(id);
+        self.save_inventory(id, &mut saving.inventory)?;
+        self.save_expended_uses(id, &saving.expended_uses)?;
+        transaction.commit()?;
+        *character = saving;
+        Ok(id)
+    }
+
+    // Deletes the character along with everything saved only for
+    // them; their junctions, inventory, feature uses and rolls. The features,
+    // spells and items they referred to are left in the catalog.
+    pub fn delete_character(&self, id: i64) -> Result<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        for table in Character::junct_tables().unwrap_or_default() {
+            transaction.execute(
+                format!(
+                    "DELETE FROM {} WHERE {}=?1",
+                    table,
+                    Character::junct_columns(&table).unwrap().0
+                )
+                .as_str(),
+                [id],
+            )?;
+        }
+        for table in [
+            InventoryEntry::table(),
+            EXPENDED_USES.to_string(),
+            Roll::table(),
+        ] {
+            transaction.execute(
+                format!("DELETE FROM {} WHERE character=?1", table).as_str(),
+                [id],
+            )?;
+        }
+        self.delete::<Character>(id)?;
+        transaction.commit()
+    }
+
+    // Saves a copy of the character under a new id, returning it.
+    // Everything is copied other than their roll history, and the
+    // copy's name is marked so it can be told apart.
+    pub fn duplicate_character(&self, id: i64) -> Result<i64> {
+        let mut character = self.load_character(id)?;
+        character.id = None;
+        character.name = format!("{} (copy)", character.name);
+        self.save_character(&mut character)
+    }
+
+    pub fn rename_character(&self, id: i64, name: &str) -> Result<()> {
+        self.connection.execute(
+            format!(
+                "UPDATE {} SET name=?2, modified=?3 WHERE id=?1",
+                Character::table()
+            )
+            .as_str(),
+            params![id, name, now()],
+        )?;
+        Ok(())
+    }
+
+    // The id of the first row of the model's table with the
+    // given name, saving the model if there isn't one.
+    fn find_or_save<T: Model>(&self, model: &T, name: &str) -> Result<i64> {
+        self.create_table::<T>()?;
+        let existing = self
             .connection
-            .prepare(format!("SELECT {} FROM {}", T::queries(), T::table()).as_str())?;
+            .query_row(
+                format!("SELECT id FROM {} WHERE name=?1 LIMIT 1", T::table()).as_str(),
+                [name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match existing {
+            Some(id) => Ok(id),
+            None => self.save(model),
+        }
+    }
 
-        let rows = stmt.query_map([], |row| Ok(T::build(&row)?))?;
-        rows.into_iter().collect()
+    pub fn load_inventory(&self, character: i64) -> Result<Vec<InventoryEntry>> {
+        self.create_table::<InventoryEntry>()?;
+        let mut stmt = self.connection.prepare(
+            format!(
+                "SELECT {} FROM {} WHERE character=?1 ORDER BY key",
+                InventoryEntry::queries(),
+                InventoryEntry::table()
+            )
+            .as_str(),
+        )?;
+        let entries = stmt
+            .query_map([character], InventoryEntry::build)?
+            .collect::<Result<Vec<_>>>()?;
+
+        entries
+            .into_iter()
+            .map(|mut entry| {
+                if let Some(item) = entry.item.id {
+                    entry.item = self.load::<Item>(item)?;
+                }
+                Ok(entry)
+            })
+            .collect()
+    }
+
+    // The character's inventory rows are rewritten on every save,
+    // so stacks that have been removed or merged don't linger.
+    // Items that aren't in the catalog yet are added to it first.
+    pub fn save_inventory(&self, character: i64, inventory: &mut [InventoryEntry]) -> Result<()> {
+        self.create_table::<InventoryEntry>()?;
+        self.connection.execute(
+            format!("DELETE FROM {} WHERE character=?1", InventoryEntry::table()).as_str(),
+            [character],
+        )?;
+
+        for i in 0..inventory.len() {
+            // Stacks split from a new item share the one catalog row.
+            if inventory[i].item.id.is_none() {
+                let saved = inventory[..i]
+                    .iter()
+                    .find(|other| other.item.name == inventory[i].item.name)
+                    .and_then(|other| other.item.id);
+                inventory[i].item.id = match saved {
+                    Some(id) => Some(id),
+                    None => Some(self.save(&inventory[i].item)?),
+                };
+            }
+            let entry = &mut inventory[i];
+            entry.id = None;
+            entry.character = Some(character);
+            entry.id = Some(self.save(entry)?);
+        }
+        Ok(())
+    }
+
+    fn create_expended_uses_table(&self) -> Result<()> {
+        self.connection.execute(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {} (
+                    character INTEGER REFERENCES {}(id),
+                    feature TEXT NOT NULL,
+                    spent INTEGER NOT NULL,
+                    PRIMARY KEY (character, feature)
+                )",
+                EXPENDED_USES,
+                Character::table()
+            )
+            .as_str(),
+            [],
+        )?;
+        Ok(())
+    }
+
+    pub fn load_expended_uses(&self, character: i64) -> Result<HashMap<String, u8>> {
+        self.create_expended_uses_table()?;
+        let mut stmt = self.connection.prepare(
+            format!(
+                "SELECT feature, spent FROM {} WHERE character=?1",
+                EXPENDED_USES
+            )
+            .as_str(),
+        )?;
+        let expended_uses = stmt.query_map([character], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        expended_uses.collect()
+    }
+
+    // Rewritten on every save, so features whose
+    // uses have come back don't linger.
+    pub fn save_expended_uses(
+        &self,
+        character: i64,
+        expended_uses: &HashMap<String, u8>,
+    ) -> Result<()> {
+        self.create_expended_uses_table()?;
+        self.connection.execute(
+            format!("DELETE FROM {} WHERE character=?1", EXPENDED_USES).as_str(),
+            [character],
+        )?;
+        for (feature, spent) in expended_uses.iter().filter(|(_, spent)| **spent > 0) {
+            self.connection.execute(
+                format!(
+                    "INSERT INTO {} (character, feature, spent) VALUES (?1, ?2, ?3)",
+                    EXPENDED_USES
+                )
+                .as_str(),
+                params![character, feature, spent],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn list_all_characters(&self) -> Result<Vec<SavedCharacter>> {
+        self.list_characters(&Query::new())
+    }
+
+    // The characters the query matches, without loading
+    // anything more of them than the select screen shows.
+    pub fn list_characters(&self, query: &Query<Character>) -> Result<Vec<SavedCharacter>> {
+        let mut stmt = self.connection.prepare(
+            format!(
+                "SELECT id, name, class, race, level, modified FROM {}{}",
+                Character::table(),
+                query.clauses()?
+            )
+            .as_str(),
+        )?;
+        let characters = stmt.query_map(params_from_iter(query.values()), |row| {
+            let class: Option<String> = row.get(2)?;
+            let race: Option<String> = row.get(3)?;
+            Ok(SavedCharacter {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                race: race.unwrap_or_default(),
+                class: class.unwrap_or_default(),
+                level: row.get(4)?,
+                modified: row.get(5)?,
+            })
+        })?;
+        characters.into_iter().collect()
+    }
+
+    // What the search palette finds by name; the spells, items,
+    // features, feats, languages and characters whose names have the
+    // query's characters in them in order. Only the first of each
+    // kind by name are loaded, up to the limit, since the palette
+    // asks again as the query's narrowed down.
+    pub fn list_catalog(&self, name: &str, limit: usize) -> Result<Vec<CatalogEntry>> {
+        fn by_name<T: Model>(name: &str, limit: usize) -> Query<T> {
+            Query::new()
+                .filter("name", Filter::Subsequence(name.to_string()))
+                .order_by("name", Order::Ascending)
+                .limit(limit)
+        }
+        let mut catalog = Vec::new();
+        catalog.extend(
+            self.query(&by_name::<Spell>(name, limit))?
+                .into_iter()
+                .map(CatalogEntry::Spell),
+        );
+        catalog.extend(
+            self.query(&by_name::<Item>(name, limit))?
+                .into_iter()
+                .map(|item| CatalogEntry::Item(Box::new(item))),
+        );
+        catalog.extend(
+            self.query(&by_name::<Feature>(name, limit))?
+                .into_iter()
+                .map(CatalogEntry::Feature),
+        );
+        catalog.extend(
+            self.query(&by_name::<Feat>(name, limit))?
+                .into_iter()
+                .map(CatalogEntry::Feat),
+        );
+        catalog.extend(
+            self.query(&by_name::<Language>(name, limit))?
+                .into_iter()
+                .map(CatalogEntry::Language),
+        );
+        catalog.extend(
+            self.list_characters(&by_name(name, limit))?
+                .into_iter()
+                .map(CatalogEntry::Character),
+        );
+        Ok(catalog)
+    }
+
+    // The catalog entry for a model Database::search found.
+    pub fn catalog_entry(&self, hit: &SearchHit) -> Result<CatalogEntry> {
+        Ok(match hit.kind {
+            SearchKind::Spell => CatalogEntry::Spell(self.load(hit.id)?),
+            SearchKind::Item => CatalogEntry::Item(Box::new(self.load(hit.id)?)),
+            SearchKind::Feature => CatalogEntry::Feature(self.load(hit.id)?),
+        })
+    }
+
+    // Statistics over every roll the query matches,
+    // ignoring its limit and offset.
+    pub fn roll_statistics(&self, query: &Query<Roll>) -> Result<RollStatistics> {
+        let d20s = query.clone().filter("natural_d20", Filter::NotNull);
+        let naturals = |natural: i64| {
+            self.count(
+                &query
+                    .clone()
+                    .filter("natural_d20", Filter::Equals(natural.into())),
+            )
+        };
+        Ok(RollStatistics {
+            rolls: self.count(query)?,
+            total: self.sum(query, "total")?,
+            d20s: self.count(&d20s)?,
+            d20_total: self.sum(&d20s, "natural_d20")?,
+            natural_20s: naturals(20)?,
+            natural_1s: naturals(1)?,
+        })
+    }
+}
+
+// The current time in seconds since the Unix epoch, as
+// timestamps are saved.
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data::{
+            conditions::Condition,
+            equipment::EquipmentCategory,
+            items::{Weapon, WeaponCategory},
+            proficiency::ProficiencyClass,
+            rolls::RollKind,
+            spells::School,
+        },
+        dice::{DiceExpression, Roller},
+    };
+    use rusqlite::Error;
+    use std::str::FromStr;
+
+    fn database() -> Database {
+        let db = Database::open_in_memory().unwrap();
+        db.create_tables().unwrap();
+        db
+    }
+
+    fn spell(name: &str, level: u8) -> Spell {
+        Spell {
+            name: name.to_string(),
+            school: Some(School::Evocation),
+            level,
+            ..Spell::new()
+        }
+    }
+
+    fn weapon(name: &str, category: WeaponCategory) -> Item {
+        Item {
+            name: name.to_string(),
+            class: "Weapon".to_string(),
+            weapon: Some(Weapon {
+                category,
+                damage: DiceExpression::from_str("1d6").unwrap(),
+                properties: Vec::new(),
+                range: None,
+            }),
+            ..Item::new()
+        }
+    }
+
+    fn names(spells: Vec<Spell>) -> Vec<String> {
+        spells.into_iter().map(|spell| spell.name).collect()
+    }
+
+    fn spellbook(db: &Database) {
+        for (name, level) in [
+            ("Fireball", 3),
+            ("Magic Missile", 1),
+            ("Shield", 1),
+            ("Mage Hand", 0),
+            ("Counterspell", 3),
+            ("Misty Step", 2),
+        ] {
+            db.save(&spell(name, level)).unwrap();
+        }
+    }
+
+    #[test]
+    fn query_filters_orders_and_pages_rows() {
+        let db = database();
+        spellbook(&db);
+
+        let evocations = Query::<Spell>::new()
+            .filter("school", Filter::Equals("Evocation".to_string().into()))
+            .order_by("level", Order::Descending)
+            .order_by("name", Order::Ascending);
+        assert_eq!(
+            names(db.query(&evocations).unwrap()),
+            [
+                "Counterspell",
+                "Fireball",
+                "Misty Step",
+                "Magic Missile",
+                "Shield",
+                "Mage Hand"
+            ]
+        );
+        assert_eq!(
+            names(db.query(&evocations.clone().limit(2).offset(1)).unwrap()),
+            ["Fireball", "Misty Step"]
+        );
+        // An offset without a limit still skips rows.
+        assert_eq!(
+            names(db.query(&evocations.clone().offset(4)).unwrap()),
+            ["Shield", "Mage Hand"]
+        );
+        assert_eq!(db.count(&evocations.clone().limit(2)).unwrap(), 6);
+        assert_eq!(db.sum(&evocations, "level").unwrap(), 10);
+
+        let first = Query::<Spell>::new().filter("level", Filter::Equals(1.into()));
+        assert_eq!(db.count(&first).unwrap(), 2);
+    }
+
+    #[test]
+    fn query_filters_text() {
+        let db = database();
+        spellbook(&db);
+        let by_name = |filter| {
+            let query = Query::<Spell>::new()
+                .filter("name", filter)
+                .order_by("name", Order::Ascending);
+            names(db.query(&query).unwrap())
+        };
+
+        assert_eq!(
+            by_name(Filter::Subsequence("MIS".to_string())),
+            ["Magic Missile", "Misty Step"]
+        );
+        assert_eq!(
+            by_name(Filter::Subsequence("mg ms".to_string())),
+            ["Magic Missile"]
+        );
+        // LIKE's wildcards are only matched as they are.
+        assert_eq!(by_name(Filter::Subsequence("%".to_string())), [""; 0]);
+        assert_eq!(by_name(Filter::Subsequence("_".to_string())), [""; 0]);
+        assert_eq!(
+            by_name(Filter::OneOf(vec![
+                "shield".to_string(),
+                "Fireball".to_string(),
+                "Wish".to_string()
+            ])),
+            ["Fireball", "Shield"]
+        );
+        assert_eq!(by_name(Filter::OneOf(Vec::new())), [""; 0]);
+    }
+
+    #[test]
+    fn query_rejects_unknown_columns() {
+        let db = database();
+        spellbook(&db);
+
+        let filtered = Query::<Spell>::new().filter("lvl", Filter::Equals(1.into()));
+        assert!(matches!(
+            db.query(&filtered),
+            Err(Error::InvalidColumnName(column)) if column == "lvl"
+        ));
+        let ordered = Query::<Spell>::new().order_by("name; DROP TABLE spells", Order::Ascending);
+        assert!(matches!(
+            db.query(&ordered),
+            Err(Error::InvalidColumnName(_))
+        ));
+        assert!(matches!(
+            db.sum(&Query::<Spell>::new(), "nope"),
+            Err(Error::InvalidColumnName(_))
+        ));
+        assert_eq!(db.count(&Query::<Spell>::new()).unwrap(), 6);
     }
 
-    //    TODO: This method here will need to change, given the other db method and character struct
-    //    changes.
-    //    pub fn list_all_characters(&self) -> Result<Vec<SavedCharacter>> {
-    //        let mut stmt = self
-    //            .connection
-    //            .prepare("SELECT id, name, race, class FROM characters")?;
-    //        let characters = stmt.query_map([], |row| {
-    //            Ok(SavedCharacter {
-    //                id: row.get(0)?,
-    //                name: row.get(1)?,
-    //                race: row.get(2)?,
-    //                class: row.get(3)?,
-    //            })
-    //        })?;
-    //        characters.into_iter().collect()
-    //    }
+    #[test]
+    fn catalog_is_listed_by_name_a_page_at_a_time() {
+        let db = database();
+        spellbook(&db);
+        db.save(&weapon("Mace", WeaponCategory::SimpleMelee))
+            .unwrap();
+        db.save(&Feat {
+            name: "Mobile".to_string(),
+            ..Feat::default()
+        })
+        .unwrap();
+
+        let catalog = db.list_catalog("m", 2).unwrap();
+        let names = catalog.iter().map(|entry| entry.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["Mage Hand", "Magic Missile", "Mace", "Mobile"]);
+
+        let catalog = db.list_catalog("msl", 50).unwrap();
+        let names = catalog.iter().map(|entry| entry.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["Magic Missile"]);
+    }
+
+    #[test]
+    fn equipment_categories_query_the_catalog() {
+        let db = database();
+        for (name, category) in [
+            ("Club", WeaponCategory::SimpleMelee),
+            ("Shortbow", WeaponCategory::SimpleRanged),
+            ("Longsword", WeaponCategory::MartialMelee),
+            ("Longbow", WeaponCategory::MartialRanged),
+        ] {
+            db.save(&weapon(name, category)).unwrap();
+        }
+        db.save(&Item {
+            name: "Lute".to_string(),
+            class: "Musical Instrument".to_string(),
+            ..Item::new()
+        })
+        .unwrap();
+        let found = |category: EquipmentCategory| {
+            db.query(&category.query().order_by("name", Order::Ascending))
+                .unwrap()
+                .into_iter()
+                .map(|item| item.name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(found(EquipmentCategory::SimpleWeapon), ["Club", "Shortbow"]);
+        assert_eq!(found(EquipmentCategory::SimpleMeleeWeapon), ["Club"]);
+        assert_eq!(
+            found(EquipmentCategory::MartialWeapon),
+            ["Longbow", "Longsword"]
+        );
+        assert_eq!(found(EquipmentCategory::MartialMeleeWeapon), ["Longsword"]);
+        assert_eq!(
+            found(EquipmentCategory::Other("musical instrument".to_string())),
+            ["Lute"]
+        );
+    }
+
+    #[test]
+    fn characters_are_saved_under_the_id_they_were_given() {
+        let db = database();
+        let mut character = Character {
+            name: "Mira".to_string(),
+            level: 3,
+            max_hit_points: 24,
+            ..Character::new()
+        };
+        character.add_condition(Condition::Exhaustion(2), None);
+        character.add_condition(Condition::Poisoned, Some(10));
+        let id = db.save(&character).unwrap();
+
+        // Saving again with the id updates the same row.
+        character.id = Some(id);
+        character.name = "Mirabel".to_string();
+        assert_eq!(db.save(&character).unwrap(), id);
+
+        let loaded = db.load::<Character>(id).unwrap();
+        assert_eq!(loaded.name, "Mirabel");
+        assert_eq!(loaded.level, 3);
+        assert_eq!(loaded.max_hit_points, 24);
+        assert_eq!(loaded.conditions, character.conditions);
+        let saved = db.list_all_characters().unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!((saved[0].id, saved[0].name.as_str()), (Some(id), "Mirabel"));
+    }
+
+    #[test]
+    fn feature_uses_are_saved_whatever_the_features_are_called() {
+        let db = database();
+        let mut character = Character::new();
+        character
+            .expended_uses
+            .insert("Channel Divinity: Turn Undead".to_string(), 2);
+        character
+            .expended_uses
+            .insert("Bolts, Arrows, and Stones".to_string(), 1);
+        let id = db.save_character(&mut character).unwrap();
+        assert_eq!(character.id, Some(id));
+
+        let loaded = db.load_character(id).unwrap();
+        assert_eq!(loaded.expended_uses, character.expended_uses);
+
+        // Features whose uses have all come back aren't kept.
+        character.expended_uses.clear();
+        character.expended_uses.insert("Alert".to_string(), 0);
+        db.save_character(&mut character).unwrap();
+        let rows: i64 = db
+            .connection
+            .query_row(
+                format!("SELECT count(*) FROM {}", EXPENDED_USES).as_str(),
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(rows, 0);
+    }
+
+    #[test]
+    fn new_proficiencies_and_languages_are_matched_by_name() {
+        let db = database();
+        let elvish = db
+            .save(&Language {
+                name: "Elvish".to_string(),
+                ..Language::new()
+            })
+            .unwrap();
+        let mut character = Character::new();
+        character.languages.push(Language {
+            name: "Elvish".to_string(),
+            ..Language::new()
+        });
+        character.proficiencies.push(Proficiency {
+            name: Some("Stealth".to_string()),
+            class: Some(ProficiencyClass::Skill),
+            ..Proficiency::new()
+        });
+        let id = db.save_character(&mut character).unwrap();
+
+        // The language already in the catalog is used, rather
+        // than saving another with the same name.
+        assert_eq!(character.languages[0].id, Some(elvish));
+        assert_eq!(rows(&db, &Language::table()), 1);
+        let stealth = character.proficiencies[0].id.unwrap();
+        let loaded = db.load_character(id).unwrap();
+        assert_eq!(loaded.languages[0].name, "Elvish");
+        assert_eq!(loaded.proficiencies[0].id, Some(stealth));
+
+        // Saving again doesn't add them a second time.
+        db.save_character(&mut character).unwrap();
+        assert_eq!(rows(&db, &Proficiency::table()), 1);
+    }
+
+    #[test]
+    fn rolls_are_listed_newest_first() {
+        let db = database();
+        let id = db.save(&Character::new()).unwrap();
+        let result = Roller::seeded(3).roll(&"1d20+5".parse().unwrap());
+        let mut roll = Roll::from_result(Some(id), RollKind::Check, "Stealth".to_string(), &result);
+        assert!((now() - roll.timestamp).abs() <= 1);
+
+        // Rolls made in the same second are told apart by their ids.
+        for (timestamp, kind) in [
+            (100, RollKind::Check),
+            (200, RollKind::Save),
+            (200, RollKind::Check),
+        ] {
+            roll.timestamp = timestamp;
+            roll.kind = Some(kind);
+            db.save(&roll).unwrap();
+        }
+        // Ordered as the roll history pages through them.
+        let newest_first = |id: i64| {
+            Query::<Roll>::new()
+                .filter("character", Filter::Equals(id.into()))
+                .order_by("timestamp", Order::Descending)
+                .order_by("id", Order::Descending)
+        };
+        let rolls = db.query(&newest_first(id)).unwrap();
+        let order: Vec<_> = rolls
+            .iter()
+            .map(|roll| (roll.timestamp, roll.kind.unwrap()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                (200, RollKind::Check),
+                (200, RollKind::Save),
+                (100, RollKind::Check)
+            ]
+        );
+        assert_eq!(rolls[0].label, "Stealth");
+        assert_eq!(rolls[0].expression, "1d20+5");
+
+        let saves =
+            newest_first(id).filter("kind", Filter::Equals(RollKind::Save.to_string().into()));
+        assert_eq!(db.query(&saves).unwrap().len(), 1);
+        assert!(db.query(&newest_first(id + 1)).unwrap().is_empty());
+    }
+
+    fn rows(db: &Database, table: &str) -> i64 {
+        db.connection
+            .query_row(
+                format!("SELECT count(*) FROM {}", table).as_str(),
+                [],
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn inventories_are_saved_apart_from_the_catalog() {
+        let db = database();
+        let mut character = Character::new();
+        let rope = Item {
+            name: "Rope".to_string(),
+            weight: 10,
+            ..Item::new()
+        };
+        let ropes = character.add_item(rope.clone(), 3);
+        character.split_stack(ropes, 1).unwrap();
+        character.entry_mut(ropes).unwrap().notes = "Knotted".to_string();
+        let id = db.save_character(&mut character).unwrap();
+
+        // Both stacks of the new item share the one catalog row.
+        assert_eq!(rows(&db, &Item::table()), 1);
+        assert!(character
+            .inventory
+            .iter()
+            .all(|entry| entry.item.id.is_some()));
+        let loaded = db.load_character(id).unwrap();
+        let stacks: Vec<_> = loaded
+            .inventory
+            .iter()
+            .map(|entry| {
+                (
+                    entry.key,
+                    entry.item.name.as_str(),
+                    entry.quantity,
+                    entry.notes.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(stacks, vec![(0, "Rope", 2, "Knotted"), (1, "Rope", 1, "")]);
+
+        // A second character holding rope has their own stack of it.
+        let mut other = Character::new();
+        other.add_item(loaded.inventory[0].item.clone(), 5);
+        let other_id = db.save_character(&mut other).unwrap();
+        assert_eq!(rows(&db, &Item::table()), 1);
+        assert_eq!(
+            db.load_character(other_id).unwrap().inventory[0].quantity,
+            5
+        );
+        assert_eq!(db.load_character(id).unwrap().inventory.len(), 2);
+    }
+
+    #[test]
+    fn failed_saves_leave_everything_as_it_was() {
+        let db = database();
+        let mut character = Character {
+            name: "Mira".to_string(),
+            ..Character::new()
+        };
+        db.save_character(&mut character).unwrap();
+
+        // A proficiency that isn't in the catalog fails the save at
+        // its junction, after the character and before their inventory.
+        let mut changed = character.clone();
+        changed.name = "Mirabel".to_string();
+        changed.add_item(
+            Item {
+                name: "Lantern".to_string(),
+                ..Item::new()
+            },
+            1,
+        );
+        changed.proficiencies.push(Proficiency {
+            id: Some(99),
+            ..Proficiency::default()
+        });
+        assert!(db.save_character(&mut changed).is_err());
+        assert_eq!(changed.inventory[0].item.id, None);
+        let saved = db.load_character(character.id.unwrap()).unwrap();
+        assert_eq!(saved.name, "Mira");
+        assert!(saved.inventory.is_empty());
+        assert_eq!(rows(&db, &Item::table()), 0);
+
+        // A new character isn't saved at all.
+        changed.id = None;
+        assert!(db.save_character(&mut changed).is_err());
+        assert_eq!(changed.id, None);
+        assert_eq!(db.list_all_characters().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn new_databases_start_at_the_latest_version() {
+        let db = database();
+        let version = |db: &Database| -> i64 {
+            db.connection
+                .query_row("PRAGMA user_version", [], |row| row.get(0))
+                .unwrap()
+        };
+        assert_eq!(version(&db), SCHEMA_VERSION);
+        db.migrate().unwrap();
+        assert_eq!(version(&db), SCHEMA_VERSION);
+    }
+
+    // A character with a feat, a rope, a spent use of a
+    // feature and a roll, saved.
+    fn adventurer(db: &Database) -> Character {
+        let mut character = Character::new();
+        character.name = "Mira".to_string();
+        let feat = Feat {
+            name: "Alert".to_string(),
+            ..Feat::default()
+        };
+        character.feats.push(Feat {
+            id: Some(db.save(&feat).unwrap()),
+            ..feat
+        });
+        let rope = Item {
+            name: "Rope".to_string(),
+            ..Item::new()
+        };
+        character.add_item(rope, 1);
+        character.expended_uses.insert("Second Wind".to_string(), 1);
+        let id = db.save_character(&mut character).unwrap();
+        db.save(&Roll {
+            character: Some(id),
+            kind: Some(RollKind::Check),
+            label: "Initiative".to_string(),
+            ..Roll::default()
+        })
+        .unwrap();
+        character
+    }
+
+    // How many rows of the table refer to the character.
+    fn character_rows(db: &Database, table: &str, column: &str, id: i64) -> i64 {
+        db.connection
+            .query_row(
+                format!("SELECT count(*) FROM {} WHERE {}=?1", table, column).as_str(),
+                [id],
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn duplicated_characters_have_their_own_inventory() {
+        let db = database();
+        let original = adventurer(&db);
+        let id = original.id.unwrap();
+        let copy = db.duplicate_character(id).unwrap();
+        assert_ne!(copy, id);
+
+        let mut duplicate = db.load_character(copy).unwrap();
+        assert_eq!(duplicate.name, "Mira (copy)");
+        assert_eq!(duplicate.feats.len(), 1);
+        assert_eq!(duplicate.inventory.len(), 1);
+        assert_ne!(duplicate.inventory[0].id, original.inventory[0].id);
+        // Both stacks are of the one catalog item.
+        assert_eq!(
+            duplicate.inventory[0].item.id,
+            original.inventory[0].item.id
+        );
+        // Rolls aren't copied.
+        assert_eq!(character_rows(&db, "rolls", "character", copy), 0);
+
+        duplicate.inventory[0].quantity = 5;
+        db.save_character(&mut duplicate).unwrap();
+        assert_eq!(db.load_character(id).unwrap().inventory[0].quantity, 1);
+        assert_eq!(db.load_character(copy).unwrap().inventory[0].quantity, 5);
+    }
+
+    #[test]
+    fn deleted_characters_leave_nothing_behind() {
+        let db = database();
+        let id = adventurer(&db).id.unwrap();
+        let other = adventurer(&db).id.unwrap();
+        for (table, column) in [
+            ("inventory", "character"),
+            (EXPENDED_USES, "character"),
+            ("rolls", "character"),
+            ("character_feats", "character"),
+        ] {
+            assert_eq!(character_rows(&db, table, column, id), 1, "{}", table);
+        }
+
+        db.delete_character(id).unwrap();
+        for table in Character::junct_tables().unwrap() {
+            let column = Character::junct_columns(&table).unwrap().0;
+            assert_eq!(character_rows(&db, &table, &column, id), 0, "{}", table);
+        }
+        for table in [
+            InventoryEntry::table(),
+            EXPENDED_USES.to_string(),
+            Roll::table(),
+        ] {
+            assert_eq!(character_rows(&db, &table, "character", id), 0, "{}", table);
+        }
+        assert!(db.load_character(id).is_err());
+        // The catalog, and everyone else's rows, are left alone.
+        assert_eq!(db.load_character(other).unwrap().inventory.len(), 1);
+        assert_eq!(character_rows(&db, "rolls", "character", other), 1);
+        assert_eq!(rows(&db, &Feat::table()), 2);
+        assert_eq!(rows(&db, &Item::table()), 2);
+    }
+
+    #[test]
+    fn search_index_follows_updates_and_deletes() {
+        let db = database();
+        let mut fireball = Spell {
+            description: "A bright streak flashes to a point you choose".to_string(),
+            ..spell("Fireball", 3)
+        };
+        let id = db.save(&fireball).unwrap();
+        let found = |query: &str| {
+            db.search(query, &[])
+                .unwrap()
+                .into_iter()
+                .map(|hit| hit.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(found("streak"), [id]);
+
+        // Saving it again replaces what was indexed for it.
+        fireball.id = Some(id);
+        fireball.description = "A blossom of flame".to_string();
+        db.save(&fireball).unwrap();
+        assert_eq!(found("streak"), [0; 0]);
+        assert_eq!(found("flame"), [id]);
+        assert_eq!(found("fireball"), [id]);
+
+        db.delete::<Spell>(id).unwrap();
+        assert_eq!(found("flame"), [0; 0]);
+        assert_eq!(found("fireball"), [0; 0]);
+    }
+
+    #[test]
+    fn search_takes_punctuation_and_quotes_as_typed() {
+        let db = database();
+        let id = db
+            .save(&Spell {
+                description: "Each dart hits a creature of your choice".to_string(),
+                ..spell("Magic Missile", 1)
+            })
+            .unwrap();
+
+        for query in [
+            "\"",
+            "\"\"",
+            "'",
+            "*",
+            "-",
+            "(",
+            ")",
+            ":",
+            "^",
+            "+",
+            "AND",
+            "OR",
+            "NOT",
+            "NEAR(",
+            "name:dart",
+            "dart\"",
+            "\"dart",
+            "d*rt",
+            "{name}",
+            "a -b",
+        ] {
+            assert!(db.search(query, &[]).is_ok(), "{} was an error", query);
+        }
+        let found = |query: &str| db.search(query, &[]).unwrap().len();
+        assert_eq!(found("\"dart\""), 1);
+        assert_eq!(found("dart,"), 1);
+        assert_eq!(found("Magic-Missile"), 1);
+        assert_eq!(found("your choice."), 1);
+        assert_eq!(found("(creature)"), 1);
+        // Operators are only searched for as words.
+        assert_eq!(found("dart OR fireball"), 0);
+        assert_eq!(db.search("dart", &[SearchKind::Item]).unwrap().len(), 0);
+        assert_eq!(db.search("dart", &[SearchKind::Spell]).unwrap()[0].id, id);
+        assert_eq!(found("   "), 0);
+    }
 }
@@ -0,0 +1,327 @@
+use crate::{
+    data::{
+        currency::{Denomination, Price},
+        items::{Item, WeaponCategory},
+        query::{Filter, Query},
+    },
+    dice::{DiceExpression, Roller},
+};
+use rusqlite::{
+    types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+    Result,
+};
+use std::{fmt, str::FromStr};
+
+// Starting equipment is written out the way the PHB lists it, with
+// each choice group separated by "; ", the options of a group by
+// " or " and the items of an option by " and ":
+//
+//   Rapier or Shortsword; Shortbow and 20 Arrows; any simple weapon
+//
+// A group with a single option is equipment the character always gets.
+
+// Kinds of item a choice like "any simple weapon" can be filled with.
+// Other is matched against the item's class, ie. "any musical instrument".
+#[derive(Debug, Clone, PartialEq)]
+pub enum EquipmentCategory {
+    SimpleWeapon,
+    SimpleMeleeWeapon,
+    MartialWeapon,
+    MartialMeleeWeapon,
+    Other(String),
+}
+
+impl EquipmentCategory {
+    // The catalog items of the category.
+    pub fn query(&self) -> Query<Item> {
+        let weapons = |categories: &[WeaponCategory]| {
+            Filter::OneOf(categories.iter().map(|c| c.to_string()).collect())
+        };
+        match self {
+            EquipmentCategory::SimpleWeapon => Query::new().filter(
+                "weapon_category",
+                weapons(&[WeaponCategory::SimpleMelee, WeaponCategory::SimpleRanged]),
+            ),
+            EquipmentCategory::SimpleMeleeWeapon => {
+                Query::new().filter("weapon_category", weapons(&[WeaponCategory::SimpleMelee]))
+            }
+            EquipmentCategory::MartialWeapon => Query::new().filter(
+                "weapon_category",
+                weapons(&[WeaponCategory::MartialMelee, WeaponCategory::MartialRanged]),
+            ),
+            EquipmentCategory::MartialMeleeWeapon => {
+                Query::new().filter("weapon_category", weapons(&[WeaponCategory::MartialMelee]))
+            }
+            EquipmentCategory::Other(class) => {
+                Query::new().filter("class", Filter::OneOf(vec![class.clone()]))
+            }
+        }
+    }
+}
+
+impl FromStr for EquipmentCategory {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "simple weapon" => Ok(EquipmentCategory::SimpleWeapon),
+            "simple melee weapon" => Ok(EquipmentCategory::SimpleMeleeWeapon),
+            "martial weapon" => Ok(EquipmentCategory::MartialWeapon),
+            "martial melee weapon" => Ok(EquipmentCategory::MartialMeleeWeapon),
+            "" => Err(()),
+            other => Ok(EquipmentCategory::Other(other.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for EquipmentCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EquipmentCategory::SimpleWeapon => write!(f, "simple weapon"),
+            EquipmentCategory::SimpleMeleeWeapon => write!(f, "simple melee weapon"),
+            EquipmentCategory::MartialWeapon => write!(f, "martial weapon"),
+            EquipmentCategory::MartialMeleeWeapon => write!(f, "martial melee weapon"),
+            EquipmentCategory::Other(class) => write!(f, "{}", class),
+        }
+    }
+}
+
+// A single item in an option, either a named item from the
+// catalog or any item of a category, with how many are given.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EquipmentItem {
+    Named(String, u16),
+    Any(EquipmentCategory, u16),
+}
+
+impl FromStr for EquipmentItem {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+        let (quantity, rest) = match input.split_once(' ') {
+            Some((number, rest)) if number.chars().all(|c| c.is_ascii_digit()) => {
+                (number.parse().map_err(|_| ())?, rest.trim())
+            }
+            _ => (1, input),
+        };
+        match rest.strip_prefix("any ") {
+            Some(category) => Ok(EquipmentItem::Any(
+                EquipmentCategory::from_str(category.trim())?,
+                quantity,
+            )),
+            None if rest.is_empty() => Err(()),
+            None => Ok(EquipmentItem::Named(rest.to_string(), quantity)),
+        }
+    }
+}
+
+impl fmt::Display for EquipmentItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EquipmentItem::Named(name, 1) => write!(f, "{}", name),
+            EquipmentItem::Named(name, quantity) => write!(f, "{} {}", quantity, name),
+            EquipmentItem::Any(category, 1) => write!(f, "any {}", category),
+            EquipmentItem::Any(category, quantity) => write!(f, "{} any {}", quantity, category),
+        }
+    }
+}
+
+// One of the options of a choice group, ie. "Shortbow and 20 Arrows".
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquipmentOption {
+    pub items: Vec<EquipmentItem>,
+}
+
+impl FromStr for EquipmentOption {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Ok(EquipmentOption {
+            items: input
+                .split(" and ")
+                .map(EquipmentItem::from_str)
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+}
+
+impl fmt::Display for EquipmentOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let items = self
+            .items
+            .iter()
+            .map(|item| item.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "{}", items.join(" and "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChoiceGroup {
+    pub options: Vec<EquipmentOption>,
+}
+
+impl FromStr for ChoiceGroup {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Ok(ChoiceGroup {
+            options: input
+                .split(" or ")
+                .map(EquipmentOption::from_str)
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+}
+
+impl fmt::Display for ChoiceGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = self
+            .options
+            .iter()
+            .map(|option| option.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "{}", options.join(" or "))
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct StartingEquipment {
+    pub groups: Vec<ChoiceGroup>,
+}
+
+impl FromSql for StartingEquipment {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<StartingEquipment> {
+        Ok(StartingEquipment::from_str(value.as_str()?).unwrap_or_default())
+    }
+}
+
+impl ToSql for StartingEquipment {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromStr for StartingEquipment {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Ok(StartingEquipment {
+            groups: input
+                .split("; ")
+                .filter(|group| !group.trim().is_empty())
+                .map(ChoiceGroup::from_str)
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+}
+
+impl fmt::Display for StartingEquipment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let groups = self
+            .groups
+            .iter()
+            .map(|group| group.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "{}", groups.join("; "))
+    }
+}
+
+// Gold a character can start with instead of their class and
+// background equipment, ie. "5d4x10 gp"; see PHB p.143.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StartingGold {
+    pub dice: DiceExpression,
+    pub multiplier: u64,
+}
+
+impl StartingGold {
+    pub fn roll(&self, roller: &mut Roller) -> Price {
+        Price {
+            amount: roller.roll(&self.dice).total.max(0) as u64 * self.multiplier,
+            denomination: Denomination::Gold,
+        }
+    }
+
+    // The PHB's starting gold for each class, for classes
+    // that don't set their own.
+    pub fn for_class(class: &str) -> StartingGold {
+        let (dice, multiplier) = match class.to_lowercase().as_str() {
+            "barbarian" | "druid" => ("2d4", 10),
+            "bard" | "cleric" | "fighter" | "paladin" | "ranger" => ("5d4", 10),
+            "monk" => ("5d4", 1),
+            "rogue" | "warlock" | "wizard" => ("4d4", 10),
+            "sorcerer" => ("3d4", 10),
+            _ => ("4d4", 10),
+        };
+        StartingGold {
+            dice: DiceExpression::from_str(dice).unwrap(),
+            multiplier,
+        }
+    }
+}
+
+impl FromStr for StartingGold {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let dice = input.trim().strip_suffix("gp").ok_or(())?.trim();
+        let (dice, multiplier) = match dice.split_once('x') {
+            Some((dice, multiplier)) => (dice, multiplier.trim().parse().map_err(|_| ())?),
+            None => (dice, 1),
+        };
+        Ok(StartingGold {
+            dice: DiceExpression::from_str(dice).map_err(|_| ())?,
+            multiplier,
+        })
+    }
+}
+
+impl fmt::Display for StartingGold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.multiplier {
+            1 => write!(f, "{} gp", self.dice),
+            multiplier => write!(f, "{}x{} gp", self.dice, multiplier),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starting_equipment_round_trips() {
+        let text =
+            "Rapier or Shortsword; Shortbow and 20 Arrows or any simple weapon; Thieves' Tools";
+        let equipment = StartingEquipment::from_str(text).unwrap();
+        assert_eq!(equipment.groups.len(), 3);
+        assert_eq!(
+            equipment.groups[1].options[0].items,
+            vec![
+                EquipmentItem::Named("Shortbow".to_string(), 1),
+                EquipmentItem::Named("Arrows".to_string(), 20),
+            ]
+        );
+        assert_eq!(
+            equipment.groups[1].options[1].items,
+            vec![EquipmentItem::Any(EquipmentCategory::SimpleWeapon, 1)]
+        );
+        assert_eq!(equipment.to_string(), text);
+        assert_eq!(
+            StartingEquipment::from_str(""),
+            Ok(StartingEquipment::default())
+        );
+    }
+
+    #[test]
+    fn starting_gold_rolls_within_the_class_range() {
+        let gold = StartingGold::for_class("Fighter");
+        assert_eq!(gold.to_string(), "5d4x10 gp");
+        assert_eq!(StartingGold::from_str("5d4x10 gp"), Ok(gold.clone()));
+
+        let mut roller = Roller::seeded(3);
+        for _ in 0..20 {
+            let price = gold.roll(&mut roller);
+            assert_eq!(price.denomination, Denomination::Gold);
+            assert!((50..=200).contains(&price.amount));
+            assert_eq!(price.amount % 10, 0);
+        }
+        assert_eq!(StartingGold::for_class("Monk").multiplier, 1);
+    }
+}
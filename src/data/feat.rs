@@ -0,0 +1,313 @@
+use crate::data::{
+    character::{Character, Model},
+    feature::{Feature, FeatureClass, FeatureResource, Recharge, ResourceMax},
+    proficiency::{Proficiency, ProficiencyClass},
+    stats::Stats,
+};
+use rusqlite::{
+    types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+    Result, Row,
+};
+use std::{fmt, str::FromStr};
+
+// Something a character needs before they can take a feat; see PHB p.165.
+// Spellcasting is "the ability to cast at least one spell".
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeatPrerequisite {
+    Ability(Stats, u8),
+    Race(String),
+    Proficiency(String),
+    Spellcasting,
+}
+
+impl FeatPrerequisite {
+    pub fn met_by(&self, character: &Character) -> bool {
+        match self {
+            FeatPrerequisite::Ability(stat, minimum) => character.ability_score(stat) >= *minimum,
+            FeatPrerequisite::Race(race) => character.race.eq_ignore_ascii_case(race),
+            FeatPrerequisite::Proficiency(name) => {
+                character.all_proficiencies().iter().any(|proficiency| {
+                    proficiency
+                        .name
+                        .as_deref()
+                        .map(|proficiency| proficiency.eq_ignore_ascii_case(name))
+                        .unwrap_or(false)
+                })
+            }
+            FeatPrerequisite::Spellcasting => !character.spells.is_empty(),
+        }
+    }
+
+    // Prerequisites of the same kind are alternatives to each
+    // other, ie. "INT 13 or WIS 13" for Ritual Caster.
+    fn same_kind(&self, other: &FeatPrerequisite) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+impl FromStr for FeatPrerequisite {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if input == "Spellcasting" {
+            return Ok(FeatPrerequisite::Spellcasting);
+        }
+        if let Some(race) = input.strip_prefix("Race: ") {
+            return Ok(FeatPrerequisite::Race(race.to_string()));
+        }
+        if let Some(name) = input.strip_prefix("Proficiency: ") {
+            return Ok(FeatPrerequisite::Proficiency(name.to_string()));
+        }
+        let (stat, minimum) = input.split_once(' ').ok_or(())?;
+        Ok(FeatPrerequisite::Ability(
+            Stats::from_str(stat)?,
+            minimum.parse().map_err(|_| ())?,
+        ))
+    }
+}
+
+impl fmt::Display for FeatPrerequisite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeatPrerequisite::Ability(stat, minimum) => write!(f, "{} {}", stat, minimum),
+            FeatPrerequisite::Race(race) => write!(f, "Race: {}", race),
+            FeatPrerequisite::Proficiency(name) => write!(f, "Proficiency: {}", name),
+            FeatPrerequisite::Spellcasting => write!(f, "Spellcasting"),
+        }
+    }
+}
+
+// What taking a feat does for the character. Ability increases
+// can't raise a score above 20, and a resource is tracked like
+// any other limited-use feature, under the feat's name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeatEffect {
+    AbilityIncrease(Stats, u8),
+    Proficiency(ProficiencyClass, String),
+    Resource(FeatureResource),
+}
+
+impl FromStr for FeatEffect {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if let Some(resource) = input.strip_prefix("Uses: ") {
+            let (max, recharge) = resource.split_once('/').ok_or(())?;
+            return Ok(FeatEffect::Resource(FeatureResource {
+                max: ResourceMax::from_str(max)?,
+                recharge: Recharge::from_str(recharge)?,
+            }));
+        }
+        if let Some((class, name)) = input.split_once(" proficiency: ") {
+            return Ok(FeatEffect::Proficiency(
+                ProficiencyClass::from_str(class)?,
+                name.to_string(),
+            ));
+        }
+        let (stat, increase) = input.split_once(" +").ok_or(())?;
+        Ok(FeatEffect::AbilityIncrease(
+            Stats::from_str(stat)?,
+            increase.parse().map_err(|_| ())?,
+        ))
+    }
+}
+
+impl fmt::Display for FeatEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeatEffect::AbilityIncrease(stat, increase) => write!(f, "{} +{}", stat, increase),
+            FeatEffect::Proficiency(class, name) => write!(f, "{} proficiency: {}", class, name),
+            FeatEffect::Resource(resource) => {
+                write!(f, "Uses: {}/{}", resource.max, resource.recharge)
+            }
+        }
+    }
+}
+
+// Prerequisites and effects are each stored as a single "; "
+// separated column on the feat; resources can contain commas.
+pub struct FeatPrerequisites(pub Vec<FeatPrerequisite>);
+
+impl FromSql for FeatPrerequisites {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<FeatPrerequisites> {
+        Ok(FeatPrerequisites(
+            value
+                .as_str()?
+                .split("; ")
+                .filter(|prerequisite| !prerequisite.is_empty())
+                .map(|prerequisite| FeatPrerequisite::from_str(prerequisite).unwrap())
+                .collect(),
+        ))
+    }
+}
+
+impl ToSql for FeatPrerequisites {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(
+            self.0
+                .iter()
+                .map(|prerequisite| prerequisite.to_string())
+                .collect::<Vec<_>>()
+                .join("; "),
+        ))
+    }
+}
+
+pub struct FeatEffects(pub Vec<FeatEffect>);
+
+impl FromSql for FeatEffects {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<FeatEffects> {
+        Ok(FeatEffects(
+            value
+                .as_str()?
+                .split("; ")
+                .filter(|effect| !effect.is_empty())
+                .map(|effect| FeatEffect::from_str(effect).unwrap())
+                .collect(),
+        ))
+    }
+}
+
+impl ToSql for FeatEffects {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(
+            self.0
+                .iter()
+                .map(|effect| effect.to_string())
+                .collect::<Vec<_>>()
+                .join("; "),
+        ))
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct Feat {
+    pub id: Option<i64>,
+    pub name: String,
+    pub description: String,
+    pub prerequisites: Vec<FeatPrerequisite>,
+    pub effects: Vec<FeatEffect>,
+}
+
+impl Feat {
+    // Prerequisites the character doesn't meet, grouped with their
+    // alternatives. A group is met when any one of them is.
+    pub fn unmet_prerequisites(&self, character: &Character) -> Vec<Vec<FeatPrerequisite>> {
+        let mut groups: Vec<Vec<FeatPrerequisite>> = Vec::new();
+        for prerequisite in &self.prerequisites {
+            match groups
+                .iter_mut()
+                .find(|group| group[0].same_kind(prerequisite))
+            {
+                Some(group) => group.push(prerequisite.clone()),
+                None => groups.push(vec![prerequisite.clone()]),
+            }
+        }
+        groups
+            .into_iter()
+            .filter(|group| {
+                !group
+                    .iter()
+                    .any(|prerequisite| prerequisite.met_by(character))
+            })
+            .collect()
+    }
+
+    pub fn ability_increase(&self, stat: &Stats) -> u8 {
+        self.effects
+            .iter()
+            .filter_map(|effect| match effect {
+                FeatEffect::AbilityIncrease(increased, increase) if increased == stat => {
+                    Some(*increase)
+                }
+                _ => None,
+            })
+            .sum()
+    }
+
+    pub fn proficiencies(&self) -> Vec<Proficiency> {
+        self.effects
+            .iter()
+            .filter_map(|effect| match effect {
+                FeatEffect::Proficiency(class, name) => Some(Proficiency {
+                    id: None,
+                    name: Some(name.clone()),
+                    class: Some(class.clone()),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // The feat as a feature, so its uses are tracked
+    // alongside the character's other features.
+    pub fn feature(&self) -> Feature {
+        Feature {
+            id: None,
+            name: self.name.clone(),
+            class: Some(FeatureClass::Feat),
+            description: self.description.clone(),
+            resource: self.effects.iter().find_map(|effect| match effect {
+                FeatEffect::Resource(resource) => Some(resource.clone()),
+                _ => None,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Feat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "ID: {:#?}, Name: {}, Prerequisites: {:#?}, Effects: {:#?}, Description: {}",
+            self.id, self.name, self.prerequisites, self.effects, self.description
+        )
+    }
+}
+
+impl Model for Feat {
+    fn parameters(&self) -> Vec<Box<dyn ToSql>> {
+        vec![
+            Box::new(self.id),
+            Box::new(self.name.clone()),
+            Box::new(self.description.clone()),
+            Box::new(FeatPrerequisites(self.prerequisites.clone())),
+            Box::new(FeatEffects(self.effects.clone())),
+        ]
+    }
+
+    fn build(row: &Row) -> Result<Feat> {
+        let prerequisites: FeatPrerequisites = row.get(3)?;
+        let effects: FeatEffects = row.get(4)?;
+        Ok(Feat {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            description: row.get(2)?,
+            prerequisites: prerequisites.0,
+            effects: effects.0,
+        })
+    }
+
+    fn table() -> String {
+        "feats".to_string()
+    }
+
+    fn columns() -> String {
+        "id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        description TEXT NOT NULL,
+        prerequisites TEXT NOT NULL,
+        effects TEXT NOT NULL"
+            .to_string()
+    }
+
+    fn queries() -> String {
+        "id, name, description, prerequisites, effects".to_string()
+    }
+
+    fn values() -> String {
+        "?1, ?2, ?3, ?4, ?5".to_string()
+    }
+
+    fn id(&self) -> Option<i64> {
+        self.id
+    }
+}
@@ -1,4 +1,8 @@
-use crate::data::character::Model;
+use crate::data::{
+    catalog::SearchKind,
+    character::{Character, Model},
+    stats::Stats,
+};
 use ::std::{fmt, str::FromStr};
 use rusqlite::{
     types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
@@ -53,18 +57,168 @@ impl fmt::Display for FeatureClass {
     }
 }
 
+// The maximum number of uses a limited-use feature has.
+// Fixed is a flat number of uses, Level is the character's
+// level times the given multiplier (Lay on Hands is Level(5)),
+// Scaling holds (level, uses) thresholds for features like
+// Rage or Channel Divinity, and Modifier is the ability modifier
+// of the given stat, to a minimum of 1 (Bardic Inspiration).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResourceMax {
+    Fixed(u8),
+    Level(u8),
+    Scaling(Vec<(u8, u8)>),
+    Modifier(Stats),
+}
+
+impl ResourceMax {
+    pub fn evaluate(&self, character: &Character) -> u8 {
+        match self {
+            ResourceMax::Fixed(uses) => *uses,
+            ResourceMax::Level(multiplier) => character.level.saturating_mul(*multiplier),
+            ResourceMax::Scaling(thresholds) => thresholds
+                .iter()
+                .rev()
+                .find(|(level, _)| *level <= character.level)
+                .map(|(_, uses)| *uses)
+                .unwrap_or(0),
+            ResourceMax::Modifier(stat) => character.modifier(stat).max(1) as u8,
+        }
+    }
+}
+
+impl fmt::Display for ResourceMax {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceMax::Fixed(uses) => write!(f, "Fixed({})", uses),
+            ResourceMax::Level(multiplier) => write!(f, "Level({})", multiplier),
+            ResourceMax::Scaling(thresholds) => write!(
+                f,
+                "Scaling({})",
+                thresholds
+                    .iter()
+                    .map(|(level, uses)| format!("{}:{}", level, uses))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            ResourceMax::Modifier(stat) => write!(f, "Modifier({})", stat),
+        }
+    }
+}
+
+impl FromStr for ResourceMax {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (kind, arg) = input
+            .strip_suffix(')')
+            .and_then(|s| s.split_once('('))
+            .ok_or(())?;
+        match kind {
+            "Fixed" => Ok(ResourceMax::Fixed(arg.parse().map_err(|_| ())?)),
+            "Level" => Ok(ResourceMax::Level(arg.parse().map_err(|_| ())?)),
+            "Scaling" => Ok(ResourceMax::Scaling(
+                arg.split(',')
+                    .map(|pair| {
+                        let (level, uses) = pair.split_once(':').ok_or(())?;
+                        Ok((
+                            level.parse().map_err(|_| ())?,
+                            uses.parse().map_err(|_| ())?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, ()>>()?,
+            )),
+            "Modifier" => Ok(ResourceMax::Modifier(Stats::from_str(arg)?)),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromSql for ResourceMax {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<ResourceMax> {
+        Ok(ResourceMax::from_str(value.as_str()?).unwrap())
+    }
+}
+
+impl ToSql for ResourceMax {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+// When the expended uses of a feature are restored.
+// Anything that recharges on a short rest also recharges
+// on a long rest.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recharge {
+    ShortRest,
+    LongRest,
+    Dawn,
+}
+
+impl Recharge {
+    // Whether a feature with this recharge condition is
+    // restored by the given rest or event.
+    pub fn restored_by(&self, rest: &Recharge) -> bool {
+        matches!(
+            (self, rest),
+            (Recharge::ShortRest, Recharge::ShortRest)
+                | (Recharge::ShortRest, Recharge::LongRest)
+                | (Recharge::LongRest, Recharge::LongRest)
+                | (Recharge::Dawn, Recharge::Dawn)
+        )
+    }
+}
+
+impl FromSql for Recharge {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Recharge> {
+        Ok(Recharge::from_str(value.as_str()?).unwrap())
+    }
+}
+
+impl ToSql for Recharge {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromStr for Recharge {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "Short Rest" => Ok(Recharge::ShortRest),
+            "Long Rest" => Ok(Recharge::LongRest),
+            "Dawn" => Ok(Recharge::Dawn),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Recharge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recharge::ShortRest => write!(f, "Short Rest"),
+            Recharge::LongRest => write!(f, "Long Rest"),
+            Recharge::Dawn => write!(f, "Dawn"),
+        }
+    }
+}
+
+// A limited-use resource attached to a feature, such as
+// "Second Wind (1/short rest)". Only the definition lives here;
+// the uses a character has left are tracked on the Character.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureResource {
+    pub max: ResourceMax,
+    pub recharge: Recharge,
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct Feature {
     pub id: Option<i64>,
     pub name: String,
     pub class: Option<FeatureClass>,
     pub description: String,
-}
-
-impl Feature {
-    pub fn new() -> Self {
-        Self::default()
-    }
+    pub resource: Option<FeatureResource>,
 }
 
 impl fmt::Display for Feature {
@@ -74,8 +228,9 @@ impl fmt::Display for Feature {
             "ID: {:#?}, 
             Name: {}, 
             Class: {:#?},
-            Description: {}",
-            self.id, self.name, self.class, self.description
+            Description: {},
+            Resource: {:#?}",
+            self.id, self.name, self.class, self.description, self.resource
         )
     }
 }
@@ -87,15 +242,22 @@ impl Model for Feature {
         params.push(Box::new(self.name.clone()));
         params.push(Box::new(self.class.clone()));
         params.push(Box::new(self.description.clone()));
+        params.push(Box::new(self.resource.as_ref().map(|r| r.max.clone())));
+        params.push(Box::new(self.resource.as_ref().map(|r| r.recharge.clone())));
         params
     }
 
     fn build(row: &Row) -> Result<Feature> {
+        let max: Option<ResourceMax> = row.get(4)?;
+        let recharge: Option<Recharge> = row.get(5)?;
         Ok(Feature {
             id: row.get(0)?,
             name: row.get(1)?,
             class: row.get(2)?,
             description: row.get(3)?,
+            resource: max
+                .zip(recharge)
+                .map(|(max, recharge)| FeatureResource { max, recharge }),
         })
     }
 
@@ -107,19 +269,29 @@ impl Model for Feature {
         "id INTEGER PRIMARY KEY,
         name TEXT NOT NULL,
         class TEXT NOT NULL,
-        description TEXT NOT NULL"
+        description TEXT NOT NULL,
+        resource_max TEXT,
+        recharge TEXT"
             .to_string()
     }
 
     fn queries() -> String {
-        "id, name, class, description".to_string()
+        "id, name, class, description, resource_max, recharge".to_string()
     }
 
     fn values() -> String {
-        "?1, ?2, ?3, ?4".to_string()
+        "?1, ?2, ?3, ?4, ?5, ?6".to_string()
     }
 
     fn id(&self) -> Option<i64> {
         self.id
     }
+
+    fn search_kind() -> Option<SearchKind> {
+        Some(SearchKind::Feature)
+    }
+
+    fn search_text(&self) -> Option<(&str, &str)> {
+        Some((&self.name, &self.description))
+    }
 }
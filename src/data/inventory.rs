@@ -0,0 +1,147 @@
+use crate::data::{character::Model, items::Item};
+use rusqlite::{Result, Row, ToSql};
+use std::fmt;
+
+// A stack of a catalog item held by a single character. The Item
+// is the shared catalog row; everything about this particular
+// stack (how many, where it is, what's been written on it) lives
+// here so two characters holding "Rope" don't share a quantity.
+//
+// Entries are identified within the character's inventory by their
+// key rather than their id, since the rows are rewritten whenever
+// the character is saved. Container holds the key of the entry
+// this one is stored inside, ie. a Backpack.
+#[derive(Default, Debug, Clone)]
+pub struct InventoryEntry {
+    pub id: Option<i64>,
+    pub character: Option<i64>,
+    pub key: u32,
+    pub item: Item,
+    pub quantity: u16,
+    pub equipped: bool,
+    pub attuned: bool,
+    pub container: Option<u32>,
+    pub notes: String,
+    // Charges spent from an item with charges.
+    pub expended_charges: u8,
+}
+
+impl InventoryEntry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_charges(&self) -> Option<u8> {
+        self.item.charges.as_ref().map(|charges| charges.max)
+    }
+
+    pub fn current_charges(&self) -> Option<u8> {
+        self.max_charges()
+            .map(|max| max.saturating_sub(self.expended_charges))
+    }
+
+    // Bonuses only apply while the item is in use: equipped or
+    // attuned to, and attuned to if it needs to be.
+    pub fn in_use(&self) -> bool {
+        self.attuned || (self.equipped && self.item.attunement.is_none())
+    }
+
+    // Whether the two entries are stacks of the same catalog item,
+    // and so can be merged. Items that haven't been saved to the
+    // catalog yet are matched by name instead.
+    pub fn same_item(&self, other: &InventoryEntry) -> bool {
+        match (self.item.id, other.item.id) {
+            (Some(id), Some(other_id)) => id == other_id,
+            (None, None) => self.item.name == other.item.name,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for InventoryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "ID: {:#?}, Key: {}, Item: {}, Quantity: {}, Equipped: {}, Attuned: {}, Container: {:#?}, Notes: {}, Charges spent: {}",
+            self.id,
+            self.key,
+            self.item.name,
+            self.quantity,
+            self.equipped,
+            self.attuned,
+            self.container,
+            self.notes,
+            self.expended_charges
+        )
+    }
+}
+
+impl Model for InventoryEntry {
+    fn parameters(&self) -> Vec<Box<dyn ToSql>> {
+        vec![
+            Box::new(self.id),
+            Box::new(self.character),
+            Box::new(self.key),
+            Box::new(self.item.id),
+            Box::new(self.quantity),
+            Box::new(self.equipped),
+            Box::new(self.attuned),
+            Box::new(self.container),
+            Box::new(self.notes.clone()),
+            Box::new(self.expended_charges),
+        ]
+    }
+
+    // Only the item's id is read from the row; the catalog
+    // item itself is loaded separately by the Database.
+    fn build(row: &Row) -> Result<InventoryEntry> {
+        Ok(InventoryEntry {
+            id: row.get(0)?,
+            character: row.get(1)?,
+            key: row.get(2)?,
+            item: Item {
+                id: row.get(3)?,
+                ..Item::default()
+            },
+            quantity: row.get(4)?,
+            equipped: row.get(5)?,
+            attuned: row.get(6)?,
+            container: row.get(7)?,
+            notes: row.get(8)?,
+            expended_charges: row.get(9)?,
+        })
+    }
+
+    fn table() -> String {
+        "inventory".to_string()
+    }
+
+    fn columns() -> String {
+        "id INTEGER PRIMARY KEY,
+        character INTEGER REFERENCES characters(id),
+        key INTEGER NOT NULL,
+        item INTEGER REFERENCES items(id),
+        quantity INTEGER NOT NULL,
+        equipped INTEGER NOT NULL,
+        attuned INTEGER NOT NULL,
+        container INTEGER,
+        notes TEXT NOT NULL,
+        expended_charges INTEGER NOT NULL,
+        UNIQUE (character, key)"
+            .to_string()
+    }
+
+    fn queries() -> String {
+        "id, character, key, item, quantity, equipped, attuned, container, notes, \
+        expended_charges"
+            .to_string()
+    }
+
+    fn values() -> String {
+        "?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10".to_string()
+    }
+
+    fn id(&self) -> Option<i64> {
+        self.id
+    }
+}
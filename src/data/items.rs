@@ -1,4 +1,7 @@
-use crate::data::character::Model;
+use crate::{
+    data::{catalog::SearchKind, character::Model, currency::Price, feature::Recharge},
+    dice::{Dice, DiceExpression, Term},
+};
 use ::std::{fmt, str::FromStr};
 use rusqlite::{
     types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
@@ -55,23 +58,375 @@ impl fmt::Display for ItemRarity {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeaponCategory {
+    SimpleMelee,
+    SimpleRanged,
+    MartialMelee,
+    MartialRanged,
+}
+
+impl WeaponCategory {
+    pub fn is_ranged(&self) -> bool {
+        matches!(
+            self,
+            WeaponCategory::SimpleRanged | WeaponCategory::MartialRanged
+        )
+    }
+
+    // The name proficiencies use for the whole category,
+    // ie. "Simple" in "Simple Weapons".
+    pub fn group(&self) -> &str {
+        match self {
+            WeaponCategory::SimpleMelee | WeaponCategory::SimpleRanged => "Simple",
+            WeaponCategory::MartialMelee | WeaponCategory::MartialRanged => "Martial",
+        }
+    }
+}
+
+impl FromSql for WeaponCategory {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<WeaponCategory> {
+        Ok(WeaponCategory::from_str(value.as_str()?).unwrap())
+    }
+}
+
+impl ToSql for WeaponCategory {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromStr for WeaponCategory {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "Simple Melee" => Ok(WeaponCategory::SimpleMelee),
+            "Simple Ranged" => Ok(WeaponCategory::SimpleRanged),
+            "Martial Melee" => Ok(WeaponCategory::MartialMelee),
+            "Martial Ranged" => Ok(WeaponCategory::MartialRanged),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for WeaponCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeaponCategory::SimpleMelee => write!(f, "Simple Melee"),
+            WeaponCategory::SimpleRanged => write!(f, "Simple Ranged"),
+            WeaponCategory::MartialMelee => write!(f, "Martial Melee"),
+            WeaponCategory::MartialRanged => write!(f, "Martial Ranged"),
+        }
+    }
+}
+
+// Weapon properties; see PHB p.147. Versatile holds the damage
+// dice used when the weapon is wielded with two hands.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WeaponProperty {
+    Ammunition,
+    Finesse,
+    Heavy,
+    Light,
+    Loading,
+    Reach,
+    Thrown,
+    TwoHanded,
+    Versatile(Dice),
+}
+
+impl FromStr for WeaponProperty {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "Ammunition" => Ok(WeaponProperty::Ammunition),
+            "Finesse" => Ok(WeaponProperty::Finesse),
+            "Heavy" => Ok(WeaponProperty::Heavy),
+            "Light" => Ok(WeaponProperty::Light),
+            "Loading" => Ok(WeaponProperty::Loading),
+            "Reach" => Ok(WeaponProperty::Reach),
+            "Thrown" => Ok(WeaponProperty::Thrown),
+            "Two-Handed" => Ok(WeaponProperty::TwoHanded),
+            _ => {
+                let dice = input
+                    .strip_prefix("Versatile (")
+                    .and_then(|rest| rest.strip_suffix(')'))
+                    .ok_or(())?;
+                match DiceExpression::from_str(dice)
+                    .map_err(|_| ())?
+                    .terms
+                    .as_slice()
+                {
+                    [Term::Dice(dice)] => Ok(WeaponProperty::Versatile(dice.clone())),
+                    _ => Err(()),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for WeaponProperty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeaponProperty::Ammunition => write!(f, "Ammunition"),
+            WeaponProperty::Finesse => write!(f, "Finesse"),
+            WeaponProperty::Heavy => write!(f, "Heavy"),
+            WeaponProperty::Light => write!(f, "Light"),
+            WeaponProperty::Loading => write!(f, "Loading"),
+            WeaponProperty::Reach => write!(f, "Reach"),
+            WeaponProperty::Thrown => write!(f, "Thrown"),
+            WeaponProperty::TwoHanded => write!(f, "Two-Handed"),
+            WeaponProperty::Versatile(dice) => write!(f, "Versatile ({})", dice),
+        }
+    }
+}
+
+// Stored as a single ", " separated column on the item.
+pub struct WeaponProperties(pub Vec<WeaponProperty>);
+
+impl FromSql for WeaponProperties {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<WeaponProperties> {
+        Ok(WeaponProperties(
+            value
+                .as_str()?
+                .split(", ")
+                .filter(|property| !property.is_empty())
+                .map(|property| WeaponProperty::from_str(property).unwrap())
+                .collect(),
+        ))
+    }
+}
+
+impl ToSql for WeaponProperties {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(
+            self.0
+                .iter()
+                .map(|property| property.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ))
+    }
+}
+
+// Normal and long range in feet, for ranged and thrown weapons.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeaponRange {
+    pub normal: u16,
+    pub long: u16,
+}
+
+impl FromSql for WeaponRange {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<WeaponRange> {
+        Ok(WeaponRange::from_str(value.as_str()?).unwrap())
+    }
+}
+
+impl ToSql for WeaponRange {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromStr for WeaponRange {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (normal, long) = input.split_once('/').ok_or(())?;
+        Ok(WeaponRange {
+            normal: normal.trim().parse().map_err(|_| ())?,
+            long: long.trim().parse().map_err(|_| ())?,
+        })
+    }
+}
+
+impl fmt::Display for WeaponRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.normal, self.long)
+    }
+}
+
+// The attack and damage profile of an item that's a weapon.
+// Damage is the weapon's own dice and damage type, ie. "1d8 slashing";
+// the wielder's ability modifier is added by the rules engine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Weapon {
+    pub category: WeaponCategory,
+    pub damage: DiceExpression,
+    pub properties: Vec<WeaponProperty>,
+    pub range: Option<WeaponRange>,
+}
+
+impl Weapon {
+    pub fn has_property(&self, property: &WeaponProperty) -> bool {
+        self.properties.contains(property)
+    }
+
+    // The damage dice used two-handed, for versatile weapons.
+    pub fn versatile(&self) -> Option<&Dice> {
+        self.properties.iter().find_map(|property| match property {
+            WeaponProperty::Versatile(dice) => Some(dice),
+            _ => None,
+        })
+    }
+}
+
+// Items that can hold other items, ie. a Backpack. Capacity is
+// the most weight, in pounds, the container can hold. The contents
+// of a weightless container, ie. a Bag of Holding, don't count
+// toward the weight the character is carrying.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Container {
+    pub capacity: u64,
+    pub weightless: bool,
+}
+
+// Magic items that need attuning to before they work. An empty
+// list of classes means any character can attune to the item,
+// otherwise only those of one of the listed classes can,
+// ie. "requires attunement by a wizard".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attunement {
+    pub classes: Vec<String>,
+}
+
+impl FromSql for Attunement {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Attunement> {
+        Ok(Attunement::from_str(value.as_str()?).unwrap())
+    }
+}
+
+impl ToSql for Attunement {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromStr for Attunement {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Ok(Attunement {
+            classes: input
+                .split(", ")
+                .filter(|class| !class.is_empty())
+                .map(|class| class.to_string())
+                .collect(),
+        })
+    }
+}
+
+impl fmt::Display for Attunement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.classes.join(", "))
+    }
+}
+
+// Charges a magic item holds, ie. a Wand of Magic Missiles with
+// 7 charges that "regains 1d6+1 expended charges daily at dawn".
+// Items without a regain expression regain all of their charges.
+// The charges a character has spent are kept on their inventory entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemCharges {
+    pub max: u8,
+    pub recharge: Recharge,
+    pub regain: Option<DiceExpression>,
+}
+
+// Bonuses a magic item gives while it's in use. Attack and damage
+// bonuses on a weapon only apply to attacks made with it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemBonus {
+    ArmorClass(i8),
+    SavingThrows(i8),
+    AbilityChecks(i8),
+    AttackRolls(i8),
+    DamageRolls(i8),
+}
+
+impl FromStr for ItemBonus {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (bonus, target) = input.split_once(' ').ok_or(())?;
+        let bonus: i8 = bonus.trim_start_matches('+').parse().map_err(|_| ())?;
+        match target {
+            "AC" => Ok(ItemBonus::ArmorClass(bonus)),
+            "saving throws" => Ok(ItemBonus::SavingThrows(bonus)),
+            "ability checks" => Ok(ItemBonus::AbilityChecks(bonus)),
+            "attack rolls" => Ok(ItemBonus::AttackRolls(bonus)),
+            "damage rolls" => Ok(ItemBonus::DamageRolls(bonus)),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for ItemBonus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemBonus::ArmorClass(bonus) => write!(f, "{:+} AC", bonus),
+            ItemBonus::SavingThrows(bonus) => write!(f, "{:+} saving throws", bonus),
+            ItemBonus::AbilityChecks(bonus) => write!(f, "{:+} ability checks", bonus),
+            ItemBonus::AttackRolls(bonus) => write!(f, "{:+} attack rolls", bonus),
+            ItemBonus::DamageRolls(bonus) => write!(f, "{:+} damage rolls", bonus),
+        }
+    }
+}
+
+// Stored as a single ", " separated column on the item.
+pub struct ItemBonuses(pub Vec<ItemBonus>);
+
+impl FromSql for ItemBonuses {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<ItemBonuses> {
+        Ok(ItemBonuses(
+            value
+                .as_str()?
+                .split(", ")
+                .filter(|bonus| !bonus.is_empty())
+                .map(|bonus| ItemBonus::from_str(bonus).unwrap())
+                .collect(),
+        ))
+    }
+}
+
+impl ToSql for ItemBonuses {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(
+            self.0
+                .iter()
+                .map(|bonus| bonus.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ))
+    }
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct Item {
     pub id: Option<i64>,
     pub name: String,
     pub class: String,
-    pub quantity: u16,
     pub rarity: Option<ItemRarity>,
-    pub value: u16,
+    // None for items that can't usually be bought, ie. treasure.
+    pub value: Option<Price>,
     pub weight: u64,
     pub properties: String,
     pub description: String,
+    pub weapon: Option<Weapon>,
+    pub container: Option<Container>,
+    // None for items that don't need attuning to.
+    pub attunement: Option<Attunement>,
+    pub charges: Option<ItemCharges>,
+    pub bonuses: Vec<ItemBonus>,
 }
 
 impl Item {
     pub fn new() -> Self {
         Self::default()
     }
+
+    // The item's bonus of the given kind, ie. the 1 of "+1 AC",
+    // or 0 if it doesn't have one.
+    pub fn bonus(&self, kind: fn(&ItemBonus) -> Option<i8>) -> i8 {
+        self.bonuses.iter().filter_map(kind).sum()
+    }
 }
 
 impl fmt::Display for Item {
@@ -81,51 +436,110 @@ impl fmt::Display for Item {
             "ID: {:#?}, 
             Name: {}, 
             Class: {},
-            Quantity: {},
             Rarity: {:#?},
-            Value: {},
+            Value: {:#?},
             Weight: {},
             Properties: {},
-            Description: {}",
+            Description: {},
+            Weapon: {:#?},
+            Container: {:#?},
+            Attunement: {:#?},
+            Charges: {:#?},
+            Bonuses: {:#?}",
             self.id,
             self.name,
             self.class,
-            self.quantity,
             self.rarity,
             self.value,
             self.weight,
             self.properties,
-            self.description
+            self.description,
+            self.weapon,
+            self.container,
+            self.attunement,
+            self.charges,
+            self.bonuses
         )
     }
 }
 
 impl Model for Item {
     fn parameters(&self) -> Vec<Box<dyn ToSql>> {
-        let mut params: Vec<Box<dyn ToSql>> = Vec::new();
-        params.push(Box::new(self.id));
-        params.push(Box::new(self.name.clone()));
-        params.push(Box::new(self.class.clone()));
-        params.push(Box::new(self.quantity));
-        params.push(Box::new(self.rarity.clone()));
-        params.push(Box::new(self.value));
-        params.push(Box::new(self.weight));
-        params.push(Box::new(self.properties.clone()));
-        params.push(Box::new(self.description.clone()));
-        params
+        vec![
+            Box::new(self.id),
+            Box::new(self.name.clone()),
+            Box::new(self.class.clone()),
+            Box::new(self.rarity.clone()),
+            Box::new(self.value),
+            Box::new(self.weight),
+            Box::new(self.properties.clone()),
+            Box::new(self.description.clone()),
+            Box::new(self.weapon.as_ref().map(|w| w.category)),
+            Box::new(self.weapon.as_ref().map(|w| w.damage.to_string())),
+            Box::new(
+                self.weapon
+                    .as_ref()
+                    .map(|w| WeaponProperties(w.properties.clone())),
+            ),
+            Box::new(self.weapon.as_ref().and_then(|w| w.range)),
+            Box::new(self.container.map(|c| c.capacity)),
+            Box::new(self.container.map(|c| c.weightless)),
+            Box::new(self.attunement.clone()),
+            Box::new(self.charges.as_ref().map(|c| c.max)),
+            Box::new(self.charges.as_ref().map(|c| c.recharge.clone())),
+            Box::new(
+                self.charges
+                    .as_ref()
+                    .and_then(|c| c.regain.as_ref())
+                    .map(|regain| regain.to_string()),
+            ),
+            Box::new(ItemBonuses(self.bonuses.clone())),
+        ]
     }
 
     fn build(row: &Row) -> Result<Item> {
+        // Items without a weapon category or damage aren't weapons.
+        let category: Option<WeaponCategory> = row.get(8)?;
+        let damage: Option<String> = row.get(9)?;
+        let properties: Option<WeaponProperties> = row.get(10)?;
+        let range: Option<WeaponRange> = row.get(11)?;
+        let capacity: Option<u64> = row.get(12)?;
+        let weightless: Option<bool> = row.get(13)?;
+        let max_charges: Option<u8> = row.get(15)?;
+        let charge_recharge: Option<Recharge> = row.get(16)?;
+        let charge_regain: Option<String> = row.get(17)?;
+        let bonuses: Option<ItemBonuses> = row.get(18)?;
+        let weapon = category
+            .zip(damage.and_then(|damage| DiceExpression::from_str(&damage).ok()))
+            .map(|(category, damage)| Weapon {
+                category,
+                damage,
+                properties: properties.map(|p| p.0).unwrap_or_default(),
+                range,
+            });
         Ok(Item {
             id: row.get(0)?,
             name: row.get(1)?,
             class: row.get(2)?,
-            quantity: row.get(3)?,
-            rarity: row.get(4)?,
-            value: row.get(5)?,
-            weight: row.get(6)?,
-            properties: row.get(7)?,
-            description: row.get(8)?,
+            rarity: row.get(3)?,
+            value: row.get(4)?,
+            weight: row.get(5)?,
+            properties: row.get(6)?,
+            description: row.get(7)?,
+            weapon,
+            container: capacity.map(|capacity| Container {
+                capacity,
+                weightless: weightless.unwrap_or(false),
+            }),
+            attunement: row.get(14)?,
+            charges: max_charges
+                .zip(charge_recharge)
+                .map(|(max, recharge)| ItemCharges {
+                    max,
+                    recharge,
+                    regain: charge_regain.and_then(|regain| DiceExpression::from_str(&regain).ok()),
+                }),
+            bonuses: bonuses.map(|b| b.0).unwrap_or_default(),
         })
     }
 
@@ -141,24 +555,49 @@ impl Model for Item {
         "id INTEGER PRIMARY KEY,
          name TEXT NOT NULL,
          class TEXT NOT NULL,
-         quantity INTEGER,
-         rarity TEXT NOT NULL,
-         value INTEGER,
+         rarity TEXT,
+         value TEXT,
          weight INTEGER,
          properties TEXT NOT NULL,
-         description TEXT NOT NULL"
+         description TEXT NOT NULL,
+         weapon_category TEXT,
+         damage TEXT,
+         weapon_properties TEXT,
+         weapon_range TEXT,
+         container_capacity INTEGER,
+         weightless_contents INTEGER,
+         attunement TEXT,
+         max_charges INTEGER,
+         charge_recharge TEXT,
+         charge_regain TEXT,
+         bonuses TEXT"
             .to_string()
     }
 
     fn queries() -> String {
-        "id, name, class, quantity, rarity, value, weight, properties, description".to_string()
+        "id, name, class, rarity, value, weight, properties, description, \
+        weapon_category, damage, weapon_properties, weapon_range, \
+        container_capacity, weightless_contents, attunement, \
+        max_charges, charge_recharge, charge_regain, bonuses"
+            .to_string()
     }
 
     fn values() -> String {
-        "?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9".to_string()
+        (1..=19)
+            .map(|i| format!("?{}", i))
+            .collect::<Vec<_>>()
+            .join(", ")
     }
 
     fn id(&self) -> Option<i64> {
         self.id
     }
+
+    fn search_kind() -> Option<SearchKind> {
+        Some(SearchKind::Item)
+    }
+
+    fn search_text(&self) -> Option<(&str, &str)> {
+        Some((&self.name, &self.description))
+    }
 }
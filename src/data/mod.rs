@@ -1,12 +1,23 @@
 pub mod alignments;
 pub mod background;
+pub mod catalog;
 pub mod character;
 pub mod class;
+pub mod conditions;
+pub mod currency;
 pub mod database;
+pub mod equipment;
+pub mod feat;
 pub mod feature;
+pub mod inventory;
 pub mod items;
 pub mod language;
 pub mod proficiency;
+pub mod query;
 pub mod race;
+pub mod rolls;
+pub mod rules;
+pub mod skills;
 pub mod spells;
+pub mod srd;
 pub mod stats;
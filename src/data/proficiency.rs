@@ -5,7 +5,7 @@ use rusqlite::{
 };
 use std::{fmt, str::FromStr};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 // TODO: Consider sub-class in the Enum
 // ie. Skill(Acrobatics), Armor(Medium), Weapon(Simple), Tool(DisguiseKit)
 pub enum ProficiencyClass {
@@ -100,7 +100,7 @@ impl Model for Proficiency {
     }
 
     fn columns() -> String {
-        "id INTEGER, 
+        "id INTEGER PRIMARY KEY,
         name TEXT NOT NULL, 
         class TEXT NOT NULL"
             .to_string()
@@ -0,0 +1,180 @@
+use crate::data::character::Model;
+use rusqlite::{types::Value, Error, Result};
+use std::marker::PhantomData;
+
+// Which of a model's rows to load with Database::query, and in what
+// order, built up a condition at a time:
+//
+//     Query::<Spell>::new()
+//         .filter("level", Filter::Equals(3.into()))
+//         .order_by("name", Order::Ascending)
+//         .limit(50)
+//
+// Columns are checked against the model's when the query is run, so
+// a misspelt one is an error rather than part of the SQL.
+pub struct Query<T: Model> {
+    filters: Vec<(String, Filter)>,
+    order: Vec<(String, Order)>,
+    limit: Option<usize>,
+    offset: usize,
+    model: PhantomData<T>,
+}
+
+#[derive(Clone)]
+pub enum Filter {
+    Equals(Value),
+    // Text with the string's characters in it in the same order,
+    // though not necessarily next to each other, ignoring case and
+    // spaces; ie. the names the search palette's fuzzy matching finds.
+    Subsequence(String),
+    // Text that's any one of the strings, ignoring case.
+    OneOf(Vec<String>),
+    NotNull,
+}
+
+#[derive(Clone, Copy)]
+pub enum Order {
+    Ascending,
+    Descending,
+}
+
+impl<T: Model> Query<T> {
+    // Every row, in whatever order SQLite gives them.
+    pub fn new() -> Self {
+        Query {
+            filters: Vec::new(),
+            order: Vec::new(),
+            limit: None,
+            offset: 0,
+            model: PhantomData,
+        }
+    }
+
+    // Only rows where the column matches the filter; each filter
+    // added narrows the query further.
+    pub fn filter(mut self, column: &str, filter: Filter) -> Self {
+        self.filters.push((column.to_string(), filter));
+        self
+    }
+
+    // Orders by the column, with ties broken by any
+    // columns ordered by after it.
+    pub fn order_by(mut self, column: &str, order: Order) -> Self {
+        self.order.push((column.to_string(), order));
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    // Errors unless the column is one of the model's.
+    pub fn check_column(column: &str) -> Result<()> {
+        match T::queries().split(',').any(|c| c.trim() == column) {
+            true => Ok(()),
+            false => Err(Error::InvalidColumnName(column.to_string())),
+        }
+    }
+
+    // The WHERE clause for the filters, with a ? for each value;
+    // see values. Empty when there aren't any filters.
+    pub fn conditions(&self) -> Result<String> {
+        let mut conditions = Vec::new();
+        for (column, filter) in &self.filters {
+            Self::check_column(column)?;
+            conditions.push(match filter {
+                Filter::Equals(_) => format!("{} = ?", column),
+                Filter::Subsequence(_) => format!("{} LIKE ? ESCAPE '\\'", column),
+                Filter::OneOf(strings) => format!(
+                    "{} COLLATE NOCASE IN ({})",
+                    column,
+                    vec!["?"; strings.len()].join(", ")
+                ),
+                Filter::NotNull => format!("{} IS NOT NULL", column),
+            });
+        }
+        Ok(match conditions.is_empty() {
+            true => String::new(),
+            false => format!(" WHERE {}", conditions.join(" AND ")),
+        })
+    }
+
+    // The conditions followed by the ORDER BY, LIMIT and OFFSET.
+    pub fn clauses(&self) -> Result<String> {
+        let mut clauses = self.conditions()?;
+        if !self.order.is_empty() {
+            let mut order = Vec::new();
+            for (column, direction) in &self.order {
+                Self::check_column(column)?;
+                order.push(match direction {
+                    Order::Ascending => format!("{} ASC", column),
+                    Order::Descending => format!("{} DESC", column),
+                });
+            }
+            clauses.push_str(&format!(" ORDER BY {}", order.join(", ")));
+        }
+        // SQLite only takes an OFFSET after a LIMIT, where
+        // a negative limit means there isn't one.
+        match self.limit {
+            Some(limit) => clauses.push_str(&format!(" LIMIT {}", limit)),
+            None if self.offset > 0 => clauses.push_str(" LIMIT -1"),
+            None => {}
+        }
+        if self.offset > 0 {
+            clauses.push_str(&format!(" OFFSET {}", self.offset));
+        }
+        Ok(clauses)
+    }
+
+    // The values for the conditions' parameters, in order.
+    pub fn values(&self) -> Vec<Value> {
+        self.filters
+            .iter()
+            .flat_map(|(_, filter)| match filter {
+                Filter::Equals(value) => vec![value.clone()],
+                // A % between each character matches anything in
+                // between them. The characters LIKE treats specially
+                // are escaped, so they're matched as they are.
+                Filter::Subsequence(text) => {
+                    let pattern = text
+                        .chars()
+                        .filter(|c| !c.is_whitespace())
+                        .map(|c| match c {
+                            '\\' | '%' | '_' => format!("\\{}", c),
+                            c => c.to_string(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join("%");
+                    vec![Value::Text(format!("%{}%", pattern))]
+                }
+                Filter::OneOf(strings) => strings.iter().cloned().map(Value::Text).collect(),
+                Filter::NotNull => Vec::new(),
+            })
+            .collect()
+    }
+}
+
+impl<T: Model> Default for Query<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Derived Clone would need the model to be Clone too.
+impl<T: Model> Clone for Query<T> {
+    fn clone(&self) -> Self {
+        Query {
+            filters: self.filters.clone(),
+            order: self.order.clone(),
+            limit: self.limit,
+            offset: self.offset,
+            model: PhantomData,
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::data::{feature::Feature, language::Language, proficiency::Proficiency};
+use crate::data::{feature::Feature, language::Language, proficiency::Proficiency, stats::Stats};
 
 #[derive(Default, Clone)]
 pub struct Race {
@@ -7,4 +7,22 @@ pub struct Race {
     pub languages: Vec<Language>,
     pub proficiencies: Vec<Proficiency>,
     pub features: Vec<Feature>,
+    pub ability_increases: Vec<(Stats, u8)>,
+    // +1 increases to abilities of the player's choice, ie. the
+    // Half-Elf's two, along with extra languages and skills.
+    pub ability_choices: u8,
+    pub language_choices: u8,
+    pub skill_choices: u8,
+    pub speed: u8,
+    pub subraces: Vec<Subrace>,
+}
+
+// Everything a subrace adds on top of its race, ie. a Hill
+// Dwarf's extra point of WIS.
+#[derive(Default, Clone)]
+pub struct Subrace {
+    pub name: String,
+    pub ability_increases: Vec<(Stats, u8)>,
+    pub proficiencies: Vec<Proficiency>,
+    pub language_choices: u8,
 }
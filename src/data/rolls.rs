@@ -0,0 +1,217 @@
+use crate::{
+    data::{character::Model, database::now},
+    dice::RollResult,
+};
+use enum_iterator::IntoEnumIterator;
+use rusqlite::{
+    types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+    Result, Row,
+};
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, IntoEnumIterator)]
+pub enum RollKind {
+    Attack,
+    Save,
+    Check,
+    Damage,
+}
+
+impl RollKind {
+    pub fn get_all_kinds() -> Vec<RollKind> {
+        RollKind::into_enum_iter().collect()
+    }
+}
+
+impl FromSql for RollKind {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<RollKind> {
+        Ok(RollKind::from_str(value.as_str()?).unwrap())
+    }
+}
+
+impl ToSql for RollKind {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromStr for RollKind {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "Attack" => Ok(RollKind::Attack),
+            "Save" => Ok(RollKind::Save),
+            "Check" => Ok(RollKind::Check),
+            "Damage" => Ok(RollKind::Damage),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for RollKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RollKind::Attack => write!(f, "Attack"),
+            RollKind::Save => write!(f, "Save"),
+            RollKind::Check => write!(f, "Check"),
+            RollKind::Damage => write!(f, "Damage"),
+        }
+    }
+}
+
+// A single roll made in the TUI, kept so a character's roll
+// history can be looked back over. Only the text of the roll
+// is stored, not the full RollResult.
+#[derive(Default, Debug, Clone)]
+pub struct Roll {
+    pub id: Option<i64>,
+    pub character: Option<i64>,
+    // Seconds since the unix epoch.
+    pub timestamp: i64,
+    pub kind: Option<RollKind>,
+    pub label: String,
+    pub expression: String,
+    pub breakdown: String,
+    pub total: i32,
+    pub natural_d20: Option<u32>,
+}
+
+impl Roll {
+    // A roll made just now by the given character.
+    pub fn from_result(
+        character: Option<i64>,
+        kind: RollKind,
+        label: String,
+        result: &RollResult,
+    ) -> Self {
+        Roll {
+            id: None,
+            character,
+            timestamp: now(),
+            kind: Some(kind),
+            label,
+            expression: result.expression.to_string(),
+            breakdown: result.breakdown(),
+            total: result.total,
+            natural_d20: result.natural_d20(),
+        }
+    }
+}
+
+// Figures over a character's rolls for the roll history, kept as
+// counts and sums so rolls made after they were loaded can be added.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct RollStatistics {
+    pub rolls: usize,
+    pub total: i64,
+    pub d20s: usize,
+    pub d20_total: i64,
+    pub natural_20s: usize,
+    pub natural_1s: usize,
+}
+
+impl RollStatistics {
+    pub fn add(&mut self, roll: &Roll) {
+        self.rolls += 1;
+        self.total += roll.total as i64;
+        if let Some(d20) = roll.natural_d20 {
+            self.d20s += 1;
+            self.d20_total += d20 as i64;
+            match d20 {
+                20 => self.natural_20s += 1,
+                1 => self.natural_1s += 1,
+                _ => {}
+            }
+        }
+    }
+
+    pub fn average_total(&self) -> Option<f64> {
+        match self.rolls {
+            0 => None,
+            rolls => Some(self.total as f64 / rolls as f64),
+        }
+    }
+
+    pub fn average_d20(&self) -> Option<f64> {
+        match self.d20s {
+            0 => None,
+            d20s => Some(self.d20_total as f64 / d20s as f64),
+        }
+    }
+}
+
+impl fmt::Display for Roll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "ID: {:#?}, Character: {:#?}, Kind: {:#?}, Label: {}, {}: {} = {}",
+            self.id,
+            self.character,
+            self.kind,
+            self.label,
+            self.expression,
+            self.breakdown,
+            self.total
+        )
+    }
+}
+
+impl Model for Roll {
+    fn parameters(&self) -> Vec<Box<dyn ToSql>> {
+        vec![
+            Box::new(self.id),
+            Box::new(self.character),
+            Box::new(self.timestamp),
+            Box::new(self.kind),
+            Box::new(self.label.clone()),
+            Box::new(self.expression.clone()),
+            Box::new(self.breakdown.clone()),
+            Box::new(self.total),
+            Box::new(self.natural_d20),
+        ]
+    }
+
+    fn build(row: &Row) -> Result<Roll> {
+        Ok(Roll {
+            id: row.get(0)?,
+            character: row.get(1)?,
+            timestamp: row.get(2)?,
+            kind: row.get(3)?,
+            label: row.get(4)?,
+            expression: row.get(5)?,
+            breakdown: row.get(6)?,
+            total: row.get(7)?,
+            natural_d20: row.get(8)?,
+        })
+    }
+
+    fn table() -> String {
+        "rolls".to_string()
+    }
+
+    fn columns() -> String {
+        "id INTEGER PRIMARY KEY,
+        character INTEGER REFERENCES characters(id),
+        timestamp INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        label TEXT NOT NULL,
+        expression TEXT NOT NULL,
+        breakdown TEXT NOT NULL,
+        total INTEGER NOT NULL,
+        natural_d20 INTEGER"
+            .to_string()
+    }
+
+    fn queries() -> String {
+        "id, character, timestamp, kind, label, expression, breakdown, total, natural_d20"
+            .to_string()
+    }
+
+    fn values() -> String {
+        "?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9".to_string()
+    }
+
+    fn id(&self) -> Option<i64> {
+        self.id
+    }
+}
@@ -0,0 +1,1110 @@
+use crate::data::{
+    character::Character,
+    conditions::Condition,
+    feat::{Feat, FeatPrerequisite},
+    feature::Recharge,
+    inventory::InventoryEntry,
+    items::{Item, ItemBonus, Weapon, WeaponProperty},
+    proficiency::ProficiencyClass,
+    skills::Skill,
+    stats::Stats,
+    stats::Stats::{CHA, CON, DEX, INT, STR, WIS},
+};
+use crate::dice::{DiceExpression, Term};
+use std::{collections::HashMap, fmt};
+
+// The rules engine; everything on the sheet that isn't entered
+// by hand is worked out here from the Character, so the stored
+// values (ie. speed) stay as the player wrote them and any
+// effects (ie. conditions) are applied on top of them.
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum RollMode {
+    #[default]
+    Normal,
+    Advantage,
+    Disadvantage,
+}
+
+impl RollMode {
+    // Any number of sources of advantage and disadvantage
+    // cancel each other out; see PHB p.173.
+    pub fn from_sources(advantage: bool, disadvantage: bool) -> RollMode {
+        match (advantage, disadvantage) {
+            (true, false) => RollMode::Advantage,
+            (false, true) => RollMode::Disadvantage,
+            _ => RollMode::Normal,
+        }
+    }
+
+    // Combines two modes, ie. one toggled by the player at roll
+    // time and one from the character's derived stats.
+    pub fn with(self, other: RollMode) -> RollMode {
+        RollMode::from_sources(
+            self == RollMode::Advantage || other == RollMode::Advantage,
+            self == RollMode::Disadvantage || other == RollMode::Disadvantage,
+        )
+    }
+}
+
+impl fmt::Display for RollMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RollMode::Normal => write!(f, "Normal"),
+            RollMode::Advantage => write!(f, "Advantage"),
+            RollMode::Disadvantage => write!(f, "Disadvantage"),
+        }
+    }
+}
+
+// The variant encumbrance rules; see PHB p.176.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Encumbrance {
+    #[default]
+    Unencumbered,
+    // Carrying more than 5 times STR.
+    Encumbered,
+    // Carrying more than 10 times STR.
+    HeavilyEncumbered,
+    // Carrying more than the character's carrying capacity.
+    OverCapacity,
+}
+
+impl fmt::Display for Encumbrance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encumbrance::Unencumbered => write!(f, "Unencumbered"),
+            Encumbrance::Encumbered => write!(f, "Encumbered"),
+            Encumbrance::HeavilyEncumbered => write!(f, "Heavily Encumbered"),
+            Encumbrance::OverCapacity => write!(f, "Over Capacity"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct DerivedStats {
+    pub speed: u8,
+    // None when nothing reduces the character's hit point maximum.
+    pub hit_point_max: Option<u16>,
+    pub ability_checks: HashMap<Stats, RollMode>,
+    pub saving_throws: HashMap<Stats, RollMode>,
+    pub auto_fail_saves: Vec<Stats>,
+    pub attack_rolls: RollMode,
+    // Attack rolls made against the character.
+    pub attacks_against: RollMode,
+    pub incapacitated: bool,
+    pub dead: bool,
+    // Pounds carried and the most the character can carry.
+    pub carried_weight: u64,
+    pub carrying_capacity: u64,
+    pub encumbrance: Encumbrance,
+    // Armor class with the bonuses of any magic items in use.
+    pub armor_class: u8,
+    // Ability scores with the increases from any feats taken.
+    pub ability_scores: HashMap<Stats, u8>,
+}
+
+impl DerivedStats {
+    pub fn ability_check(&self, stat: &Stats) -> RollMode {
+        *self.ability_checks.get(stat).unwrap_or(&RollMode::Normal)
+    }
+
+    pub fn saving_throw(&self, stat: &Stats) -> RollMode {
+        *self.saving_throws.get(stat).unwrap_or(&RollMode::Normal)
+    }
+}
+
+// Sources of advantage and disadvantage on a single kind of roll.
+// These are collected before being combined into a RollMode, since
+// any advantage and any disadvantage cancel out regardless of count.
+#[derive(Default, Clone, Copy)]
+struct Sources {
+    advantage: bool,
+    disadvantage: bool,
+}
+
+impl Sources {
+    fn mode(self) -> RollMode {
+        RollMode::from_sources(self.advantage, self.disadvantage)
+    }
+}
+
+#[derive(Default)]
+struct Effects {
+    ability_checks: HashMap<Stats, Sources>,
+    saving_throws: HashMap<Stats, Sources>,
+    attack_rolls: Sources,
+    attacks_against: Sources,
+    immobile: bool,
+}
+
+impl Effects {
+    fn check_disadvantage(&mut self, stats: &[Stats]) {
+        for stat in stats {
+            self.ability_checks.entry(*stat).or_default().disadvantage = true;
+        }
+    }
+
+    fn save_disadvantage(&mut self, stats: &[Stats]) {
+        for stat in stats {
+            self.saving_throws.entry(*stat).or_default().disadvantage = true;
+        }
+    }
+}
+
+const ALL_STATS: [Stats; 6] = [STR, DEX, CON, INT, WIS, CHA];
+
+pub fn derived_stats(character: &Character) -> DerivedStats {
+    let mut derived = DerivedStats {
+        speed: character.speed,
+        ..DerivedStats::default()
+    };
+    let mut effects = Effects::default();
+
+    for active in &character.conditions {
+        match active.condition {
+            Condition::Blinded => {
+                effects.attack_rolls.disadvantage = true;
+                effects.attacks_against.advantage = true;
+            }
+            Condition::Frightened | Condition::Poisoned => {
+                effects.attack_rolls.disadvantage = true;
+                effects.check_disadvantage(&ALL_STATS);
+            }
+            Condition::Grappled => effects.immobile = true,
+            Condition::Incapacitated => derived.incapacitated = true,
+            Condition::Invisible => {
+                effects.attack_rolls.advantage = true;
+                effects.attacks_against.disadvantage = true;
+            }
+            Condition::Paralyzed
+            | Condition::Petrified
+            | Condition::Stunned
+            | Condition::Unconscious => {
+                derived.incapacitated = true;
+                effects.immobile = true;
+                effects.attacks_against.advantage = true;
+                derived.auto_fail_saves = vec![STR, DEX];
+            }
+            Condition::Prone => {
+                effects.attack_rolls.disadvantage = true;
+                // Only within 5 feet; ranged attacks against a prone
+                // creature have disadvantage instead.
+                effects.attacks_against.advantage = true;
+            }
+            Condition::Restrained => {
+                effects.immobile = true;
+                effects.attack_rolls.disadvantage = true;
+                effects.attacks_against.advantage = true;
+                effects.save_disadvantage(&[DEX]);
+            }
+            Condition::Charmed | Condition::Deafened | Condition::Exhaustion(_) => {}
+        }
+    }
+
+    derived.ability_scores = ALL_STATS
+        .iter()
+        .map(|stat| (*stat, character.ability_score(stat)))
+        .collect();
+
+    derived.armor_class = (character.armor_class as i16
+        + magic_bonus(character, |bonus| match bonus {
+            ItemBonus::ArmorClass(bonus) => Some(*bonus),
+            _ => None,
+        }) as i16)
+        .clamp(0, u8::MAX as i16) as u8;
+
+    derived.carried_weight = character.carried_weight();
+    derived.carrying_capacity = carrying_capacity(character);
+    derived.encumbrance = encumbrance(character);
+    match derived.encumbrance {
+        Encumbrance::Unencumbered => {}
+        Encumbrance::Encumbered => derived.speed = derived.speed.saturating_sub(10),
+        // Heavily encumbered characters have disadvantage on rolls
+        // using STR, DEX or CON; nearly every attack roll uses
+        // STR or DEX, so attack rolls are treated the same.
+        Encumbrance::HeavilyEncumbered | Encumbrance::OverCapacity => {
+            derived.speed = derived.speed.saturating_sub(20);
+            effects.attack_rolls.disadvantage = true;
+            effects.check_disadvantage(&[STR, DEX, CON]);
+            effects.save_disadvantage(&[STR, DEX, CON]);
+        }
+    }
+    // Past their carrying capacity a character can only push or
+    // drag what they're carrying, at 5 feet.
+    if derived.encumbrance == Encumbrance::OverCapacity {
+        derived.speed = derived.speed.min(5);
+    }
+
+    // Exhaustion effects are cumulative; see PHB p.291.
+    let exhaustion = character.exhaustion();
+    if exhaustion >= 1 {
+        effects.check_disadvantage(&ALL_STATS);
+    }
+    if exhaustion >= 2 {
+        derived.speed /= 2;
+    }
+    if exhaustion >= 3 {
+        effects.attack_rolls.disadvantage = true;
+        effects.save_disadvantage(&ALL_STATS);
+    }
+    if exhaustion >= 4 {
+        derived.hit_point_max = Some(character.max_hit_points / 2);
+    }
+    if exhaustion >= 5 {
+        effects.immobile = true;
+    }
+    derived.dead = exhaustion >= 6;
+
+    if effects.immobile {
+        derived.speed = 0;
+    }
+    derived.ability_checks = effects
+        .ability_checks
+        .into_iter()
+        .map(|(stat, sources)| (stat, sources.mode()))
+        .collect();
+    derived.saving_throws = effects
+        .saving_throws
+        .into_iter()
+        .map(|(stat, sources)| (stat, sources.mode()))
+        .collect();
+    derived.attack_rolls = effects.attack_rolls.mode();
+    derived.attacks_against = effects.attacks_against.mode();
+    derived
+}
+
+// Proficiency bonus by level; see PHB p.15. A character who
+// hasn't been given a level yet is treated as level 1.
+pub fn proficiency_bonus(level: u8) -> i8 {
+    (2 + (level.clamp(1, 20) - 1) / 4) as i8
+}
+
+pub fn skill_proficient(character: &Character, skill: &Skill) -> bool {
+    character.all_proficiencies().iter().any(|proficiency| {
+        matches!(proficiency.class, Some(ProficiencyClass::Skill))
+            && proficiency.name.as_deref() == Some(skill.to_string().as_str())
+    })
+}
+
+pub fn saving_throw_proficient(character: &Character, stat: &Stats) -> bool {
+    *character.saving_throws.get(stat).unwrap_or(&false)
+}
+
+pub fn ability_check_bonus(character: &Character, stat: &Stats) -> i8 {
+    character.modifier(stat) + check_magic_bonus(character)
+}
+
+pub fn saving_throw_bonus(character: &Character, stat: &Stats) -> i8 {
+    let magic = magic_bonus(character, |bonus| match bonus {
+        ItemBonus::SavingThrows(bonus) => Some(*bonus),
+        _ => None,
+    });
+    match saving_throw_proficient(character, stat) {
+        true => character.modifier(stat) + proficiency_bonus(character.level) + magic,
+        false => character.modifier(stat) + magic,
+    }
+}
+
+pub fn skill_bonus(character: &Character, skill: &Skill) -> i8 {
+    let magic = check_magic_bonus(character);
+    match skill_proficient(character, skill) {
+        true => character.modifier(&skill.ability()) + proficiency_bonus(character.level) + magic,
+        false => character.modifier(&skill.ability()) + magic,
+    }
+}
+
+fn check_magic_bonus(character: &Character) -> i8 {
+    magic_bonus(character, |bonus| match bonus {
+        ItemBonus::AbilityChecks(bonus) => Some(*bonus),
+        _ => None,
+    })
+}
+
+// The total bonus of the given kind from every magic item the
+// character has in use. Weapons are left out, since their attack
+// and damage bonuses only apply to attacks made with them.
+pub fn magic_bonus(character: &Character, kind: fn(&ItemBonus) -> Option<i8>) -> i8 {
+    character
+        .inventory
+        .iter()
+        .filter(|entry| entry.in_use() && entry.item.weapon.is_none())
+        .map(|entry| entry.item.bonus(kind))
+        .sum()
+}
+
+// A character can be attuned to at most three items at once;
+// see DMG p.138.
+pub const MAX_ATTUNED_ITEMS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttunementError {
+    NotRequired,
+    AlreadyAttuned,
+    WrongClass(Vec<String>),
+    TooManyItems,
+}
+
+impl fmt::Display for AttunementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttunementError::NotRequired => write!(f, "Item doesn't require attunement"),
+            AttunementError::AlreadyAttuned => write!(f, "Already attuned to this item"),
+            AttunementError::WrongClass(classes) => {
+                write!(f, "Requires attunement by a {}", classes.join(" or "))
+            }
+            AttunementError::TooManyItems => write!(
+                f,
+                "Already attuned to {} items, end an attunement first",
+                MAX_ATTUNED_ITEMS
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeatError {
+    AlreadyTaken,
+    // Each group is met by any one of its prerequisites.
+    PrerequisitesNotMet(Vec<Vec<FeatPrerequisite>>),
+}
+
+impl fmt::Display for FeatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeatError::AlreadyTaken => write!(f, "Already has this feat"),
+            FeatError::PrerequisitesNotMet(groups) => write!(
+                f,
+                "Requires {}",
+                groups
+                    .iter()
+                    .map(|group| group
+                        .iter()
+                        .map(|prerequisite| prerequisite.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" or "))
+                    .collect::<Vec<_>>()
+                    .join(" and ")
+            ),
+        }
+    }
+}
+
+pub fn can_take_feat(character: &Character, feat: &Feat) -> Result<(), FeatError> {
+    if character
+        .feats
+        .iter()
+        .any(|taken| taken.name.eq_ignore_ascii_case(&feat.name))
+    {
+        return Err(FeatError::AlreadyTaken);
+    }
+    let unmet = feat.unmet_prerequisites(character);
+    if !unmet.is_empty() {
+        return Err(FeatError::PrerequisitesNotMet(unmet));
+    }
+    Ok(())
+}
+
+pub fn attuned_items(character: &Character) -> usize {
+    character
+        .inventory
+        .iter()
+        .filter(|entry| entry.attuned)
+        .count()
+}
+
+pub fn can_attune(character: &Character, entry: &InventoryEntry) -> Result<(), AttunementError> {
+    let attunement = entry
+        .item
+        .attunement
+        .as_ref()
+        .ok_or(AttunementError::NotRequired)?;
+    if entry.attuned {
+        return Err(AttunementError::AlreadyAttuned);
+    }
+    if !attunement.classes.is_empty()
+        && !attunement
+            .classes
+            .iter()
+            .any(|class| class.eq_ignore_ascii_case(&character.class))
+    {
+        return Err(AttunementError::WrongClass(attunement.classes.clone()));
+    }
+    if attuned_items(character) >= MAX_ATTUNED_ITEMS {
+        return Err(AttunementError::TooManyItems);
+    }
+    Ok(())
+}
+
+// Ranged weapons use DEX, finesse weapons whichever of STR
+// and DEX is higher, and everything else STR; see PHB p.194.
+// Thrown melee weapons keep the ability they'd use in melee.
+pub fn weapon_ability(character: &Character, weapon: &Weapon) -> Stats {
+    let finesse = weapon.has_property(&WeaponProperty::Finesse)
+        && character.modifier(&DEX) > character.modifier(&STR);
+    match weapon.category.is_ranged() || finesse {
+        true => DEX,
+        false => STR,
+    }
+}
+
+// A weapon proficiency covers the weapon by name, ie. "Longsword"
+// or "Longswords", or by its category, ie. "Martial Weapons".
+pub fn weapon_proficient(character: &Character, item: &Item) -> bool {
+    let weapon = match &item.weapon {
+        Some(weapon) => weapon,
+        None => return false,
+    };
+    let name = item.name.to_lowercase();
+    let group = weapon.category.group().to_lowercase();
+    character.all_proficiencies().iter().any(|proficiency| {
+        let proficiency_name = proficiency.name.as_deref().unwrap_or("").to_lowercase();
+        matches!(proficiency.class, Some(ProficiencyClass::Weapon))
+            && (proficiency_name == name
+                || proficiency_name == format!("{}s", name)
+                || proficiency_name == group
+                || proficiency_name == format!("{} weapons", group))
+    })
+}
+
+// A magic weapon's own bonuses apply to attacks made with it,
+// as long as it's attuned to if it needs to be.
+fn weapon_magic_bonus(entry: &InventoryEntry, kind: fn(&ItemBonus) -> Option<i8>) -> i8 {
+    match entry.item.attunement.is_none() || entry.attuned {
+        true => entry.item.bonus(kind),
+        false => 0,
+    }
+}
+
+pub fn attack_bonus(character: &Character, entry: &InventoryEntry) -> Option<i8> {
+    let weapon = entry.item.weapon.as_ref()?;
+    let modifier = character.modifier(&weapon_ability(character, weapon))
+        + weapon_magic_bonus(entry, |bonus| match bonus {
+            ItemBonus::AttackRolls(bonus) => Some(*bonus),
+            _ => None,
+        });
+    match weapon_proficient(character, &entry.item) {
+        true => Some(modifier + proficiency_bonus(character.level)),
+        false => Some(modifier),
+    }
+}
+
+// The weapon's damage dice plus the wielder's ability modifier.
+// Versatile weapons can be used two-handed for their larger dice.
+pub fn damage_expression(
+    character: &Character,
+    entry: &InventoryEntry,
+    two_handed: bool,
+) -> Option<DiceExpression> {
+    let weapon = entry.item.weapon.as_ref()?;
+    let mut damage = weapon.damage.clone();
+    if let (true, Some(versatile)) = (two_handed, weapon.versatile()) {
+        if let Some(term) = damage
+            .terms
+            .iter_mut()
+            .find(|term| matches!(term, Term::Dice(_)))
+        {
+            *term = Term::Dice(versatile.clone());
+        }
+    }
+    let modifier = character.modifier(&weapon_ability(character, weapon))
+        + weapon_magic_bonus(entry, |bonus| match bonus {
+            ItemBonus::DamageRolls(bonus) => Some(*bonus),
+            _ => None,
+        });
+    if modifier != 0 {
+        damage.terms.push(Term::Modifier(modifier as i32));
+    }
+    Some(damage)
+}
+
+// A STR score that hasn't been set yet is treated as 10.
+pub fn carrying_capacity(character: &Character) -> u64 {
+    character.ability_score(&STR) as u64 * 15
+}
+
+pub fn encumbrance(character: &Character) -> Encumbrance {
+    let strength = character.ability_score(&STR) as u64;
+    let carried = character.carried_weight();
+    if carried > carrying_capacity(character) {
+        Encumbrance::OverCapacity
+    } else if carried > strength * 10 {
+        Encumbrance::HeavilyEncumbered
+    } else if carried > strength * 5 {
+        Encumbrance::Encumbered
+    } else {
+        Encumbrance::Unencumbered
+    }
+}
+
+// Spell slots for each caster level, 1st to 9th level slots;
+// see PHB p.165.
+const SPELL_SLOTS: [[u8; 9]; 20] = [
+    [2, 0, 0, 0, 0, 0, 0, 0, 0],
+    [3, 0, 0, 0, 0, 0, 0, 0, 0],
+    [4, 2, 0, 0, 0, 0, 0, 0, 0],
+    [4, 3, 0, 0, 0, 0, 0, 0, 0],
+    [4, 3, 2, 0, 0, 0, 0, 0, 0],
+    [4, 3, 3, 0, 0, 0, 0, 0, 0],
+    [4, 3, 3, 1, 0, 0, 0, 0, 0],
+    [4, 3, 3, 2, 0, 0, 0, 0, 0],
+    [4, 3, 3, 3, 1, 0, 0, 0, 0],
+    [4, 3, 3, 3, 2, 0, 0, 0, 0],
+    [4, 3, 3, 3, 2, 1, 0, 0, 0],
+    [4, 3, 3, 3, 2, 1, 0, 0, 0],
+    [4, 3, 3, 3, 2, 1, 1, 0, 0],
+    [4, 3, 3, 3, 2, 1, 1, 0, 0],
+    [4, 3, 3, 3, 2, 1, 1, 1, 0],
+    [4, 3, 3, 3, 2, 1, 1, 1, 0],
+    [4, 3, 3, 3, 2, 1, 1, 1, 1],
+    [4, 3, 3, 3, 3, 1, 1, 1, 1],
+    [4, 3, 3, 3, 3, 2, 1, 1, 1],
+    [4, 3, 3, 3, 3, 2, 2, 1, 1],
+];
+
+// The character's spell slots of each level, 1st to 9th, from
+// their class and level. Paladins and rangers cast as half their
+// level, rounded up, from 2nd level. A warlock's pact magic slots
+// are all of the same level; see PHB p.107.
+pub fn spell_slots(character: &Character) -> [u8; 9] {
+    let level = character.level.clamp(1, 20);
+    let caster_level = match character.class.to_lowercase().as_str() {
+        "bard" | "cleric" | "druid" | "sorcerer" | "wizard" => level,
+        "paladin" | "ranger" if level >= 2 => level.div_ceil(2),
+        "warlock" => {
+            let mut slots = [0; 9];
+            let slot_level = level.div_ceil(2).min(5) as usize;
+            slots[slot_level - 1] = match level {
+                1 => 1,
+                2..=10 => 2,
+                11..=16 => 3,
+                _ => 4,
+            };
+            return slots;
+        }
+        _ => 0,
+    };
+    match caster_level {
+        0 => [0; 9],
+        caster_level => SPELL_SLOTS[caster_level as usize - 1],
+    }
+}
+
+// Pact magic slots come back on a short rest,
+// everyone else's on a long rest.
+pub fn spell_slot_recharge(character: &Character) -> Recharge {
+    match character.class.eq_ignore_ascii_case("warlock") {
+        true => Recharge::ShortRest,
+        false => Recharge::LongRest,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{
+        items::{Attunement, WeaponCategory},
+        proficiency::Proficiency,
+    };
+
+    fn adventurer() -> Character {
+        Character {
+            name: "Mira".to_string(),
+            speed: 30,
+            hit_points: 17,
+            max_hit_points: 24,
+            ..Character::new()
+        }
+    }
+
+    fn with(conditions: &[Condition]) -> DerivedStats {
+        let mut character = adventurer();
+        for condition in conditions {
+            character.add_condition(*condition, None);
+        }
+        derived_stats(&character)
+    }
+
+    fn checks(derived: &DerivedStats) -> Vec<RollMode> {
+        ALL_STATS
+            .iter()
+            .map(|stat| derived.ability_check(stat))
+            .collect()
+    }
+
+    fn saves(derived: &DerivedStats) -> Vec<RollMode> {
+        ALL_STATS
+            .iter()
+            .map(|stat| derived.saving_throw(stat))
+            .collect()
+    }
+
+    #[test]
+    fn unaffected_characters_roll_normally() {
+        let derived = with(&[]);
+        assert_eq!(derived.speed, 30);
+        assert_eq!(derived.hit_point_max, None);
+        assert_eq!(derived.attack_rolls, RollMode::Normal);
+        assert_eq!(derived.attacks_against, RollMode::Normal);
+        assert_eq!(checks(&derived), vec![RollMode::Normal; 6]);
+        assert_eq!(saves(&derived), vec![RollMode::Normal; 6]);
+        assert!(derived.auto_fail_saves.is_empty());
+        assert!(!derived.incapacitated && !derived.dead);
+    }
+
+    #[test]
+    fn conditions_give_advantage_and_disadvantage() {
+        use RollMode::{Advantage, Disadvantage, Normal};
+        // The condition, then its attack rolls and attacks against it.
+        let attacks = [
+            (Condition::Blinded, Disadvantage, Advantage),
+            (Condition::Charmed, Normal, Normal),
+            (Condition::Deafened, Normal, Normal),
+            (Condition::Frightened, Disadvantage, Normal),
+            (Condition::Grappled, Normal, Normal),
+            (Condition::Incapacitated, Normal, Normal),
+            (Condition::Invisible, Advantage, Disadvantage),
+            (Condition::Paralyzed, Normal, Advantage),
+            (Condition::Petrified, Normal, Advantage),
+            (Condition::Poisoned, Disadvantage, Normal),
+            (Condition::Prone, Disadvantage, Advantage),
+            (Condition::Restrained, Disadvantage, Advantage),
+            (Condition::Stunned, Normal, Advantage),
+            (Condition::Unconscious, Normal, Advantage),
+        ];
+        for (condition, attack_rolls, attacks_against) in attacks {
+            let derived = with(&[condition]);
+            assert_eq!(derived.attack_rolls, attack_rolls, "{}", condition);
+            assert_eq!(derived.attacks_against, attacks_against, "{}", condition);
+        }
+
+        for condition in [Condition::Frightened, Condition::Poisoned] {
+            assert_eq!(checks(&with(&[condition])), vec![Disadvantage; 6]);
+        }
+        let restrained = with(&[Condition::Restrained]);
+        assert_eq!(restrained.saving_throw(&DEX), Disadvantage);
+        assert_eq!(restrained.saving_throw(&STR), Normal);
+
+        // Advantage and disadvantage from different conditions cancel out.
+        let derived = with(&[Condition::Blinded, Condition::Invisible]);
+        assert_eq!(derived.attack_rolls, Normal);
+        assert_eq!(derived.attacks_against, Normal);
+    }
+
+    #[test]
+    fn conditions_stop_characters_moving_and_acting() {
+        for condition in Condition::get_all_conditions() {
+            let derived = with(&[condition]);
+            let immobile = matches!(
+                condition,
+                Condition::Grappled
+                    | Condition::Paralyzed
+                    | Condition::Petrified
+                    | Condition::Restrained
+                    | Condition::Stunned
+                    | Condition::Unconscious
+            );
+            let fails_saves = matches!(
+                condition,
+                Condition::Paralyzed
+                    | Condition::Petrified
+                    | Condition::Stunned
+                    | Condition::Unconscious
+            );
+            let incapacitated = fails_saves || condition == Condition::Incapacitated;
+            assert_eq!(derived.speed == 0, immobile, "{}", condition);
+            assert_eq!(derived.incapacitated, incapacitated, "{}", condition);
+            assert_eq!(
+                derived.auto_fail_saves.is_empty(),
+                !fails_saves,
+                "{}",
+                condition
+            );
+        }
+        assert_eq!(with(&[Condition::Stunned]).auto_fail_saves, vec![STR, DEX]);
+    }
+
+    #[test]
+    fn exhaustion_levels_add_up() {
+        use RollMode::{Disadvantage, Normal};
+        let levels: Vec<DerivedStats> = (1..=6)
+            .map(|level| with(&[Condition::Exhaustion(level)]))
+            .collect();
+
+        // 1: disadvantage on ability checks.
+        assert_eq!(checks(&levels[0]), vec![Disadvantage; 6]);
+        assert_eq!(levels[0].speed, 30);
+        assert_eq!(levels[0].attack_rolls, Normal);
+        // 2: speed halved.
+        assert_eq!(levels[1].speed, 15);
+        assert_eq!(saves(&levels[1]), vec![Normal; 6]);
+        // 3: disadvantage on attack rolls and saving throws.
+        assert_eq!(levels[2].attack_rolls, Disadvantage);
+        assert_eq!(saves(&levels[2]), vec![Disadvantage; 6]);
+        assert_eq!(levels[2].hit_point_max, None);
+        // 4: hit point maximum halved, rather than current hit points.
+        assert_eq!(levels[3].hit_point_max, Some(12));
+        assert_eq!(levels[3].speed, 15);
+        // 5: speed reduced to 0.
+        assert_eq!(levels[4].speed, 0);
+        assert!(!levels[4].dead);
+        // 6: death.
+        assert!(levels[5].dead);
+
+        // Each level keeps the effects of those before it.
+        for derived in &levels {
+            assert_eq!(checks(derived), vec![Disadvantage; 6]);
+        }
+        for derived in &levels[3..] {
+            assert_eq!(derived.hit_point_max, Some(12));
+            assert_eq!(derived.attack_rolls, Disadvantage);
+        }
+    }
+
+    #[test]
+    fn proficiency_adds_to_saves_and_skills() {
+        assert_eq!(
+            [0, 1, 4, 5, 8, 9, 12, 13, 16, 17, 20].map(proficiency_bonus),
+            [2, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6]
+        );
+
+        let mut character = adventurer();
+        character.level = 5;
+        character.stats = HashMap::from([(DEX, 16), (WIS, 9)]);
+        character.saving_throws = HashMap::from([(DEX, true)]);
+        character.proficiencies = vec![Proficiency {
+            name: Some(Skill::Stealth.to_string()),
+            class: Some(ProficiencyClass::Skill),
+            ..Proficiency::default()
+        }];
+
+        assert_eq!(ability_check_bonus(&character, &DEX), 3);
+        assert_eq!(saving_throw_bonus(&character, &DEX), 6);
+        assert_eq!(saving_throw_bonus(&character, &WIS), -1);
+        // Stealth is proficient and Acrobatics, on the same ability,
+        // isn't; Athletics falls back on an unset Strength of 10.
+        assert_eq!(skill_bonus(&character, &Skill::Stealth), 6);
+        assert_eq!(skill_bonus(&character, &Skill::Acrobatics), 3);
+        assert_eq!(skill_bonus(&character, &Skill::Perception), -1);
+        assert_eq!(skill_bonus(&character, &Skill::Athletics), 0);
+    }
+
+    fn weapon(name: &str, category: WeaponCategory, damage: &str, properties: &[&str]) -> Item {
+        Item {
+            name: name.to_string(),
+            weapon: Some(Weapon {
+                category,
+                damage: damage.parse().unwrap(),
+                properties: properties
+                    .iter()
+                    .map(|property| property.parse().unwrap())
+                    .collect(),
+                range: None,
+            }),
+            ..Item::default()
+        }
+    }
+
+    fn held(item: &Item) -> InventoryEntry {
+        InventoryEntry {
+            item: item.clone(),
+            quantity: 1,
+            ..InventoryEntry::new()
+        }
+    }
+
+    #[test]
+    fn weapons_use_the_right_ability_and_proficiency() {
+        let rapier = weapon(
+            "Rapier",
+            WeaponCategory::MartialMelee,
+            "1d8 piercing",
+            &["Finesse"],
+        );
+        let longsword = weapon(
+            "Longsword",
+            WeaponCategory::MartialMelee,
+            "1d8 slashing",
+            &["Versatile (1d10)"],
+        );
+        let shortbow = weapon(
+            "Shortbow",
+            WeaponCategory::SimpleRanged,
+            "1d6 piercing",
+            &[],
+        );
+
+        let mut character = adventurer();
+        character.stats = HashMap::from([(STR, 12), (DEX, 16)]);
+        character.proficiencies = ["Simple Weapons", "Longswords"]
+            .map(|name| Proficiency {
+                name: Some(name.to_string()),
+                class: Some(ProficiencyClass::Weapon),
+                ..Proficiency::default()
+            })
+            .to_vec();
+
+        // Finesse and ranged weapons use the higher DEX.
+        assert_eq!(
+            weapon_ability(&character, rapier.weapon.as_ref().unwrap()),
+            DEX
+        );
+        assert_eq!(
+            weapon_ability(&character, longsword.weapon.as_ref().unwrap()),
+            STR
+        );
+        assert_eq!(
+            weapon_ability(&character, shortbow.weapon.as_ref().unwrap()),
+            DEX
+        );
+
+        // Proficiency by name or by category, at +2 for level 1.
+        assert!(!weapon_proficient(&character, &rapier));
+        assert!(weapon_proficient(&character, &longsword));
+        assert!(weapon_proficient(&character, &shortbow));
+        assert_eq!(attack_bonus(&character, &held(&rapier)), Some(3));
+        assert_eq!(attack_bonus(&character, &held(&longsword)), Some(3));
+        assert_eq!(attack_bonus(&character, &held(&shortbow)), Some(5));
+        assert_eq!(attack_bonus(&character, &held(&Item::default())), None);
+
+        let damage = |item: &Item, two_handed| {
+            damage_expression(&character, &held(item), two_handed)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(damage(&rapier, false), "1d8+3 piercing");
+        assert_eq!(damage(&longsword, false), "1d8+1 slashing");
+        assert_eq!(damage(&longsword, true), "1d10+1 slashing");
+        // Only versatile weapons get bigger dice for two hands.
+        assert_eq!(damage(&rapier, true), "1d8+3 piercing");
+    }
+
+    #[test]
+    fn encumbrance_follows_the_variant_thresholds() {
+        // STR 10; encumbered past 50 lb, heavily past 100 lb
+        // and over capacity past 150 lb.
+        let carrying = |weight: u64| {
+            let mut character = adventurer();
+            character.add_item(
+                Item {
+                    name: "Stone".to_string(),
+                    weight,
+                    ..Item::new()
+                },
+                1,
+            );
+            derived_stats(&character)
+        };
+        for (weight, encumbrance, speed) in [
+            (50, Encumbrance::Unencumbered, 30),
+            (51, Encumbrance::Encumbered, 20),
+            (100, Encumbrance::Encumbered, 20),
+            (101, Encumbrance::HeavilyEncumbered, 10),
+            (150, Encumbrance::HeavilyEncumbered, 10),
+            (151, Encumbrance::OverCapacity, 5),
+        ] {
+            let derived = carrying(weight);
+            assert_eq!(derived.encumbrance, encumbrance, "{} lb", weight);
+            assert_eq!(derived.speed, speed, "{} lb", weight);
+            assert_eq!(derived.carried_weight, weight);
+        }
+        assert_eq!(carrying(0).carrying_capacity, 150);
+
+        let heavy = carrying(101);
+        assert_eq!(heavy.attack_rolls, RollMode::Disadvantage);
+        assert_eq!(heavy.ability_check(&STR), RollMode::Disadvantage);
+        assert_eq!(heavy.saving_throw(&CON), RollMode::Disadvantage);
+        assert_eq!(heavy.ability_check(&INT), RollMode::Normal);
+
+        // The thresholds go up with STR.
+        let mut strong = adventurer();
+        strong.stats.insert(STR, 16);
+        strong.add_item(
+            Item {
+                name: "Stone".to_string(),
+                weight: 80,
+                ..Item::new()
+            },
+            1,
+        );
+        assert_eq!(encumbrance(&strong), Encumbrance::Unencumbered);
+        assert_eq!(carrying_capacity(&strong), 240);
+    }
+
+    #[test]
+    fn magic_weapons_add_their_bonuses_once_attuned() {
+        let mut sword = weapon(
+            "Sword of Sharpness",
+            WeaponCategory::MartialMelee,
+            "2d6 slashing",
+            &[],
+        );
+        sword.bonuses = vec![ItemBonus::AttackRolls(2), ItemBonus::DamageRolls(1)];
+        let mut entry = held(&sword);
+        let character = adventurer();
+
+        assert_eq!(attack_bonus(&character, &entry), Some(2));
+        assert_eq!(
+            damage_expression(&character, &entry, false)
+                .unwrap()
+                .to_string(),
+            "2d6+1 slashing"
+        );
+
+        // Items that need attuning to do nothing until they are.
+        entry.item.attunement = Some(Attunement {
+            classes: Vec::new(),
+        });
+        assert_eq!(attack_bonus(&character, &entry), Some(0));
+        assert_eq!(
+            damage_expression(&character, &entry, false)
+                .unwrap()
+                .to_string(),
+            "2d6 slashing"
+        );
+        entry.attuned = true;
+        assert_eq!(attack_bonus(&character, &entry), Some(2));
+    }
+
+    #[test]
+    fn worn_items_add_to_armor_class_saves_and_checks() {
+        let mut character = adventurer();
+        character.armor_class = 15;
+        let cloak = character.add_item(
+            Item {
+                name: "Cloak of Protection".to_string(),
+                attunement: Some(Attunement {
+                    classes: Vec::new(),
+                }),
+                bonuses: vec![ItemBonus::ArmorClass(1), ItemBonus::SavingThrows(1)],
+                ..Item::new()
+            },
+            1,
+        );
+        let stone = character.add_item(
+            Item {
+                name: "Stone of Good Luck".to_string(),
+                bonuses: vec![ItemBonus::AbilityChecks(1), ItemBonus::SavingThrows(1)],
+                ..Item::new()
+            },
+            1,
+        );
+
+        // Carried isn't in use; equipped is, unless it needs attuning to.
+        assert_eq!(derived_stats(&character).armor_class, 15);
+        assert_eq!(saving_throw_bonus(&character, &DEX), 0);
+        character.entry_mut(cloak).unwrap().equipped = true;
+        character.entry_mut(stone).unwrap().equipped = true;
+        assert_eq!(derived_stats(&character).armor_class, 15);
+        assert_eq!(saving_throw_bonus(&character, &DEX), 1);
+        assert_eq!(skill_bonus(&character, &Skill::Stealth), 1);
+
+        character.entry_mut(cloak).unwrap().attuned = true;
+        assert_eq!(derived_stats(&character).armor_class, 16);
+        assert_eq!(saving_throw_bonus(&character, &DEX), 2);
+        assert_eq!(ability_check_bonus(&character, &DEX), 1);
+    }
+
+    fn feat(name: &str, prerequisites: &[&str], effects: &[&str]) -> Feat {
+        Feat {
+            name: name.to_string(),
+            prerequisites: prerequisites.iter().map(|p| p.parse().unwrap()).collect(),
+            effects: effects.iter().map(|e| e.parse().unwrap()).collect(),
+            ..Feat::default()
+        }
+    }
+
+    #[test]
+    fn feats_need_every_group_of_prerequisites_met() {
+        let mut character = adventurer();
+        character.race = "Elf".to_string();
+        let ritual_caster = feat("Ritual Caster", &["INT 13", "WIS 13"], &[]);
+        let elven_accuracy = feat("Elven Accuracy", &["Race: Elf", "Race: Half-Elf"], &[]);
+        let heavy_master = feat(
+            "Heavy Armor Master",
+            &["STR 13", "Proficiency: Heavy Armor"],
+            &[],
+        );
+
+        assert_eq!(can_take_feat(&character, &elven_accuracy), Ok(()));
+        let error = can_take_feat(&character, &ritual_caster).unwrap_err();
+        assert_eq!(error.to_string(), "Requires INT 13 or WIS 13");
+        // Meeting one of the alternatives is enough.
+        character.stats.insert(WIS, 13);
+        assert_eq!(can_take_feat(&character, &ritual_caster), Ok(()));
+
+        // Only the groups that aren't met are reported.
+        character.stats.insert(STR, 14);
+        assert_eq!(
+            can_take_feat(&character, &heavy_master),
+            Err(FeatError::PrerequisitesNotMet(vec![vec![
+                FeatPrerequisite::Proficiency("Heavy Armor".to_string())
+            ]]))
+        );
+        character.race = "Dwarf".to_string();
+        assert!(can_take_feat(&character, &elven_accuracy).is_err());
+    }
+
+    #[test]
+    fn feats_are_only_taken_once() {
+        let mut character = adventurer();
+        assert_eq!(character.take_feat(feat("Alert", &[], &[])), Ok(()));
+        assert_eq!(
+            character.take_feat(feat("alert", &[], &[])),
+            Err(FeatError::AlreadyTaken)
+        );
+        assert_eq!(character.feats.len(), 1);
+
+        // Nothing's taken when the prerequisites aren't met.
+        let grappler = feat("Grappler", &["STR 13"], &[]);
+        assert!(character.take_feat(grappler).is_err());
+        assert_eq!(character.feats.len(), 1);
+    }
+
+    #[test]
+    fn feat_ability_increases_stop_at_20() {
+        let mut character = adventurer();
+        character.stats.insert(STR, 19);
+        character.stats.insert(CON, 12);
+        character
+            .take_feat(feat("Durable", &[], &["CON +1", "STR +2"]))
+            .unwrap();
+        assert_eq!(character.ability_score(&STR), 20);
+        assert_eq!(character.ability_score(&CON), 13);
+        assert_eq!(character.modifier(&CON), 1);
+
+        // Scores already above 20 aren't brought down.
+        character.stats.insert(STR, 22);
+        assert_eq!(character.ability_score(&STR), 22);
+
+        // Increases from several feats add up before the cap.
+        character
+            .take_feat(feat("Tough", &[], &["CON +6"]))
+            .unwrap();
+        character
+            .take_feat(feat("Hardy", &[], &["CON +6"]))
+            .unwrap();
+        assert_eq!(character.ability_score(&CON), 20);
+    }
+
+    #[test]
+    fn spell_slots_follow_the_caster_level() {
+        let slots = |class: &str, level: u8| {
+            let mut character = adventurer();
+            character.class = class.to_string();
+            character.level = level;
+            spell_slots(&character)
+        };
+        assert_eq!(slots("Wizard", 1), [2, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(slots("cleric", 5), [4, 3, 2, 0, 0, 0, 0, 0, 0]);
+        // Half casters have none at 1st level, then cast as half
+        // their level rounded up.
+        assert_eq!(slots("Paladin", 1), [0; 9]);
+        assert_eq!(slots("Paladin", 5), slots("Wizard", 3));
+        assert_eq!(slots("Fighter", 20), [0; 9]);
+        // Pact magic slots are all of one level.
+        assert_eq!(slots("Warlock", 1), [1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(slots("Warlock", 11), [0, 0, 0, 0, 3, 0, 0, 0, 0]);
+        assert_eq!(slots("Warlock", 20), [0, 0, 0, 0, 4, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,86 @@
+use crate::data::stats::Stats;
+use enum_iterator::IntoEnumIterator;
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, IntoEnumIterator)]
+pub enum Skill {
+    Acrobatics,
+    AnimalHandling,
+    Arcana,
+    Athletics,
+    Deception,
+    History,
+    Insight,
+    Intimidation,
+    Investigation,
+    Medicine,
+    Nature,
+    Perception,
+    Performance,
+    Persuasion,
+    Religion,
+    SleightOfHand,
+    Stealth,
+    Survival,
+}
+
+impl Skill {
+    pub fn get_all_skills() -> Vec<Skill> {
+        Skill::into_enum_iter().collect()
+    }
+
+    // The ability each skill is rolled with; see PHB p.174.
+    pub fn ability(&self) -> Stats {
+        match self {
+            Skill::Athletics => Stats::STR,
+            Skill::Acrobatics | Skill::SleightOfHand | Skill::Stealth => Stats::DEX,
+            Skill::Arcana
+            | Skill::History
+            | Skill::Investigation
+            | Skill::Nature
+            | Skill::Religion => Stats::INT,
+            Skill::AnimalHandling
+            | Skill::Insight
+            | Skill::Medicine
+            | Skill::Perception
+            | Skill::Survival => Stats::WIS,
+            Skill::Deception | Skill::Intimidation | Skill::Performance | Skill::Persuasion => {
+                Stats::CHA
+            }
+        }
+    }
+}
+
+impl fmt::Display for Skill {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Skill::Acrobatics => write!(f, "Acrobatics"),
+            Skill::AnimalHandling => write!(f, "Animal Handling"),
+            Skill::Arcana => write!(f, "Arcana"),
+            Skill::Athletics => write!(f, "Athletics"),
+            Skill::Deception => write!(f, "Deception"),
+            Skill::History => write!(f, "History"),
+            Skill::Insight => write!(f, "Insight"),
+            Skill::Intimidation => write!(f, "Intimidation"),
+            Skill::Investigation => write!(f, "Investigation"),
+            Skill::Medicine => write!(f, "Medicine"),
+            Skill::Nature => write!(f, "Nature"),
+            Skill::Perception => write!(f, "Perception"),
+            Skill::Performance => write!(f, "Performance"),
+            Skill::Persuasion => write!(f, "Persuasion"),
+            Skill::Religion => write!(f, "Religion"),
+            Skill::SleightOfHand => write!(f, "Sleight of Hand"),
+            Skill::Stealth => write!(f, "Stealth"),
+            Skill::Survival => write!(f, "Survival"),
+        }
+    }
+}
+
+impl FromStr for Skill {
+    type Err = ();
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Skill::into_enum_iter()
+            .find(|skill| skill.to_string() == input)
+            .ok_or(())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::data::character::Model;
+use crate::data::{catalog::SearchKind, character::Model};
 use rusqlite::{
     types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
     Result, Row,
@@ -169,4 +169,12 @@ impl Model for Spell {
     fn id(&self) -> Option<i64> {
         self.id
     }
+
+    fn search_kind() -> Option<SearchKind> {
+        Some(SearchKind::Spell)
+    }
+
+    fn search_text(&self) -> Option<(&str, &str)> {
+        Some((&self.name, &self.description))
+    }
 }
@@ -0,0 +1,523 @@
+use crate::data::{
+    background::Background,
+    class::Class,
+    equipment::StartingEquipment,
+    language::Language,
+    proficiency::{Proficiency, ProficiencyClass},
+    race::{Race, Subrace},
+    skills::Skill,
+    stats::Stats::{self, CHA, CON, DEX, INT, STR, WIS},
+};
+use std::str::FromStr;
+
+// Built-in races, classes and backgrounds from the PHB, so a new
+// character can be made before anything's been added to the
+// database. Only what character creation needs is filled in;
+// features are left to the player to add.
+
+pub fn proficiency(class: ProficiencyClass, name: &str) -> Proficiency {
+    Proficiency {
+        id: None,
+        name: Some(name.to_string()),
+        class: Some(class),
+    }
+}
+
+pub fn language(name: &str) -> Language {
+    Language {
+        id: None,
+        name: name.to_string(),
+        // Descriptions are unique in the languages table.
+        description: LANGUAGES
+            .iter()
+            .find(|(language, _)| *language == name)
+            .map(|(_, speakers)| format!("Typical speakers: {}", speakers))
+            .unwrap_or_else(|| name.to_string()),
+    }
+}
+
+fn proficiencies(class: ProficiencyClass, names: &[&str]) -> Vec<Proficiency> {
+    names
+        .iter()
+        .map(|name| proficiency(class.clone(), name))
+        .collect()
+}
+
+fn skills(skills: &[Skill]) -> Vec<Proficiency> {
+    skills
+        .iter()
+        .map(|skill| proficiency(ProficiencyClass::Skill, &skill.to_string()))
+        .collect()
+}
+
+fn languages(names: &[&str]) -> Vec<Language> {
+    names.iter().map(|name| language(name)).collect()
+}
+
+// The standard and exotic languages, and who typically
+// speaks them; see PHB p.123.
+const LANGUAGES: [(&str, &str); 16] = [
+    ("Common", "Humans"),
+    ("Dwarvish", "Dwarves"),
+    ("Elvish", "Elves"),
+    ("Giant", "Ogres, giants"),
+    ("Gnomish", "Gnomes"),
+    ("Goblin", "Goblinoids"),
+    ("Halfling", "Halflings"),
+    ("Orc", "Orcs"),
+    ("Abyssal", "Demons"),
+    ("Celestial", "Celestials"),
+    ("Draconic", "Dragons, dragonborn"),
+    ("Deep Speech", "Aboleths, cloakers"),
+    ("Infernal", "Devils"),
+    ("Primordial", "Elementals"),
+    ("Sylvan", "Fey creatures"),
+    ("Undercommon", "Underdark traders"),
+];
+
+pub fn all_languages() -> Vec<&'static str> {
+    LANGUAGES.iter().map(|(name, _)| *name).collect()
+}
+
+fn race(name: &str, speed: u8, increases: &[(Stats, u8)], spoken: &[&str]) -> Race {
+    Race {
+        name: name.to_string(),
+        speed,
+        ability_increases: increases.to_vec(),
+        languages: languages(spoken),
+        ..Race::default()
+    }
+}
+
+fn subrace(name: &str, increases: &[(Stats, u8)]) -> Subrace {
+    Subrace {
+        name: name.to_string(),
+        ability_increases: increases.to_vec(),
+        ..Subrace::default()
+    }
+}
+
+pub fn races() -> Vec<Race> {
+    let mut dwarf = race("Dwarf", 25, &[(CON, 2)], &["Common", "Dwarvish"]);
+    dwarf.proficiencies = proficiencies(
+        ProficiencyClass::Weapon,
+        &["Battleaxes", "Handaxes", "Light Hammers", "Warhammers"],
+    );
+    dwarf.subraces = vec![subrace("Hill Dwarf", &[(WIS, 1)])];
+    let mut mountain_dwarf = subrace("Mountain Dwarf", &[(STR, 2)]);
+    mountain_dwarf.proficiencies =
+        proficiencies(ProficiencyClass::Armor, &["Light Armor", "Medium Armor"]);
+    dwarf.subraces.push(mountain_dwarf);
+
+    let mut elf = race("Elf", 30, &[(DEX, 2)], &["Common", "Elvish"]);
+    elf.proficiencies = skills(&[Skill::Perception]);
+    let mut high_elf = subrace("High Elf", &[(INT, 1)]);
+    high_elf.proficiencies = proficiencies(
+        ProficiencyClass::Weapon,
+        &["Longswords", "Shortswords", "Shortbows", "Longbows"],
+    );
+    high_elf.language_choices = 1;
+    elf.subraces = vec![high_elf];
+
+    let mut halfling = race("Halfling", 25, &[(DEX, 2)], &["Common", "Halfling"]);
+    halfling.subraces = vec![
+        subrace("Lightfoot Halfling", &[(CHA, 1)]),
+        subrace("Stout Halfling", &[(CON, 1)]),
+    ];
+
+    let mut human = race(
+        "Human",
+        30,
+        &[(STR, 1), (DEX, 1), (CON, 1), (INT, 1), (WIS, 1), (CHA, 1)],
+        &["Common"],
+    );
+    human.language_choices = 1;
+
+    let dragonborn = race(
+        "Dragonborn",
+        30,
+        &[(STR, 2), (CHA, 1)],
+        &["Common", "Draconic"],
+    );
+
+    let mut gnome = race("Gnome", 25, &[(INT, 2)], &["Common", "Gnomish"]);
+    let mut rock_gnome = subrace("Rock Gnome", &[(CON, 1)]);
+    rock_gnome.proficiencies = proficiencies(ProficiencyClass::Tool, &["Tinker's Tools"]);
+    gnome.subraces = vec![rock_gnome];
+
+    let mut half_elf = race("Half-Elf", 30, &[(CHA, 2)], &["Common", "Elvish"]);
+    half_elf.ability_choices = 2;
+    half_elf.language_choices = 1;
+    half_elf.skill_choices = 2;
+
+    let mut half_orc = race("Half-Orc", 30, &[(STR, 2), (CON, 1)], &["Common", "Orc"]);
+    half_orc.proficiencies = skills(&[Skill::Intimidation]);
+
+    let tiefling = race(
+        "Tiefling",
+        30,
+        &[(CHA, 2), (INT, 1)],
+        &["Common", "Infernal"],
+    );
+
+    vec![
+        dwarf, elf, halfling, human, dragonborn, gnome, half_elf, half_orc, tiefling,
+    ]
+}
+
+fn class(
+    name: &str,
+    hit_die: u8,
+    saves: &[Stats],
+    skill_choices: u8,
+    skill_options: &[Skill],
+    equipment: &str,
+) -> Class {
+    Class {
+        name: name.to_string(),
+        hit_dice: (1, hit_die),
+        saving_throws: saves.iter().map(|stat| (*stat, true)).collect(),
+        skill_choices,
+        skill_options: skill_options.to_vec(),
+        starting_equipment: StartingEquipment::from_str(equipment).unwrap_or_default(),
+        ..Class::default()
+    }
+}
+
+pub fn classes() -> Vec<Class> {
+    use ProficiencyClass::{Armor, Tool, Weapon};
+    use Skill::*;
+
+    let all_armor = ["Light Armor", "Medium Armor", "Heavy Armor", "Shields"];
+    let martial = ["Simple Weapons", "Martial Weapons"];
+
+    let mut barbarian = class(
+        "Barbarian",
+        12,
+        &[STR, CON],
+        2,
+        &[
+            AnimalHandling,
+            Athletics,
+            Intimidation,
+            Nature,
+            Perception,
+            Survival,
+        ],
+        "Greataxe or any martial melee weapon; 2 Handaxes or any simple weapon; \
+        Explorer's Pack and 4 Javelins",
+    );
+    barbarian.proficiencies = proficiencies(Armor, &["Light Armor", "Medium Armor", "Shields"]);
+    barbarian
+        .proficiencies
+        .extend(proficiencies(Weapon, &martial));
+
+    let mut bard = class(
+        "Bard",
+        8,
+        &[DEX, CHA],
+        3,
+        &Skill::get_all_skills(),
+        "Rapier or Longsword or any simple weapon; Diplomat's Pack or Entertainer's Pack; \
+        Lute or any musical instrument; Leather Armor and Dagger",
+    );
+    bard.proficiencies = proficiencies(Armor, &["Light Armor"]);
+    bard.proficiencies.extend(proficiencies(
+        Weapon,
+        &[
+            "Simple Weapons",
+            "Hand Crossbows",
+            "Longswords",
+            "Rapiers",
+            "Shortswords",
+        ],
+    ));
+
+    let mut cleric = class(
+        "Cleric",
+        8,
+        &[WIS, CHA],
+        2,
+        &[History, Insight, Medicine, Persuasion, Religion],
+        "Mace or Warhammer; Scale Mail or Leather Armor or Chain Mail; \
+        Light Crossbow and 20 Crossbow Bolts or any simple weapon; \
+        Priest's Pack or Explorer's Pack; Shield and Holy Symbol",
+    );
+    cleric.proficiencies = proficiencies(Armor, &["Light Armor", "Medium Armor", "Shields"]);
+    cleric
+        .proficiencies
+        .extend(proficiencies(Weapon, &["Simple Weapons"]));
+
+    let mut druid = class(
+        "Druid",
+        8,
+        &[INT, WIS],
+        2,
+        &[
+            Arcana,
+            AnimalHandling,
+            Insight,
+            Medicine,
+            Nature,
+            Perception,
+            Religion,
+            Survival,
+        ],
+        "Shield or any simple weapon; Scimitar or any simple melee weapon; \
+        Leather Armor and Explorer's Pack and Druidic Focus",
+    );
+    druid.proficiencies = proficiencies(Armor, &["Light Armor", "Medium Armor", "Shields"]);
+    druid.proficiencies.extend(proficiencies(
+        Weapon,
+        &[
+            "Clubs",
+            "Daggers",
+            "Darts",
+            "Javelins",
+            "Maces",
+            "Quarterstaffs",
+            "Scimitars",
+            "Sickles",
+            "Slings",
+            "Spears",
+        ],
+    ));
+    druid
+        .proficiencies
+        .extend(proficiencies(Tool, &["Herbalism Kit"]));
+
+    let mut fighter = class(
+        "Fighter",
+        10,
+        &[STR, CON],
+        2,
+        &[
+            Acrobatics,
+            AnimalHandling,
+            Athletics,
+            History,
+            Insight,
+            Intimidation,
+            Perception,
+            Survival,
+        ],
+        "Chain Mail or Leather Armor and Longbow and 20 Arrows; \
+        any martial weapon and Shield or 2 any martial weapon; \
+        Light Crossbow and 20 Crossbow Bolts or 2 Handaxes; \
+        Dungeoneer's Pack or Explorer's Pack",
+    );
+    fighter.proficiencies = proficiencies(Armor, &all_armor);
+    fighter
+        .proficiencies
+        .extend(proficiencies(Weapon, &martial));
+
+    let mut monk = class(
+        "Monk",
+        8,
+        &[STR, DEX],
+        2,
+        &[Acrobatics, Athletics, History, Insight, Religion, Stealth],
+        "Shortsword or any simple weapon; Dungeoneer's Pack or Explorer's Pack; 10 Darts",
+    );
+    monk.proficiencies = proficiencies(Weapon, &["Simple Weapons", "Shortswords"]);
+
+    let mut paladin = class(
+        "Paladin",
+        10,
+        &[WIS, CHA],
+        2,
+        &[
+            Athletics,
+            Insight,
+            Intimidation,
+            Medicine,
+            Persuasion,
+            Religion,
+        ],
+        "any martial weapon and Shield or 2 any martial weapon; \
+        5 Javelins or any simple melee weapon; Priest's Pack or Explorer's Pack; \
+        Chain Mail and Holy Symbol",
+    );
+    paladin.proficiencies = proficiencies(Armor, &all_armor);
+    paladin
+        .proficiencies
+        .extend(proficiencies(Weapon, &martial));
+
+    let mut ranger = class(
+        "Ranger",
+        10,
+        &[STR, DEX],
+        3,
+        &[
+            AnimalHandling,
+            Athletics,
+            Insight,
+            Investigation,
+            Nature,
+            Perception,
+            Stealth,
+            Survival,
+        ],
+        "Scale Mail or Leather Armor; 2 Shortswords or 2 any simple melee weapon; \
+        Dungeoneer's Pack or Explorer's Pack; Longbow and 20 Arrows",
+    );
+    ranger.proficiencies = proficiencies(Armor, &["Light Armor", "Medium Armor", "Shields"]);
+    ranger.proficiencies.extend(proficiencies(Weapon, &martial));
+
+    let mut rogue = class(
+        "Rogue",
+        8,
+        &[DEX, INT],
+        4,
+        &[
+            Acrobatics,
+            Athletics,
+            Deception,
+            Insight,
+            Intimidation,
+            Investigation,
+            Perception,
+            Performance,
+            Persuasion,
+            SleightOfHand,
+            Stealth,
+        ],
+        "Rapier or Shortsword; Shortbow and 20 Arrows or Shortsword; \
+        Burglar's Pack or Dungeoneer's Pack or Explorer's Pack; \
+        Leather Armor and 2 Daggers and Thieves' Tools",
+    );
+    rogue.proficiencies = proficiencies(Armor, &["Light Armor"]);
+    rogue.proficiencies.extend(proficiencies(
+        Weapon,
+        &[
+            "Simple Weapons",
+            "Hand Crossbows",
+            "Longswords",
+            "Rapiers",
+            "Shortswords",
+        ],
+    ));
+    rogue
+        .proficiencies
+        .extend(proficiencies(Tool, &["Thieves' Tools"]));
+
+    let caster_weapons = [
+        "Daggers",
+        "Darts",
+        "Slings",
+        "Quarterstaffs",
+        "Light Crossbows",
+    ];
+
+    let mut sorcerer = class(
+        "Sorcerer",
+        6,
+        &[CON, CHA],
+        2,
+        &[
+            Arcana,
+            Deception,
+            Insight,
+            Intimidation,
+            Persuasion,
+            Religion,
+        ],
+        "Light Crossbow and 20 Crossbow Bolts or any simple weapon; \
+        Component Pouch or Arcane Focus; Dungeoneer's Pack or Explorer's Pack; 2 Daggers",
+    );
+    sorcerer.proficiencies = proficiencies(Weapon, &caster_weapons);
+
+    let mut warlock = class(
+        "Warlock",
+        8,
+        &[WIS, CHA],
+        2,
+        &[
+            Arcana,
+            Deception,
+            History,
+            Intimidation,
+            Investigation,
+            Nature,
+            Religion,
+        ],
+        "Light Crossbow and 20 Crossbow Bolts or any simple weapon; \
+        Component Pouch or Arcane Focus; Scholar's Pack or Dungeoneer's Pack; \
+        Leather Armor and any simple weapon and 2 Daggers",
+    );
+    warlock.proficiencies = proficiencies(Armor, &["Light Armor"]);
+    warlock
+        .proficiencies
+        .extend(proficiencies(Weapon, &["Simple Weapons"]));
+
+    let mut wizard = class(
+        "Wizard",
+        6,
+        &[INT, WIS],
+        2,
+        &[Arcana, History, Insight, Investigation, Medicine, Religion],
+        "Quarterstaff or Dagger; Component Pouch or Arcane Focus; \
+        Scholar's Pack or Explorer's Pack; Spellbook",
+    );
+    wizard.proficiencies = proficiencies(Weapon, &caster_weapons);
+
+    vec![
+        barbarian, bard, cleric, druid, fighter, monk, paladin, ranger, rogue, sorcerer, warlock,
+        wizard,
+    ]
+}
+
+fn background(name: &str, skill_proficiencies: &[Skill], equipment: &str) -> Background {
+    Background {
+        name: name.to_string(),
+        proficiencies: Some(skills(skill_proficiencies)),
+        starting_equipment: StartingEquipment::from_str(equipment).unwrap_or_default(),
+        ..Background::default()
+    }
+}
+
+pub fn backgrounds() -> Vec<Background> {
+    let mut acolyte = background(
+        "Acolyte",
+        &[Skill::Insight, Skill::Religion],
+        "Holy Symbol; Prayer Book; 5 Sticks of Incense; Vestments; Common Clothes; Pouch",
+    );
+    acolyte.language_choices = 2;
+
+    let mut criminal = background(
+        "Criminal",
+        &[Skill::Deception, Skill::Stealth],
+        "Crowbar; Common Clothes; Pouch",
+    );
+    if let Some(proficiencies) = criminal.proficiencies.as_mut() {
+        proficiencies.push(proficiency(ProficiencyClass::Tool, "Thieves' Tools"));
+    }
+
+    let folk_hero = background(
+        "Folk Hero",
+        &[Skill::AnimalHandling, Skill::Survival],
+        "any artisan's tools; Shovel; Iron Pot; Common Clothes; Pouch",
+    );
+
+    let mut noble = background(
+        "Noble",
+        &[Skill::History, Skill::Persuasion],
+        "Fine Clothes; Signet Ring; Scroll of Pedigree; Pouch",
+    );
+    noble.language_choices = 1;
+
+    let mut sage = background(
+        "Sage",
+        &[Skill::Arcana, Skill::History],
+        "Bottle of Black Ink; Quill; Small Knife; Common Clothes; Pouch",
+    );
+    sage.language_choices = 2;
+
+    let soldier = background(
+        "Soldier",
+        &[Skill::Athletics, Skill::Intimidation],
+        "Insignia of Rank; Set of Bone Dice or Deck of Cards; Common Clothes; Pouch",
+    );
+
+    vec![acolyte, criminal, folk_hero, noble, sage, soldier]
+}
@@ -1,6 +1,6 @@
 use std::{fmt, str::FromStr};
 
-#[derive(Eq, PartialEq, Hash, Clone)]
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
 pub enum Stats {
     STR,
     DEX,
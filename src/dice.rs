@@ -0,0 +1,588 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{error::Error, fmt, str::FromStr};
+
+// Dice expressions in the usual tabletop notation:
+//
+//   2d6+3       two d6 plus three
+//   1d20adv     a d20 with advantage (roll two, keep the highest),
+//               which can also be written "1d20 adv"
+//   1d20dis     a d20 with disadvantage (roll two, keep the lowest)
+//   4d6kh3      four d6, keeping the highest three (kl keeps the lowest)
+//   8d6 fire    eight d6 of fire damage
+//
+// Any number of dice groups and flat modifiers can be added or
+// subtracted, ie. "1d8+1d6-1". A trailing word is the damage type.
+
+const MAX_DICE: u32 = 1000;
+const MAX_SIDES: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Keep {
+    Highest(u32),
+    Lowest(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dice {
+    pub count: u32,
+    pub sides: u32,
+    pub keep: Option<Keep>,
+    pub negative: bool,
+}
+
+impl fmt::Display for Dice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.keep {
+            // Advantage and disadvantage are written the way they're
+            // usually entered rather than as 2d20kh1.
+            Some(Keep::Highest(1)) if self.count == 2 && self.sides == 20 => write!(f, "1d20adv"),
+            Some(Keep::Lowest(1)) if self.count == 2 && self.sides == 20 => write!(f, "1d20dis"),
+            Some(Keep::Highest(keep)) => write!(f, "{}d{}kh{}", self.count, self.sides, keep),
+            Some(Keep::Lowest(keep)) => write!(f, "{}d{}kl{}", self.count, self.sides, keep),
+            None => write!(f, "{}d{}", self.count, self.sides),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    Dice(Dice),
+    Modifier(i32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiceExpression {
+    pub terms: Vec<Term>,
+    pub damage_type: Option<String>,
+}
+
+impl DiceExpression {
+    // The sum of every flat modifier in the expression, or None
+    // if it's too large to add up, which parsing rejects.
+    pub fn modifier(&self) -> Option<i32> {
+        self.terms.iter().try_fold(0i32, |sum, term| match term {
+            Term::Modifier(modifier) => sum.checked_add(*modifier),
+            Term::Dice(_) => Some(sum),
+        })
+    }
+}
+
+impl fmt::Display for DiceExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, term) in self.terms.iter().enumerate() {
+            match term {
+                Term::Dice(dice) if dice.negative => write!(f, "-{}", dice)?,
+                Term::Dice(dice) if i > 0 => write!(f, "+{}", dice)?,
+                Term::Dice(dice) => write!(f, "{}", dice)?,
+                Term::Modifier(modifier) if i > 0 => write!(f, "{:+}", modifier)?,
+                Term::Modifier(modifier) => write!(f, "{}", modifier)?,
+            }
+        }
+        if let Some(damage_type) = &self.damage_type {
+            write!(f, " {}", damage_type)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiceError {
+    pub input: String,
+    pub reason: String,
+}
+
+impl fmt::Display for DiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid dice expression \"{}\": {}",
+            self.input, self.reason
+        )
+    }
+}
+
+impl Error for DiceError {}
+
+impl FromStr for DiceExpression {
+    type Err = DiceError;
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let error = |reason: &str| DiceError {
+            input: input.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let (dice, damage_type) = split_damage_type(input.trim());
+        // Spaces are allowed around + and - and before adv or dis,
+        // but not inside a term.
+        let words = dice.split_whitespace().collect::<Vec<_>>();
+        for pair in words.windows(2) {
+            if !pair[0].ends_with(['+', '-'])
+                && !pair[1].starts_with(['+', '-'])
+                && !is_advantage(pair[1])
+            {
+                return Err(error("expected + or - between terms"));
+            }
+        }
+        let chars = dice
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| c.to_ascii_lowercase())
+            .collect::<Vec<_>>();
+        if chars.is_empty() {
+            return Err(error("expression is empty"));
+        }
+
+        let mut terms = Vec::new();
+        let mut pos = 0;
+        while pos < chars.len() {
+            let negative = match chars[pos] {
+                '+' if !terms.is_empty() => {
+                    pos += 1;
+                    false
+                }
+                '-' => {
+                    pos += 1;
+                    true
+                }
+                _ if terms.is_empty() => false,
+                c => return Err(error(format!("expected + or - but found '{}'", c).as_str())),
+            };
+
+            let count = read_number(&chars, &mut pos);
+            if chars.get(pos) != Some(&'d') {
+                let modifier = count.ok_or_else(|| error("expected a number or dice"))?;
+                let modifier =
+                    i32::try_from(modifier).map_err(|_| error("modifier is too large"))?;
+                terms.push(Term::Modifier(if negative { -modifier } else { modifier }));
+                continue;
+            }
+
+            pos += 1;
+            let count = count.unwrap_or(1);
+            let sides = read_number(&chars, &mut pos).ok_or_else(|| error("dice need sides"))?;
+            if count == 0 || sides == 0 {
+                return Err(error("dice need at least one die with at least one side"));
+            }
+            if count > MAX_DICE || sides > MAX_SIDES {
+                return Err(error("too many dice or sides"));
+            }
+
+            let suffix = chars[pos..]
+                .iter()
+                .take_while(|c| c.is_ascii_alphabetic())
+                .collect::<String>();
+            pos += suffix.len();
+            let (count, keep) = match suffix.as_str() {
+                "" => (count, None),
+                "adv" | "dis" if count != 1 => {
+                    return Err(error(
+                        "advantage and disadvantage only apply to a single die",
+                    ))
+                }
+                "adv" => (2, Some(Keep::Highest(1))),
+                "dis" => (2, Some(Keep::Lowest(1))),
+                "kh" | "k" | "kl" => {
+                    let keep = read_number(&chars, &mut pos)
+                        .ok_or_else(|| error("keep needs a number of dice"))?;
+                    if keep == 0 || keep > count {
+                        return Err(error("can't keep more dice than are rolled"));
+                    }
+                    match suffix.as_str() {
+                        "kl" => (count, Some(Keep::Lowest(keep))),
+                        _ => (count, Some(Keep::Highest(keep))),
+                    }
+                }
+                other => return Err(error(format!("unknown suffix '{}'", other).as_str())),
+            };
+
+            terms.push(Term::Dice(Dice {
+                count,
+                sides,
+                keep,
+                negative,
+            }));
+        }
+
+        let expression = DiceExpression { terms, damage_type };
+        expression
+            .modifier()
+            .ok_or_else(|| error("modifiers are too large"))?;
+        Ok(expression)
+    }
+}
+
+fn is_advantage(word: &str) -> bool {
+    word.eq_ignore_ascii_case("adv") || word.eq_ignore_ascii_case("dis")
+}
+
+fn split_damage_type(input: &str) -> (&str, Option<String>) {
+    // Trailing words made up only of letters are the damage type,
+    // apart from adv and dis, which belong to the dice before them.
+    let mut dice = input;
+    while let Some((rest, word)) = dice.rsplit_once(char::is_whitespace) {
+        if word.is_empty() || !word.chars().all(|c| c.is_ascii_alphabetic()) || is_advantage(word) {
+            break;
+        }
+        dice = rest.trim_end();
+    }
+    match input[dice.len()..].trim() {
+        "" => (dice, None),
+        damage_type => (dice, Some(damage_type.to_string())),
+    }
+}
+
+fn read_number(chars: &[char], pos: &mut usize) -> Option<u32> {
+    let digits = chars[*pos..]
+        .iter()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>();
+    *pos += digits.len();
+    digits.parse().ok()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DieRoll {
+    pub sides: u32,
+    pub value: u32,
+    // Whether the die counts toward the total; dice
+    // dropped by kh/kl, advantage or disadvantage don't.
+    pub kept: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiceRoll {
+    pub dice: Dice,
+    pub rolls: Vec<DieRoll>,
+    pub subtotal: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollResult {
+    pub expression: DiceExpression,
+    pub groups: Vec<DiceRoll>,
+    pub modifier: i32,
+    pub total: i32,
+}
+
+impl RollResult {
+    // The kept result of the first d20 in the roll, for spotting
+    // natural 1s and 20s on checks, saves and attacks.
+    pub fn natural_d20(&self) -> Option<u32> {
+        self.groups
+            .iter()
+            .flat_map(|group| group.rolls.iter())
+            .find(|roll| roll.sides == 20 && roll.kept)
+            .map(|roll| roll.value)
+    }
+
+    // Every die rolled, ie. "[4, 2] + 3"; dropped dice are
+    // shown in parentheses, ie. "[6, 5, 3, (1)]".
+    pub fn breakdown(&self) -> String {
+        let mut breakdown = String::new();
+        for (i, group) in self.groups.iter().enumerate() {
+            let rolls = group
+                .rolls
+                .iter()
+                .map(|roll| match roll.kept {
+                    true => roll.value.to_string(),
+                    false => format!("({})", roll.value),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            match (i, group.dice.negative) {
+                (0, true) => breakdown.push_str(format!("-[{}]", rolls).as_str()),
+                (_, true) => breakdown.push_str(format!(" - [{}]", rolls).as_str()),
+                (0, false) => breakdown.push_str(format!("[{}]", rolls).as_str()),
+                (_, false) => breakdown.push_str(format!(" + [{}]", rolls).as_str()),
+            }
+        }
+        match (self.groups.is_empty(), self.modifier) {
+            (_, 0) if !self.groups.is_empty() => {}
+            (true, modifier) => breakdown.push_str(modifier.to_string().as_str()),
+            (false, modifier) if modifier < 0 => {
+                breakdown.push_str(format!(" - {}", -modifier).as_str())
+            }
+            (false, modifier) => breakdown.push_str(format!(" + {}", modifier).as_str()),
+        }
+        breakdown
+    }
+}
+
+impl fmt::Display for RollResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} = {}",
+            self.expression,
+            self.breakdown(),
+            self.total
+        )
+    }
+}
+
+// Rolls dice expressions. A Roller made with seeded() always
+// produces the same rolls for the same seed, so anything built
+// on top of it can be checked deterministically.
+pub struct Roller {
+    rng: StdRng,
+}
+
+impl Roller {
+    pub fn new() -> Self {
+        Roller {
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn seeded(seed: u64) -> Self {
+        Roller {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn roll(&mut self, expression: &DiceExpression) -> RollResult {
+        let mut groups = Vec::new();
+        for term in &expression.terms {
+            if let Term::Dice(dice) = term {
+                groups.push(self.roll_dice(dice));
+            }
+        }
+        // Parsing makes sure the modifiers add up.
+        let modifier = expression.modifier().unwrap_or_default();
+        RollResult {
+            expression: expression.clone(),
+            total: groups
+                .iter()
+                .map(|group| group.subtotal)
+                .sum::<i32>()
+                .saturating_add(modifier),
+            groups,
+            modifier,
+        }
+    }
+
+    fn roll_dice(&mut self, dice: &Dice) -> DiceRoll {
+        let mut rolls = (0..dice.count)
+            .map(|_| DieRoll {
+                sides: dice.sides,
+                value: self.rng.gen_range(1..=dice.sides),
+                kept: true,
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(keep) = dice.keep {
+            // Sort the indices of the dice by value so the dropped dice
+            // can be marked while the rolls stay in the order they were made.
+            let mut order = (0..rolls.len()).collect::<Vec<_>>();
+            order.sort_by_key(|&i| rolls[i].value);
+            let dropped = match keep {
+                Keep::Highest(n) => order[..rolls.len() - n as usize].to_vec(),
+                Keep::Lowest(n) => order[n as usize..].to_vec(),
+            };
+            for i in dropped {
+                rolls[i].kept = false;
+            }
+        }
+
+        let sum = rolls
+            .iter()
+            .filter(|roll| roll.kept)
+            .map(|roll| roll.value as i32)
+            .sum::<i32>();
+        DiceRoll {
+            dice: dice.clone(),
+            rolls,
+            subtotal: if dice.negative { -sum } else { sum },
+        }
+    }
+}
+
+impl Default for Roller {
+    fn default() -> Self {
+        Roller::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> DiceExpression {
+        DiceExpression::from_str(input).unwrap()
+    }
+
+    fn roll(roller: &mut Roller, input: &str) -> RollResult {
+        roller.roll(&parse(input))
+    }
+
+    fn dice(count: u32, sides: u32, keep: Option<Keep>) -> Term {
+        Term::Dice(Dice {
+            count,
+            sides,
+            keep,
+            negative: false,
+        })
+    }
+
+    #[test]
+    fn parses_dice_and_modifiers() {
+        assert_eq!(
+            parse("2d6+3").terms,
+            vec![dice(2, 6, None), Term::Modifier(3)]
+        );
+        assert_eq!(parse("d20").terms, vec![dice(1, 20, None)]);
+        assert_eq!(
+            parse("4d6kh3").terms,
+            vec![dice(4, 6, Some(Keep::Highest(3)))]
+        );
+        assert_eq!(
+            parse("2d20kl1").terms,
+            vec![dice(2, 20, Some(Keep::Lowest(1)))]
+        );
+        assert_eq!(
+            parse("1d20adv").terms,
+            vec![dice(2, 20, Some(Keep::Highest(1)))]
+        );
+        assert_eq!(
+            parse("1d20dis").terms,
+            vec![dice(2, 20, Some(Keep::Lowest(1)))]
+        );
+
+        let expression = parse("1d8-1d6-1");
+        assert_eq!(expression.modifier(), Some(-1));
+        assert_eq!(
+            expression.terms[1],
+            Term::Dice(Dice {
+                count: 1,
+                sides: 6,
+                keep: None,
+                negative: true,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_whitespace_and_damage_types() {
+        assert_eq!(parse("  2d6 + 3 "), parse("2d6+3"));
+        assert_eq!(parse("2D6+3"), parse("2d6+3"));
+
+        let fire = parse("8d6 fire");
+        assert_eq!(fire.terms, vec![dice(8, 6, None)]);
+        assert_eq!(fire.damage_type.as_deref(), Some("fire"));
+        assert_eq!(
+            parse("1d6 + 2 cold iron").damage_type.as_deref(),
+            Some("cold iron")
+        );
+        assert_eq!(fire.to_string(), "8d6 fire");
+        // Advantage can be written apart from its die, and is
+        // never taken for a damage type.
+        assert_eq!(parse("1d20 adv"), parse("1d20adv"));
+        assert_eq!(parse("1d20 DIS"), parse("1d20dis"));
+        let advantage = parse("1d20 adv fire");
+        assert_eq!(advantage.terms, parse("1d20adv").terms);
+        assert_eq!(advantage.damage_type.as_deref(), Some("fire"));
+    }
+
+    #[test]
+    fn rejects_bad_expressions() {
+        for input in [
+            "",
+            "   ",
+            "d",
+            "2d",
+            "0d6",
+            "2d0",
+            "2d6+",
+            "2d6 3",
+            "2x6",
+            "3d20adv",
+            "2d6kh3",
+            "2d6kh0",
+            "2d6q",
+            "5000d6",
+            "1d5000",
+            "2d6 adv",
+            "1d20 + adv",
+        ] {
+            assert!(DiceExpression::from_str(input).is_err(), "{:?}", input);
+        }
+        let error = DiceExpression::from_str("2d6 3").unwrap_err();
+        assert_eq!(error.input, "2d6 3");
+        assert_eq!(
+            error.to_string(),
+            "Invalid dice expression \"2d6 3\": expected + or - between terms"
+        );
+        // Each modifier fits, but not their sum.
+        assert_eq!(
+            DiceExpression::from_str("2147483647+1").unwrap_err().reason,
+            "modifiers are too large"
+        );
+        assert!(DiceExpression::from_str("2147483647-1+1").is_ok());
+    }
+
+    #[test]
+    fn totals_are_within_the_dice_range() {
+        let mut roller = Roller::seeded(7);
+        for (input, min, max) in [
+            ("2d6+3", 5, 15),
+            ("d20", 1, 20),
+            ("4d6kh3", 3, 18),
+            ("1d20dis-2", -1, 18),
+            ("1d4-1d4", -3, 3),
+        ] {
+            for _ in 0..200 {
+                let result = roll(&mut roller, input);
+                assert!((min..=max).contains(&result.total), "{}", result);
+            }
+        }
+    }
+
+    #[test]
+    fn kept_dice_make_up_the_total() {
+        let mut roller = Roller::seeded(11);
+        for _ in 0..100 {
+            let result = roll(&mut roller, "4d6kh3");
+            let rolls = &result.groups[0].rolls;
+            assert_eq!(rolls.len(), 4);
+            assert_eq!(rolls.iter().filter(|roll| roll.kept).count(), 3);
+            // The die dropped is never higher than one kept.
+            let dropped = rolls.iter().find(|roll| !roll.kept).unwrap().value;
+            assert!(rolls
+                .iter()
+                .filter(|roll| roll.kept)
+                .all(|roll| roll.value >= dropped));
+            let kept: u32 = rolls
+                .iter()
+                .filter(|roll| roll.kept)
+                .map(|roll| roll.value)
+                .sum();
+            assert_eq!(result.total, kept as i32);
+        }
+    }
+
+    #[test]
+    fn seeded_rollers_roll_the_same_every_time() {
+        let rolls = |seed| {
+            let mut roller = Roller::seeded(seed);
+            (0..20)
+                .map(|_| roll(&mut roller, "4d6kh3+1d20adv-2"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rolls(42), rolls(42));
+        assert_ne!(rolls(42), rolls(43));
+
+        let mut roller = Roller::seeded(42);
+        let result = roll(&mut roller, "2d6+3");
+        let dice: Vec<u32> = result.groups[0]
+            .rolls
+            .iter()
+            .map(|roll| roll.value)
+            .collect();
+        assert_eq!(result.total, dice.iter().sum::<u32>() as i32 + 3);
+        assert_eq!(
+            result.to_string(),
+            format!("2d6+3: [{}, {}] + 3 = {}", dice[0], dice[1], result.total)
+        );
+    }
+}
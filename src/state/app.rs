@@ -1,17 +1,39 @@
 use crate::{
-    state::{character_sheet, select_screen},
-    data::character::SavedCharacter,
-    Character,
-    Database
+    config::Config,
+    data::{
+        catalog::{CatalogEntry, SearchHit},
+        character::SavedCharacter,
+        items::Item,
+        query::Query,
+        rolls::{Roll, RollStatistics},
+    },
+    state::{character_sheet, creation_wizard, paged::PAGE_SIZE, select_screen},
+    Character, Database,
 };
 use anyhow::Result;
-use crossterm::event::{read, Event, KeyEvent};
-use std::io::{stdout, Stdout, Write};
+use crossterm::event::{poll, read, Event, KeyEvent, MouseEvent};
+use std::time::{Duration, Instant};
+use tui::{backend::Backend, Frame, Terminal};
 use HandleKeyboardInput::*;
 use States::*;
 
 pub enum HandleKeyboardInput {
     ChangeState(States),
+    SaveRoll(Roll),
+    SaveCharacter(Box<Character>),
+    WriteCharacter(Box<Character>),
+    DeleteCharacter(i64),
+    DuplicateCharacter(i64),
+    RenameCharacter(i64, String),
+    // Opens the search palette over the state.
+    Search,
+    // Finds what the search palette's query matches,
+    // by name and in descriptions.
+    SearchCatalog(String),
+    // Loads a page of the roll history.
+    LoadRolls(Query<Roll>),
+    // Loads what each query finds of the item catalog.
+    LoadItems(Vec<Query<Item>>),
     Input,
     Void,
     Exit,
@@ -20,34 +42,45 @@ pub enum HandleKeyboardInput {
 pub enum States {
     SelectScreen,
     CharacterSheet(SavedCharacter),
+    CreationWizard,
 }
 
 // All the information needed for any state
 // of the application is held in the App.
-pub struct App {
+pub struct App<B: Backend> {
     saved_characters: Vec<SavedCharacter>,
     current_character: Option<Character>,
-    state: Option<Box<dyn State>>,
-    stdout: Stdout,
+    state: Option<Box<dyn State<B>>>,
+    // Owned for the app's lifetime, so tui only redraws
+    // what changed between frames.
+    terminal: Terminal<B>,
+    // Passed on to states, ie. the keymap each one matches keys
+    // with and the theme they're drawn in.
+    config: Config,
     pub db: Database,
 }
 
-impl App {
-    pub fn new(db: Database) -> Result<App> {
+// How often states are ticked when there's no input, for
+// anything on screen that changes without the user doing anything.
+const TICK_RATE: Duration = Duration::from_millis(250);
+
+impl<B: Backend> App<B> {
+    pub fn new(db: Database, config: Config, terminal: Terminal<B>) -> Result<App<B>> {
         let mut not_self = App {
             saved_characters: Vec::new(),
             current_character: None,
             state: None,
-            stdout: stdout(),
+            terminal,
+            config,
             db,
         };
 
         // Calls on the db are made at instantiation;
-        // the .create_character_table() method will
+        // the .create_tables() method will
         // never make duplicate tables. It will check
         // for an existing table internally, and if
         // it does not exist, it will create one.
-        not_self.db.create_character_table()?;
+        not_self.db.create_tables()?;
 
         // .list_all_characters() returns a Vector of SavedCharacter
         // structs; a lightweight representation of each character saved
@@ -60,6 +93,8 @@ impl App {
         // The saved_characters vector is then passed as its new() argument.
         not_self.state = Some(Box::new(select_screen::SelectScreen::new(
             not_self.saved_characters.clone(),
+            not_self.config.keymap.clone(),
+            not_self.config.theme,
         )));
         Ok(not_self)
     }
@@ -70,6 +105,15 @@ impl App {
             SelectScreen => {
                 self.state = Some(Box::new(select_screen::SelectScreen::new(
                     self.saved_characters.clone(),
+                    self.config.keymap.clone(),
+                    self.config.theme,
+                )))
+            }
+            // The creation wizard asks for the items it needs from the
+            // catalog once it gets to the starting equipment.
+            CreationWizard => {
+                self.state = Some(Box::new(creation_wizard::CreationWizard::new(
+                    self.config.theme,
                 )))
             }
             // When changing to the CharacterScreen state, we're provided
@@ -89,11 +133,13 @@ impl App {
                 // Now we change the current state to CharacterScreen and pass the
                 // current_character as its argument.
                 //
-                // .clone() is called on the current_character struct and unwrap_or
+                // .clone() is called on the current_character struct and unwrap_or_default
                 // to account for the Option. If it's None, then we give it a
                 // blank Character struct instead (this scinario should never happen).
                 self.state = Some(Box::new(character_sheet::CharacterSheet::new(
-                    self.current_character.clone().unwrap_or(Character::new()),
+                    self.current_character.clone().unwrap_or_default(),
+                    self.config.keymap.clone(),
+                    self.config.theme,
                 )));
             }
         }
@@ -103,13 +149,15 @@ impl App {
     pub fn display_screen(&mut self) -> Result<()> {
         // Each state has its own display_screen method, which is private.
         // Everything is called through App.
+        // The whole screen is drawn each time; tui compares it
+        // with the last frame and only writes what changed.
         if let Some(state) = &mut self.state {
-            state.display_screen(&mut self.stdout)?;
+            self.terminal.draw(|f| state.display_screen(f))?;
         }
         Ok(())
     }
 
-    pub fn handle_input(&mut self) -> Result<()> {
+    pub fn run(&mut self) -> Result<()> {
         // This is effectively the main program loop. We listen for
         // user input here in the form of crossterm events. Input is classified
         // as Key(KetEvent), Mouse(MouseEvent), and Resize(u16, u16) (for terminal resizing)
@@ -119,22 +167,32 @@ impl App {
         // Each handle method returns an enum corresponding to the event type, which
         // is handled by App, rather than the states, as the states do not have access
         // to all the information needed to handle every scinario, but App does.
+        //
+        // Rather than blocking on input, the loop waits at most TICK_RATE for it,
+        // ticking the state whenever nothing happened in that time.
+        self.display_screen()?;
+        let mut last_tick = Instant::now();
         loop {
-            self.stdout.flush()?;
-            match read()? {
-                Event::Key(event) => {
-                    if let Some(state) = &mut self.state {
-                        match state.handle_keyboard_event(&mut self.stdout, event)? {
-                            Input => {}
-                            Void => {}
-                            Exit => break,
-                            ChangeState(state) => {
-                                self.change_state(state)?;
-                            }
-                        }
-                    }
+            let timeout = TICK_RATE
+                .checked_sub(last_tick.elapsed())
+                .unwrap_or(Duration::ZERO);
+            if poll(timeout)? {
+                let exit = match read()? {
+                    Event::Key(event) => self.handle_keyboard_event(event)?,
+                    Event::Mouse(event) => self.handle_mouse_event(event)?,
+                    // tui resizes its buffers to the new size on the next draw,
+                    // so redrawing is all that's needed.
+                    Event::Resize(_, _) => false,
+                };
+                if exit {
+                    break;
                 }
-                _ => {}
+            }
+            if last_tick.elapsed() >= TICK_RATE {
+                if let Some(state) = &mut self.state {
+                    state.on_tick();
+                }
+                last_tick = Instant::now();
             }
             // Display screen is always called after any input is detected and handled
             // to account for any new changes in the display of the state.
@@ -142,13 +200,181 @@ impl App {
         }
         Ok(())
     }
+
+    // Returns whether the user asked to exit.
+    fn handle_keyboard_event(&mut self, event: KeyEvent) -> Result<bool> {
+        match &mut self.state {
+            Some(state) => {
+                let input = state.handle_keyboard_event(event)?;
+                self.handle_input(input)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // Clicks and scrolling are only sent when mouse capture is on
+    // in the config, and are handled the same way as keys.
+    fn handle_mouse_event(&mut self, event: MouseEvent) -> Result<bool> {
+        match &mut self.state {
+            Some(state) => {
+                let input = state.handle_mouse_event(event)?;
+                self.handle_input(input)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // Does whatever a state asked for in response to the user's input.
+    fn handle_input(&mut self, input: HandleKeyboardInput) -> Result<bool> {
+        match input {
+            Input => {}
+            Void => {}
+            Exit => return Ok(true),
+            SaveRoll(roll) => {
+                self.db.save(&roll)?;
+            }
+            // A newly created character is saved, then opened
+            // on the character sheet like any saved character.
+            // If it can't be saved the wizard stays open, so
+            // nothing that was chosen is lost.
+            SaveCharacter(mut character) => match self.db.save_character(&mut character) {
+                Ok(id) => {
+                    self.saved_characters = self.db.list_all_characters()?;
+                    self.change_state(CharacterSheet(SavedCharacter {
+                        id: Some(id),
+                        ..SavedCharacter::new()
+                    }))?;
+                }
+                Err(error) => self.show_error(error.to_string()),
+            },
+            // Changes made on the character sheet are written
+            // without leaving it. The sheet is given back the
+            // character as it was saved, with the ids its new
+            // rows were given.
+            WriteCharacter(mut character) => match self.db.save_character(&mut character) {
+                Ok(_) => {
+                    self.saved_characters = self.db.list_all_characters()?;
+                    self.current_character = Some(*character.clone());
+                    if let Some(state) = &mut self.state {
+                        state.character_saved(*character);
+                    }
+                }
+                Err(error) => self.show_error(error.to_string()),
+            },
+            // Characters managed from the select screen are changed
+            // in the database, and the screen given the new list.
+            DeleteCharacter(id) => match self.db.delete_character(id) {
+                Ok(()) => self.characters_changed()?,
+                Err(error) => self.show_error(error.to_string()),
+            },
+            DuplicateCharacter(id) => match self.db.duplicate_character(id) {
+                Ok(_) => self.characters_changed()?,
+                Err(error) => self.show_error(error.to_string()),
+            },
+            RenameCharacter(id, name) => match self.db.rename_character(id, &name) {
+                Ok(()) => self.characters_changed()?,
+                Err(error) => self.show_error(error.to_string()),
+            },
+            // The palette opens on the first page of each kind of
+            // entry, and asks for what matches as the query's typed.
+            Search => {
+                let catalog = self.db.list_catalog("", PAGE_SIZE)?;
+                if let Some(state) = &mut self.state {
+                    state.open_search(catalog);
+                }
+            }
+            // The query is whatever's been typed so far, so one the
+            // search can't make sense of finds nothing rather than
+            // being an error that ends the app. Hits on models that
+            // can't be loaded are left out the same way.
+            SearchCatalog(query) => {
+                let catalog = self.db.list_catalog(&query, PAGE_SIZE)?;
+                let hits = self
+                    .db
+                    .search(&query, &[])
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|hit| Some((self.db.catalog_entry(&hit).ok()?, hit)))
+                    .collect();
+                if let Some(state) = &mut self.state {
+                    state.search_results(catalog, hits);
+                }
+            }
+            LoadRolls(query) => {
+                let rolls = self.db.query(&query)?;
+                let statistics = self.db.roll_statistics(&query)?;
+                if let Some(state) = &mut self.state {
+                    state.rolls_loaded(rolls, statistics);
+                }
+            }
+            LoadItems(queries) => {
+                let pages = queries
+                    .iter()
+                    .map(|query| Ok((self.db.query(query)?, self.db.count(query)?)))
+                    .collect::<Result<Vec<_>>>()?;
+                if let Some(state) = &mut self.state {
+                    state.items_loaded(pages);
+                }
+            }
+            ChangeState(state) => {
+                self.change_state(state)?;
+            }
+        }
+        Ok(false)
+    }
+
+    // Changes the user asked for that the database refused are
+    // shown by the state rather than ending the app.
+    fn show_error(&mut self, error: String) {
+        if let Some(state) = &mut self.state {
+            state.show_error(error);
+        }
+    }
+
+    fn characters_changed(&mut self) -> Result<()> {
+        self.saved_characters = self.db.list_all_characters()?;
+        if let Some(state) = &mut self.state {
+            state.characters_changed(&self.saved_characters);
+        }
+        Ok(())
+    }
 }
 
-pub trait State {
-    fn display_screen(&mut self, stdout: &mut Stdout) -> Result<()>;
-    fn handle_keyboard_event(
+// States draw into a frame from whichever tui Backend the app was
+// started with; the terminal when run, or a TestBackend in tests.
+pub trait State<B: Backend> {
+    // Draws the state into the frame; App owns the terminal and
+    // decides when a frame is drawn.
+    fn display_screen(&mut self, frame: &mut Frame<B>);
+    fn handle_keyboard_event(&mut self, event: KeyEvent) -> Result<HandleKeyboardInput>;
+    // States without anything to click on can ignore the mouse.
+    fn handle_mouse_event(&mut self, _event: MouseEvent) -> Result<HandleKeyboardInput> {
+        Ok(Void)
+    }
+    fn character_saved(&mut self, _character: Character) {}
+    // Called when something the state asked for couldn't be done.
+    fn show_error(&mut self, _error: String) {}
+    // Called with every saved character after any were changed.
+    fn characters_changed(&mut self, _characters: &[SavedCharacter]) {}
+    // Called with the first page of what can be searched
+    // for when the state asked for the search palette.
+    fn open_search(&mut self, _catalog: Vec<CatalogEntry>) {}
+    // Called with what was found for the query in the search
+    // palette; the entries whose names match it, then those
+    // with its words in their descriptions.
+    fn search_results(
         &mut self,
-        _stdout: &Stdout,
-        event: KeyEvent,
-    ) -> Result<HandleKeyboardInput>;
+        _catalog: Vec<CatalogEntry>,
+        _hits: Vec<(CatalogEntry, SearchHit)>,
+    ) {
+    }
+    // Called with a page of the roll history the state asked for,
+    // and statistics over every roll in the history.
+    fn rolls_loaded(&mut self, _rolls: Vec<Roll>, _statistics: RollStatistics) {}
+    // Called with what each of the queries the state asked for
+    // found, in order, along with how many items each would
+    // find without its limit.
+    fn items_loaded(&mut self, _pages: Vec<(Vec<Item>, usize)>) {}
+    // Called every tick the user hasn't pressed anything.
+    fn on_tick(&mut self) {}
 }
@@ -0,0 +1,209 @@
+use crate::{
+    state::{
+        keymap::{Action, Keymap, Screen},
+        text_area::{TextArea, TextInput},
+        theme::Theme,
+    },
+    Character,
+};
+use crossterm::event::KeyEvent;
+use enum_iterator::IntoEnumIterator;
+use std::fmt;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+#[derive(Clone, Copy, PartialEq, IntoEnumIterator)]
+pub enum BiographySection {
+    Gender,
+    Age,
+    Height,
+    Weight,
+    Appearance,
+    PersonalityTraits,
+    Ideals,
+    Bonds,
+    Flaws,
+    Backstory,
+}
+
+impl BiographySection {
+    pub fn get_all_sections() -> Vec<BiographySection> {
+        BiographySection::into_enum_iter().collect()
+    }
+
+    pub fn value(&self, character: &Character) -> String {
+        match self {
+            BiographySection::Gender => character.gender.clone(),
+            BiographySection::Age => character.age.to_string(),
+            BiographySection::Height => character.height.to_string(),
+            BiographySection::Weight => character.weight.to_string(),
+            BiographySection::Appearance => character.appearance.clone(),
+            BiographySection::PersonalityTraits => character.personality_traits.clone(),
+            BiographySection::Ideals => character.ideals.clone(),
+            BiographySection::Bonds => character.bonds.clone(),
+            BiographySection::Flaws => character.flaws.clone(),
+            BiographySection::Backstory => character.backstory.clone(),
+        }
+    }
+
+    // Sets the section to the text typed for it, returning whether
+    // it changed anything. Age, height and weight are left as they
+    // were if what's typed isn't a number.
+    fn set(&self, character: &mut Character, text: &str) -> bool {
+        let before = self.value(character);
+        let number = text.trim().parse().ok();
+        match self {
+            BiographySection::Gender => character.gender = text.trim().to_string(),
+            BiographySection::Age => character.age = number.unwrap_or(character.age),
+            BiographySection::Height => character.height = number.unwrap_or(character.height),
+            BiographySection::Weight => character.weight = number.unwrap_or(character.weight),
+            BiographySection::Appearance => character.appearance = text.to_string(),
+            BiographySection::PersonalityTraits => character.personality_traits = text.to_string(),
+            BiographySection::Ideals => character.ideals = text.to_string(),
+            BiographySection::Bonds => character.bonds = text.to_string(),
+            BiographySection::Flaws => character.flaws = text.to_string(),
+            BiographySection::Backstory => character.backstory = text.to_string(),
+        }
+        before != self.value(character)
+    }
+}
+
+impl fmt::Display for BiographySection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BiographySection::Gender => write!(f, "Gender"),
+            BiographySection::Age => write!(f, "Age"),
+            BiographySection::Height => write!(f, "Height"),
+            BiographySection::Weight => write!(f, "Weight"),
+            BiographySection::Appearance => write!(f, "Appearance"),
+            BiographySection::PersonalityTraits => write!(f, "Personality Traits"),
+            BiographySection::Ideals => write!(f, "Ideals"),
+            BiographySection::Bonds => write!(f, "Bonds"),
+            BiographySection::Flaws => write!(f, "Flaws"),
+            BiographySection::Backstory => write!(f, "Backstory"),
+        }
+    }
+}
+
+// The Biography tab. Sections are picked from a list and shown
+// beside it; Enter starts editing the selected section and Esc
+// finishes, writing it to the character.
+pub struct BiographyPanel {
+    state: ListState,
+    editor: Option<TextArea>,
+    scroll: u16,
+    // Where the section's text was drawn, for scrolling it with the mouse.
+    pub description_area: Rect,
+}
+
+impl BiographyPanel {
+    pub fn new() -> BiographyPanel {
+        let mut state = ListState::default();
+        state.select(Some(0));
+        BiographyPanel {
+            state,
+            editor: None,
+            scroll: 0,
+            description_area: Rect::default(),
+        }
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.editor.is_some()
+    }
+
+    fn selected(&self) -> BiographySection {
+        BiographySection::get_all_sections()[self.state.selected().unwrap_or(0)]
+    }
+
+    // Returns whether the key changed the character. While text is
+    // being typed the key itself is used, otherwise its action.
+    pub fn handle_key(
+        &mut self,
+        event: KeyEvent,
+        action: Option<Action>,
+        character: &mut Character,
+    ) -> bool {
+        if let Some(editor) = self.editor.as_mut() {
+            if let TextInput::Finished = editor.handle_key(event) {
+                let text = editor.text.clone();
+                self.editor = None;
+                return self.selected().set(character, &text);
+            }
+            return false;
+        }
+
+        let count = BiographySection::get_all_sections().len();
+        let selected = self.state.selected().unwrap_or(0);
+        match action {
+            Some(Action::Down) => {
+                self.state.select(Some((selected + 1).min(count - 1)));
+                self.scroll = 0;
+            }
+            Some(Action::Up) => {
+                self.state.select(Some(selected.saturating_sub(1)));
+                self.scroll = 0;
+            }
+            Some(Action::PageDown) => self.scroll = self.scroll.saturating_add(1),
+            Some(Action::PageUp) => self.scroll = self.scroll.saturating_sub(1),
+            Some(Action::Select) => {
+                self.editor = Some(TextArea::new(&self.selected().value(character)));
+            }
+            _ => {}
+        }
+        false
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        area: Rect,
+        character: &Character,
+        keymap: &Keymap,
+        theme: &Theme,
+    ) {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(24), Constraint::Min(0)].as_ref())
+            .split(area);
+        self.description_area = chunks[1];
+
+        let sections = BiographySection::get_all_sections()
+            .into_iter()
+            .map(|section| ListItem::new(section.to_string()))
+            .collect::<Vec<_>>();
+        let sections = List::new(sections)
+            .block(Block::default().title("Biography").borders(Borders::RIGHT))
+            .highlight_style(theme.highlight)
+            .highlight_symbol(">");
+        frame.render_stateful_widget(sections, chunks[0], &mut self.state);
+
+        let section = self.selected();
+        let (title, text) = match &self.editor {
+            Some(editor) => (format!(" {} (Esc: done)", section), editor.display()),
+            None => (
+                format!(
+                    " {} ({})",
+                    section,
+                    keymap.hints(
+                        Screen::Sheet,
+                        &[
+                            (&[Action::Select], "edit"),
+                            (&[Action::PageUp, Action::PageDown], "scroll")
+                        ]
+                    )
+                ),
+                section.value(character),
+            ),
+        };
+        let text = Paragraph::new(text)
+            .block(Block::default().title(title))
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0));
+        frame.render_widget(text, chunks[1]);
+    }
+}
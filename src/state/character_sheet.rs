@@ -1,46 +1,301 @@
 use crate::{
-    Character,
+    data::{
+        catalog::{CatalogEntry, SearchHit},
+        conditions::Condition,
+        feature::Recharge,
+        items::Item,
+        rolls::{Roll, RollStatistics},
+        rules::{derived_stats, DerivedStats, Encumbrance, FeatError, RollMode},
+        stats::Stats,
+    },
+    dice::Roller,
     state::{
-    app::{HandleKeyboardInput, HandleKeyboardInput::*, State, States::*},
-    tabs::CharacterSheetTab,
-}};
+        app::{HandleKeyboardInput, HandleKeyboardInput::*, State, States::*},
+        keymap::{Action, Keymap, Screen},
+        mouse::Mouse,
+        palette::{Palette, PaletteInput},
+        roll_history::RollHistory,
+        rolls::RollPanel,
+        sheet_fields::{FieldInput, SheetField, SheetFields},
+        tabs::{CharacterSheetTab, TabPanels},
+        theme::Theme,
+    },
+    Character,
+};
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent};
-use std::io::Stdout;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
 use tui::{
-    backend::CrosstermBackend,
-    layout::{Constraint, Layout},
-    style::{Color, Modifier, Style},
+    backend::Backend,
+    layout::{Constraint, Layout, Rect},
+    style::Style,
     text::{Span, Spans},
     widgets::{
-        Row,
-        Block,
-        Borders,
-        Tabs,
-        Table,
-        Cell
+        Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table, Tabs,
     },
-    Terminal,
+    Frame,
 };
 
 pub struct CharacterSheet {
     current_character: Character,
-    tabs: TabState,
-//    current_tab: CharacterSheetTab,
-//    index: usize,
-//    all_tabs: Vec<CharacterSheetTab>,
+    current_tab: CharacterSheetTab,
+    index: usize,
+    all_tabs: Vec<CharacterSheetTab>,
+    // Some while the conditions menu is open over the sheet.
+    conditions_menu: Option<ConditionsMenu>,
+    // Some while the search palette is.
+    palette: Option<Palette>,
+    panels: TabPanels,
+    history: RollHistory,
+    show_history: bool,
+    fields: SheetFields,
+    // Shown in the title of the tab area until the next key
+    // is pressed, ie. after the character has been written.
+    message: Option<String>,
+    // Set when the sheet was about to be left with unsaved
+    // changes, so leaving again discards them.
+    discard_warned: bool,
+    // Rolls for the charges items regain on a rest.
+    roller: Roller,
+    keymap: Keymap,
+    theme: Theme,
+    show_help: bool,
+    // Where the tab titles and the open tab were last drawn,
+    // to find what the mouse is over.
+    tabs_area: Rect,
+    tab_inner: Rect,
+}
+
+// Popup for adding and removing conditions. Digits typed while
+// the menu is open set the duration, in rounds, of the next
+// condition added.
+struct ConditionsMenu {
+    state: ListState,
+    rounds: String,
 }
 
 impl CharacterSheet {
-    pub fn new(current_character: Character) -> CharacterSheet {
+    pub fn new(current_character: Character, keymap: Keymap, theme: Theme) -> CharacterSheet {
         CharacterSheet {
+            panels: TabPanels::new(&current_character),
+            history: RollHistory::new(current_character.id),
             current_character,
             current_tab: CharacterSheetTab::Stats,
             index: 0,
             all_tabs: CharacterSheetTab::get_all_tabs(),
+            conditions_menu: None,
+            palette: None,
+            show_history: false,
+            fields: SheetFields::new(),
+            message: None,
+            discard_warned: false,
+            roller: Roller::new(),
+            keymap,
+            theme,
+            show_help: false,
+            tabs_area: Rect::default(),
+            tab_inner: Rect::default(),
         }
     }
 
+    // Leaving the sheet with unsaved changes only warns
+    // the first time; leaving again discards them.
+    fn leave(&mut self, result: HandleKeyboardInput) -> HandleKeyboardInput {
+        if self.fields.dirty && !self.discard_warned {
+            self.discard_warned = true;
+            self.message = Some(format!(
+                "Unsaved changes - {}, again to discard",
+                self.keymap
+                    .hints(Screen::Sheet, &[(&[Action::Write], "write")])
+            ));
+            return Input;
+        }
+        result
+    }
+
+    // Adds what was picked in the search palette to the character,
+    // unless they already have it. Items are added one at a time.
+    fn add_entry(&mut self, entry: CatalogEntry) {
+        let character = &mut self.current_character;
+        let already = format!("{} already has {}", character.name, entry.name());
+        let added = match &entry {
+            CatalogEntry::Spell(spell) if !character.spells.iter().any(|s| s.id == spell.id) => {
+                character.spells.push(spell.clone());
+                Ok(())
+            }
+            CatalogEntry::Item(item) => {
+                character.add_item(*item.clone(), 1);
+                Ok(())
+            }
+            CatalogEntry::Feature(feature)
+                if !character.features.iter().any(|f| f.id == feature.id) =>
+            {
+                character.features.push(feature.clone());
+                Ok(())
+            }
+            // Feats are only taken when the character meets their
+            // prerequisites; see rules::can_take_feat.
+            CatalogEntry::Feat(feat) => match character.take_feat(feat.clone()) {
+                Err(FeatError::AlreadyTaken) => Err(already),
+                Err(error) => Err(format!("Can't take {}. {}", feat.name, error)),
+                Ok(()) => Ok(()),
+            },
+            CatalogEntry::Language(language)
+                if !character.languages.iter().any(|l| l.id == language.id) =>
+            {
+                character.languages.push(language.clone());
+                Ok(())
+            }
+            _ => Err(already),
+        };
+        self.message = match &added {
+            Ok(()) => Some(format!("Added {}", entry.name())),
+            Err(error) => Some(error.clone()),
+        };
+        if added.is_ok() {
+            self.fields.dirty = true;
+            self.panels.rolls = RollPanel::new(&self.current_character);
+        }
+    }
+
+    // Buys one of the item picked in the search palette, as long
+    // as the character can afford it.
+    fn buy(&mut self, item: Item) {
+        let (name, value) = (item.name.clone(), item.value);
+        let value = value.map(|value| value.to_string()).unwrap_or_default();
+        self.message = match self.current_character.buy(item, 1) {
+            Some(_) => {
+                self.fields.dirty = true;
+                self.panels.rolls = RollPanel::new(&self.current_character);
+                Some(format!("Bought {} for {}", name, value))
+            }
+            None => Some(format!("Can't afford {} for {}", name, value)),
+        };
+    }
+
+    fn details_cell<'a>(&self, field: SheetField, suffix: &str) -> Cell<'a> {
+        Cell::from(Spans::from(vec![
+            Span::styled(
+                format!("{}: ", field),
+                self.fields.style(field, &self.theme),
+            ),
+            Span::raw(format!(
+                "{}{}",
+                self.fields.display(field, &self.current_character),
+                suffix
+            )),
+        ]))
+    }
+
+    // Keys for the Inventory, Biography and Notes tabs. While text is
+    // being typed in the Biography or Notes, every key goes to it.
+    fn handle_tab_key(
+        &mut self,
+        event: KeyEvent,
+        action: Option<Action>,
+    ) -> Option<HandleKeyboardInput> {
+        let character = &mut self.current_character;
+        let changed = match self.current_tab {
+            CharacterSheetTab::Inventory
+                if matches!(
+                    action,
+                    Some(
+                        Action::Up
+                            | Action::Down
+                            | Action::Equip
+                            | Action::Attune
+                            | Action::Drop
+                            | Action::DropAll
+                            | Action::Use
+                            | Action::Split
+                            | Action::Merge
+                            | Action::Stow
+                            | Action::Sell
+                    )
+                ) =>
+            {
+                match self.panels.inventory.handle_key(action, character) {
+                    Ok(changed) => changed,
+                    Err(error) => {
+                        self.message = Some(error);
+                        false
+                    }
+                }
+            }
+            CharacterSheetTab::Biography
+                if self.panels.biography.is_editing()
+                    || matches!(
+                        action,
+                        Some(
+                            Action::Up
+                                | Action::Down
+                                | Action::PageUp
+                                | Action::PageDown
+                                | Action::Select
+                        )
+                    ) =>
+            {
+                self.panels.biography.handle_key(event, action, character)
+            }
+            CharacterSheetTab::Notes
+                if self.panels.notes.is_editing()
+                    || matches!(
+                        action,
+                        Some(
+                            Action::Up
+                                | Action::Down
+                                | Action::PageUp
+                                | Action::PageDown
+                                | Action::Select
+                        )
+                    ) =>
+            {
+                self.panels.notes.handle_key(event, action, character)
+            }
+            _ => return None,
+        };
+        if changed {
+            self.fields.dirty = true;
+            // Equipping or dropping a weapon changes what can be rolled.
+            self.panels.rolls = RollPanel::new(&self.current_character);
+        }
+        Some(Input)
+    }
+
+    // Rolls the target selected in the Stats tab. Rolls are only
+    // saved once the character has been, since they're linked to
+    // the character's id.
+    fn roll(&mut self) -> HandleKeyboardInput {
+        match self.panels.rolls.roll(&self.current_character) {
+            Some(roll) => {
+                self.history.push(roll.clone());
+                match roll.character {
+                    Some(_) => SaveRoll(roll),
+                    None => Input,
+                }
+            }
+            None => Input,
+        }
+    }
+
+    // Scrolling with the wheel does what the up and down keys do,
+    // or over a description, what page up and down do.
+    fn scroll(&mut self, mouse: Mouse) -> Result<HandleKeyboardInput> {
+        let over_description = self
+            .panels
+            .description_area(self.current_tab)
+            .is_some_and(|area| mouse.is_in(area));
+        let action = match (mouse, over_description) {
+            (Mouse::ScrollUp(..), true) => Action::PageUp,
+            (Mouse::ScrollUp(..), false) => Action::Up,
+            (_, true) => Action::PageDown,
+            (_, false) => Action::Down,
+        };
+        if let Some(input) = self.handle_tab_key(KeyEvent::from(KeyCode::Null), Some(action)) {
+            return Ok(input);
+        }
+        self.handle_action(Some(action))
+    }
+
     fn next(&mut self) {
         self.index = (self.index + 1) % self.all_tabs.len();
         self.current_tab = self.all_tabs[self.index];
@@ -54,132 +309,635 @@ impl CharacterSheet {
         }
         self.current_tab = self.all_tabs[self.index];
     }
-}
 
-impl State for CharacterSheet {
-    fn display_screen(&mut self, stdout: &mut Stdout) -> Result<()> {
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
-        terminal.clear()?;
-        terminal.draw(|f| {
-            let chunks = Layout::default()
-                .direction(tui::layout::Direction::Vertical)
-                .constraints(
-                    [
-                        Constraint::Percentage(20),
-                        Constraint::Percentage(20),
-                        Constraint::Length(1),
-                        Constraint::Percentage(55),
-                    ]
-                    .as_ref(),
-                )
-                .split(f.size());
-
-            let key_style = Style::default()
-                .fg(Color::Green)
-                .add_modifier(Modifier::BOLD);
-
-            let details_table = Table::new(vec![
-                Row::new(vec![
-                    Cell::from(Spans::from(vec![
-                        Span::styled("Name: ", key_style),
-                        Span::raw(self.current_character.name.as_str()),
-                    ])),
-                    Cell::from(Spans::from(vec![
-                        Span::styled("Alignment: ", key_style),
-                        Span::raw(self.current_character.alignment.as_str()),
-                    ])),
-                ]),
-
-                Row::new(vec![
-                    Cell::from(Spans::from(vec![
-                        Span::styled("Race: ", key_style),
-                        Span::raw(self.current_character.race.as_str()),
-                    ])),
-                    Cell::from(Spans::from(vec![
-                        Span::styled("Experience: ", key_style),
-                        Span::raw(self.current_character.xp.to_string()),
-                    ])),
-                ]),
-
-                Row::new(vec![
-                     Cell::from(Spans::from(vec![
-                        Span::styled("Class: ", key_style),
-                        Span::raw(self.current_character.class.as_str()),
-                    ])),
-                ]),
-
-                Row::new(vec![
-                    Cell::from(Spans::from(vec![
-                        Span::styled("Background: ", key_style),
-                        Span::raw(self.current_character.background.as_str()),
-                    ])),
-                ]),
-
-            ]).block(Block::default())
-              .widths(&[Constraint::Percentage(10), Constraint::Percentage(10), Constraint::Percentage(10)]);
-
-            let tab_titles = CharacterSheetTab::get_all_tab_strings()
-                .into_iter()
-                .map(Spans::from)
-                .collect();
-
-            let tabs = Tabs::new(tab_titles)
-                .select(self.index)
-                .style(Style::default().fg(Color::Gray))
-                .highlight_style(Style::default().fg(Color::Green))
-                .divider("|");
-
-            let character_details = vec![Spans::from(vec![Span::styled(
-                format!(
-                    "{} {} {}",
-                    self.current_character.name,
-                    self.current_character.race,
-                    self.current_character.class
-                ),
-                Style::default()
-                    .add_modifier(Modifier::BOLD)
-                    .fg(Color::Green),
-            )])];
-
-            let details = Paragraph::new(character_details)
-                .alignment(tui::layout::Alignment::Center)
-                .wrap(tui::widgets::Wrap { trim: true });
-
-            f.render_widget(details, chunks[0]);
-            f.render_widget(all_tabs, chunks[1]);
-            let tab_area = Block::default()
-                .borders(Borders::ALL)
-                .border_type(tui::widgets::BorderType::Rounded)
-                .style(Style::default());
-
-            f.render_widget(details_table, chunks[0]);
-            f.render_widget(tabs, chunks[2]);
-            f.render_widget(tab_area, chunks[3]);
-            self.current_tab.display_tab(f, chunks[3], &self.current_character);
-        })?;
-        Ok(())
-    }
-
-    fn handle_keyboard_event(
+    fn handle_conditions_menu(
         &mut self,
-        _stdout: &Stdout,
         event: KeyEvent,
-    ) -> Result<HandleKeyboardInput> {
-        match event.code {
-            // On matching the Esc key, return false to the caller.
+        action: Option<Action>,
+    ) -> HandleKeyboardInput {
+        let all_conditions = Condition::get_all_conditions();
+        let menu = match self.conditions_menu.as_mut() {
+            Some(menu) => menu,
+            None => return Void,
+        };
+        let selected = menu.state.selected().unwrap_or(0);
+
+        match (action, event.code) {
+            (Some(Action::Quit | Action::Conditions), _) => self.conditions_menu = None,
+            (Some(Action::Down), _) => menu
+                .state
+                .select(Some((selected + 1).min(all_conditions.len() - 1))),
+            (Some(Action::Up), _) => menu.state.select(Some(selected.saturating_sub(1))),
+            (_, KeyCode::Char(digit)) if digit.is_ascii_digit() && menu.rounds.len() < 4 => {
+                menu.rounds.push(digit)
+            }
+            (_, KeyCode::Backspace) => {
+                menu.rounds.pop();
+            }
+
+            // Select toggles the selected condition; exhaustion can't be
+            // toggled, so its level is raised with Select or '+' and
+            // lowered with '-'.
+            (Some(Action::Select), _) | (_, KeyCode::Char('+')) | (_, KeyCode::Char('-')) => {
+                let toggle = action == Some(Action::Select);
+                let rounds = menu.rounds.parse().ok();
+                menu.rounds.clear();
+                self.fields.dirty = true;
+                let character = &mut self.current_character;
+                match (all_conditions[selected], event.code) {
+                    (Condition::Exhaustion(_), KeyCode::Char('-')) => {
+                        character.add_condition(
+                            Condition::Exhaustion(character.exhaustion().saturating_sub(1)),
+                            None,
+                        );
+                    }
+                    (Condition::Exhaustion(_), _) => {
+                        character.add_condition(
+                            Condition::Exhaustion(character.exhaustion() + 1),
+                            rounds,
+                        );
+                    }
+                    (condition, _) if toggle && character.has_condition(&condition) => {
+                        character.remove_condition(&condition);
+                    }
+                    (condition, _) if toggle => character.add_condition(condition, rounds),
+                    _ => {}
+                }
+            }
+            _ => return Void,
+        }
+        Input
+    }
+
+    // What the sheet does for each action once the popups, fields
+    // and tabs that take keys first have passed on it.
+    fn handle_action(&mut self, action: Option<Action>) -> Result<HandleKeyboardInput> {
+        match action {
+            // On matching Quit (Esc by default), return Exit to the caller.
             // This will end the main loop and the application.
-            KeyCode::Esc => Ok(Exit),
-            KeyCode::Char('q') => Ok(ChangeState(SelectScreen)),
-            KeyCode::Tab => {
+            Some(Action::Quit) => Ok(self.leave(Exit)),
+            Some(Action::Back) => Ok(self.leave(ChangeState(SelectScreen))),
+            Some(Action::Help) => {
+                self.show_help = true;
+                Ok(Input)
+            }
+            Some(Action::Search) => Ok(Search),
+            // Focuses the editable fields at the top of the sheet.
+            Some(Action::Edit) => {
+                self.fields.focus();
+                Ok(Input)
+            }
+            // Writes the character, along with any changes
+            // made on the sheet, to the database.
+            Some(Action::Write) => Ok(WriteCharacter(Box::new(self.current_character.clone()))),
+            Some(Action::NextTab) => {
                 self.next();
                 Ok(Input)
             }
-            KeyCode::BackTab => {
+            Some(Action::PreviousTab) => {
                 self.previous();
                 Ok(Input)
             }
+            // Resting restores what recharges on the rest. Like any
+            // other change, it's only saved once the character's written.
+            Some(action @ (Action::ShortRest | Action::LongRest)) => {
+                let (rest, message) = match action {
+                    Action::ShortRest => (Recharge::ShortRest, "Finished a short rest"),
+                    _ => (Recharge::LongRest, "Finished a long rest"),
+                };
+                let regained = self.current_character.rest(&rest, &mut self.roller);
+                let mut message = message.to_string();
+                for (key, charges) in regained.into_iter().filter(|(_, charges)| *charges > 0) {
+                    if let Some(entry) = self.current_character.entry(key) {
+                        message.push_str(&format!(", {} regained {}", entry.item.name, charges));
+                    }
+                }
+                self.message = Some(message);
+                self.fields.dirty = true;
+                Ok(Input)
+            }
+            Some(Action::Conditions) => {
+                let mut state = ListState::default();
+                state.select(Some(0));
+                self.conditions_menu = Some(ConditionsMenu {
+                    state,
+                    rounds: String::new(),
+                });
+                Ok(Input)
+            }
+            // Ends the current round of combat, counting down
+            // the duration of any timed conditions.
+            Some(Action::EndRound) => {
+                self.current_character.end_round();
+                self.fields.dirty = true;
+                Ok(Input)
+            }
+
+            // Rolling is done from the list of checks,
+            // saves and skills in the Stats tab.
+            Some(Action::Down) if self.current_tab == CharacterSheetTab::Stats => {
+                self.panels.rolls.next();
+                Ok(Input)
+            }
+            Some(Action::Up) if self.current_tab == CharacterSheetTab::Stats => {
+                self.panels.rolls.previous();
+                Ok(Input)
+            }
+            Some(Action::Roll | Action::Select) if self.current_tab == CharacterSheetTab::Stats => {
+                Ok(self.roll())
+            }
+
+            // The Features and Spells tabs list what the character
+            // has, with uses of features and spell slots spent with
+            // 'u' and given back with 'U'.
+            Some(Action::Down) if self.current_tab == CharacterSheetTab::Features => {
+                self.panels.features.next(&self.current_character);
+                Ok(Input)
+            }
+            Some(Action::Up) if self.current_tab == CharacterSheetTab::Features => {
+                self.panels.features.previous();
+                Ok(Input)
+            }
+            Some(Action::PageDown) if self.current_tab == CharacterSheetTab::Features => {
+                self.panels.features.scroll_down();
+                Ok(Input)
+            }
+            Some(Action::PageUp) if self.current_tab == CharacterSheetTab::Features => {
+                self.panels.features.scroll_up();
+                Ok(Input)
+            }
+            Some(Action::Use | Action::Restore)
+                if self.current_tab == CharacterSheetTab::Features =>
+            {
+                if let Some(feature) = self.panels.features.selected(&self.current_character) {
+                    match action {
+                        Some(Action::Use) => {
+                            self.current_character.spend_use(&feature);
+                        }
+                        _ => self.current_character.restore_use(&feature),
+                    }
+                    self.fields.dirty = true;
+                }
+                Ok(Input)
+            }
+            Some(Action::Down) if self.current_tab == CharacterSheetTab::Spells => {
+                self.panels.spells.next(&self.current_character);
+                Ok(Input)
+            }
+            Some(Action::Up) if self.current_tab == CharacterSheetTab::Spells => {
+                self.panels.spells.previous();
+                Ok(Input)
+            }
+            Some(Action::PageDown) if self.current_tab == CharacterSheetTab::Spells => {
+                self.panels.spells.scroll_down();
+                Ok(Input)
+            }
+            Some(Action::PageUp) if self.current_tab == CharacterSheetTab::Spells => {
+                self.panels.spells.scroll_up();
+                Ok(Input)
+            }
+            Some(Action::Use | Action::Restore)
+                if self.current_tab == CharacterSheetTab::Spells =>
+            {
+                if let Some(spell) = self.panels.spells.selected(&self.current_character) {
+                    match action {
+                        Some(Action::Use) => {
+                            self.current_character.spend_slot(spell.level);
+                        }
+                        _ => self.current_character.restore_slot(spell.level),
+                    }
+                    self.fields.dirty = true;
+                }
+                Ok(Input)
+            }
+            // The history is loaded again each time it's opened.
+            Some(Action::RollHistory) => {
+                self.show_history = true;
+                Ok(self.history.reload().map_or(Input, LoadRolls))
+            }
+            Some(Action::Advantage) => {
+                self.panels.rolls.toggle_mode(RollMode::Advantage);
+                Ok(Input)
+            }
+            Some(Action::Disadvantage) => {
+                self.panels.rolls.toggle_mode(RollMode::Disadvantage);
+                Ok(Input)
+            }
             _ => Ok(Input),
         }
     }
 }
+
+// Plain-language summary of the effects the rules engine has
+// applied to the character, for display under their conditions.
+fn effects_summary(derived: &DerivedStats) -> Vec<String> {
+    let mut effects = Vec::new();
+    let all_stats = [
+        Stats::STR,
+        Stats::DEX,
+        Stats::CON,
+        Stats::INT,
+        Stats::WIS,
+        Stats::CHA,
+    ];
+
+    for (name, modes) in [
+        ("ability checks", &derived.ability_checks),
+        ("saving throws", &derived.saving_throws),
+    ] {
+        for mode in [RollMode::Advantage, RollMode::Disadvantage] {
+            let stats = all_stats
+                .iter()
+                .filter(|stat| modes.get(stat) == Some(&mode))
+                .map(|stat| stat.to_string())
+                .collect::<Vec<_>>();
+            if stats.len() == all_stats.len() {
+                effects.push(format!("{} on {}", mode, name));
+            } else if !stats.is_empty() {
+                effects.push(format!("{} on {} {}", mode, stats.join("/"), name));
+            }
+        }
+    }
+    if !derived.auto_fail_saves.is_empty() {
+        effects.push(format!(
+            "Auto-fail {} saving throws",
+            derived
+                .auto_fail_saves
+                .iter()
+                .map(|stat| stat.to_string())
+                .collect::<Vec<_>>()
+                .join("/")
+        ));
+    }
+    if derived.attack_rolls != RollMode::Normal {
+        effects.push(format!("{} on attack rolls", derived.attack_rolls));
+    }
+    if derived.attacks_against != RollMode::Normal {
+        effects.push(format!("Attacks against have {}", derived.attacks_against));
+    }
+    if derived.encumbrance != Encumbrance::Unencumbered {
+        effects.push(derived.encumbrance.to_string());
+    }
+    if derived.incapacitated {
+        effects.push("Incapacitated".to_string());
+    }
+    if derived.dead {
+        effects.push("Dead".to_string());
+    }
+    effects
+}
+
+// A rectangle of the given percentage size, centered in the area.
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let width = area.width * percent_x / 100;
+    let height = area.height * percent_y / 100;
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+impl<B: Backend> State<B> for CharacterSheet {
+    fn display_screen(&mut self, f: &mut Frame<B>) {
+        let derived = derived_stats(&self.current_character);
+
+        if self.show_history {
+            self.history.render(f, f.size(), &self.keymap, &self.theme);
+            return;
+        }
+
+        let chunks = Layout::default()
+            .direction(tui::layout::Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(20),
+                    Constraint::Length(1),
+                    Constraint::Percentage(55),
+                ]
+                .as_ref(),
+            )
+            .split(f.size());
+
+        let key_style = self.theme.key;
+
+        let max_hit_points = derived
+            .hit_point_max
+            .map(|max| format!(" (reduced to {})", max))
+            .unwrap_or_default();
+        let details_table = Table::new(vec![
+            Row::new(vec![
+                self.details_cell(SheetField::Name, ""),
+                self.details_cell(SheetField::Alignment, ""),
+                self.details_cell(SheetField::Gender, ""),
+            ]),
+            Row::new(vec![
+                Cell::from(Spans::from(vec![
+                    Span::styled("Level: ", key_style),
+                    Span::raw(self.current_character.level.to_string()),
+                ])),
+                self.details_cell(SheetField::Experience, ""),
+            ]),
+            Row::new(vec![
+                self.details_cell(SheetField::HitPoints, ""),
+                self.details_cell(SheetField::MaxHitPoints, &max_hit_points),
+                self.details_cell(SheetField::TempHitPoints, ""),
+            ]),
+        ])
+        .block(Block::default())
+        .widths(&[
+            Constraint::Percentage(34),
+            Constraint::Percentage(33),
+            Constraint::Percentage(33),
+        ]);
+
+        let conditions = self
+            .current_character
+            .conditions
+            .iter()
+            .map(|active| active.to_string())
+            .collect::<Vec<_>>();
+
+        let combat_details = vec![
+            Spans::from(vec![
+                Span::styled("AC: ", key_style),
+                Span::raw(format!("{}  ", derived.armor_class)),
+                Span::styled("Speed: ", key_style),
+                Span::raw(format!("{} ft  ", derived.speed)),
+                Span::styled("Initiative: ", key_style),
+                Span::raw(format!("{}  ", self.current_character.initiative)),
+                Span::styled("Carrying: ", key_style),
+                Span::raw(format!(
+                    "{}/{} lb  ",
+                    derived.carried_weight, derived.carrying_capacity
+                )),
+                Span::styled("Coins: ", key_style),
+                Span::raw(self.current_character.purse.to_string()),
+            ]),
+            Spans::from(vec![
+                Span::styled("Conditions: ", key_style),
+                Span::raw(conditions.join(", ")),
+            ]),
+            Spans::from(vec![
+                Span::styled("Effects: ", key_style),
+                Span::raw(effects_summary(&derived).join("; ")),
+            ]),
+        ];
+
+        let combat = Paragraph::new(combat_details).wrap(tui::widgets::Wrap { trim: true });
+
+        let tab_titles = CharacterSheetTab::get_all_tab_strings()
+            .into_iter()
+            .map(Spans::from)
+            .collect();
+
+        let tabs = Tabs::new(tab_titles)
+            .select(self.index)
+            .style(self.theme.muted)
+            .highlight_style(self.theme.highlight)
+            .divider("|");
+
+        // The tab area's title shows whether there are changes
+        // to write, or what the last write did.
+        let status = match (&self.message, self.fields.dirty) {
+            (Some(message), _) => message.clone(),
+            (None, true) => format!(
+                "Modified - {}",
+                self.keymap
+                    .hints(Screen::Sheet, &[(&[Action::Write], "write")])
+            ),
+            (None, false) => String::new(),
+        };
+        let tab_area = Block::default()
+            .title(status)
+            .borders(Borders::ALL)
+            .border_type(tui::widgets::BorderType::Rounded)
+            .style(Style::default());
+
+        f.render_widget(details_table, chunks[0]);
+        f.render_widget(combat, chunks[1]);
+        f.render_widget(tabs, chunks[2]);
+        self.tabs_area = chunks[2];
+        self.tab_inner = tab_area.inner(chunks[3]);
+        f.render_widget(tab_area, chunks[3]);
+        self.current_tab.display_tab(
+            f,
+            self.tab_inner,
+            &self.current_character,
+            &mut self.panels,
+            &self.keymap,
+            &self.theme,
+        );
+
+        if let Some(menu) = self.conditions_menu.as_mut() {
+            let items = Condition::get_all_conditions()
+                .into_iter()
+                .map(|condition| {
+                    let marker = match condition {
+                        Condition::Exhaustion(_) => {
+                            format!("[{}]", self.current_character.exhaustion())
+                        }
+                        _ if self.current_character.has_condition(&condition) => "[x]".to_string(),
+                        _ => "[ ]".to_string(),
+                    };
+                    let name = match condition {
+                        Condition::Exhaustion(_) => "Exhaustion".to_string(),
+                        _ => condition.to_string(),
+                    };
+                    ListItem::new(format!("{} {}", marker, name))
+                })
+                .collect::<Vec<_>>();
+
+            let title = if menu.rounds.is_empty() {
+                "Conditions".to_string()
+            } else {
+                format!("Conditions - {} rounds", menu.rounds)
+            };
+            let list = List::new(items)
+                .block(Block::default().title(title).borders(Borders::ALL))
+                .highlight_style(self.theme.highlight)
+                .highlight_symbol(">");
+
+            let area = centered_rect(40, 80, f.size());
+            f.render_widget(Clear, area);
+            f.render_stateful_widget(list, area, &mut menu.state);
+        }
+        self.fields
+            .render(f, centered_rect(30, 40, f.size()), &self.theme);
+        if let Some(palette) = self.palette.as_mut() {
+            palette.render(f, &self.theme);
+        }
+
+        if self.show_help {
+            self.keymap.render_help(f, Screen::Sheet, &self.theme);
+        }
+    }
+
+    fn handle_keyboard_event(&mut self, event: KeyEvent) -> Result<HandleKeyboardInput> {
+        self.message = None;
+        // Any key closes the help.
+        if self.show_help {
+            self.show_help = false;
+            return Ok(Input);
+        }
+
+        if let Some(palette) = self.palette.as_mut() {
+            return Ok(match palette.handle_key(event) {
+                PaletteInput::Handled => Input,
+                PaletteInput::Closed => {
+                    self.palette = None;
+                    Input
+                }
+                PaletteInput::Open(character) => self.leave(ChangeState(CharacterSheet(character))),
+                PaletteInput::Query(query) => SearchCatalog(query),
+                PaletteInput::Add(entry) => {
+                    self.palette = None;
+                    self.add_entry(entry);
+                    Input
+                }
+                PaletteInput::Buy(item) => {
+                    self.palette = None;
+                    self.buy(*item);
+                    Input
+                }
+            });
+        }
+
+        // Keys are matched by the action they're bound to in the
+        // keymap; only text being typed looks at the keys themselves.
+        let action = self.keymap.action(Screen::Sheet, event);
+        if !matches!(action, Some(Action::Back | Action::Quit)) {
+            self.discard_warned = false;
+        }
+        if self.conditions_menu.is_some() {
+            return Ok(self.handle_conditions_menu(event, action));
+        }
+        if self.show_history {
+            let load = match action {
+                Some(Action::Quit | Action::RollHistory) => {
+                    self.show_history = false;
+                    None
+                }
+                Some(Action::Filter | Action::NextTab) => self.history.next_filter(),
+                Some(Action::Down) => self.history.next(),
+                Some(Action::Up) => {
+                    self.history.previous();
+                    None
+                }
+                _ => return Ok(Void),
+            };
+            return Ok(load.map_or(Input, LoadRolls));
+        }
+
+        if self.fields.is_focused() {
+            match self
+                .fields
+                .handle_key(event, action, &mut self.current_character)
+            {
+                // The roll panel is rebuilt, since the rolls it
+                // offers depend on the character.
+                FieldInput::Changed => {
+                    self.panels.rolls = RollPanel::new(&self.current_character);
+                    return Ok(Input);
+                }
+                FieldInput::Handled => return Ok(Input),
+                FieldInput::Unhandled if self.fields.is_editing() => return Ok(Void),
+                FieldInput::Unhandled => {}
+            }
+        }
+        if let Some(input) = self.handle_tab_key(event, action) {
+            return Ok(input);
+        }
+        self.handle_action(action)
+    }
+
+    // Clicking a tab's title opens it and clicking a roll in the Stats
+    // tab rolls it. The mouse is ignored while a popup is open over
+    // the sheet or anything is being typed.
+    fn handle_mouse_event(&mut self, event: MouseEvent) -> Result<HandleKeyboardInput> {
+        let mouse = match Mouse::from_event(event) {
+            Some(mouse) => mouse,
+            None => return Ok(Void),
+        };
+        if self.show_help {
+            self.show_help = false;
+            return Ok(Input);
+        }
+        if self.conditions_menu.is_some()
+            || self.palette.is_some()
+            || self.fields.is_focused()
+            || self.panels.biography.is_editing()
+            || self.panels.notes.is_editing()
+        {
+            return Ok(Void);
+        }
+        self.message = None;
+        self.discard_warned = false;
+        if self.show_history {
+            return Ok(match mouse {
+                Mouse::ScrollDown(..) => self.history.next().map_or(Input, LoadRolls),
+                Mouse::ScrollUp(..) => {
+                    self.history.previous();
+                    Input
+                }
+                Mouse::Click(..) => Void,
+            });
+        }
+
+        match mouse {
+            Mouse::Click(column, _) if mouse.is_in(self.tabs_area) => {
+                if let Some(index) = CharacterSheetTab::index_at(self.tabs_area, column) {
+                    self.index = index;
+                    self.current_tab = self.all_tabs[index];
+                }
+                Ok(Input)
+            }
+            Mouse::Click(..) if self.current_tab == CharacterSheetTab::Stats => {
+                match self.panels.rolls.target_at(mouse) {
+                    Some(index) => {
+                        self.panels.rolls.select(index);
+                        Ok(self.roll())
+                    }
+                    None => Ok(Void),
+                }
+            }
+            Mouse::ScrollUp(..) | Mouse::ScrollDown(..) if mouse.is_in(self.tab_inner) => {
+                self.scroll(mouse)
+            }
+            _ => Ok(Void),
+        }
+    }
+
+    fn open_search(&mut self, catalog: Vec<CatalogEntry>) {
+        self.palette = Some(Palette::new(catalog, true));
+    }
+
+    fn search_results(&mut self, catalog: Vec<CatalogEntry>, hits: Vec<(CatalogEntry, SearchHit)>) {
+        if let Some(palette) = self.palette.as_mut() {
+            palette.found(catalog, hits);
+        }
+    }
+
+    fn rolls_loaded(&mut self, rolls: Vec<Roll>, statistics: RollStatistics) {
+        self.history.loaded(rolls, statistics);
+    }
+
+    // The sheet's given back the character as it was saved, with
+    // the ids its new rows were given. A character written for the
+    // first time starts a history of its own, since the rolls made
+    // before it had an id weren't saved as its rolls.
+    fn character_saved(&mut self, character: Character) {
+        if character.id != self.current_character.id {
+            self.history = RollHistory::new(character.id);
+        }
+        self.current_character = character;
+        self.fields.dirty = false;
+        self.message = Some("Written".to_string());
+    }
+
+    fn show_error(&mut self, error: String) {
+        self.message = Some(format!("Couldn't write: {}", error));
+    }
+}
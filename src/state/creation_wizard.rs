@@ -0,0 +1,1092 @@
+use crate::{
+    data::{
+        background::Background,
+        class::Class,
+        items::Item,
+        proficiency::ProficiencyClass,
+        query::Query,
+        race::{Race, Subrace},
+        skills::Skill,
+        srd,
+        stats::Stats::{self, CHA, CON, DEX, INT, STR, WIS},
+    },
+    dice::Roller,
+    state::{
+        app::{HandleKeyboardInput, HandleKeyboardInput::*, State, States::*},
+        equipment_picker::EquipmentPicker,
+        theme::Theme,
+    },
+    Character,
+};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use enum_iterator::IntoEnumIterator;
+use std::{collections::HashMap, fmt};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+const ALL_STATS: [Stats; 6] = [STR, DEX, CON, INT, WIS, CHA];
+const STANDARD_ARRAY: [u8; 6] = [15, 14, 13, 12, 10, 8];
+const POINT_BUY_BUDGET: u8 = 27;
+
+// The steps of making a new character, in order.
+#[derive(Debug, Clone, Copy, PartialEq, IntoEnumIterator)]
+enum Step {
+    Name,
+    Race,
+    Class,
+    AbilityScores,
+    Background,
+    Choices,
+    Equipment,
+    Review,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Name => write!(f, "Name"),
+            Step::Race => write!(f, "Race"),
+            Step::Class => write!(f, "Class"),
+            Step::AbilityScores => write!(f, "Ability Scores"),
+            Step::Background => write!(f, "Background"),
+            Step::Choices => write!(f, "Skills & Languages"),
+            Step::Equipment => write!(f, "Equipment"),
+            Step::Review => write!(f, "Review"),
+        }
+    }
+}
+
+// Ability scores are either the standard array assigned to the
+// abilities, or bought with points; see PHB p.13.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScoreMethod {
+    StandardArray,
+    PointBuy,
+}
+
+impl fmt::Display for ScoreMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoreMethod::StandardArray => write!(f, "Standard Array"),
+            ScoreMethod::PointBuy => write!(f, "Point Buy"),
+        }
+    }
+}
+
+fn point_cost(score: u8) -> u8 {
+    match score {
+        14 => 7,
+        15 => 9,
+        score => score.saturating_sub(8),
+    }
+}
+
+// A row of the skills and languages step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Choice {
+    Skill(Skill),
+    Language(&'static str),
+}
+
+// Walks the player through making a new character: name, race and
+// subrace, class, ability scores, background, the skills and
+// languages they get to choose, and starting equipment, then a
+// review of the character before it's saved.
+//
+// Enter moves to the next step once the current one is valid,
+// and Esc goes back a step, or back to the SelectScreen from the first.
+pub struct CreationWizard {
+    step: Step,
+    error: Option<String>,
+    name: String,
+    races: Vec<Race>,
+    // Each race, or each of its subraces if it has any,
+    // as (race, subrace) indexes.
+    race_options: Vec<(usize, Option<usize>)>,
+    race: ListState,
+    classes: Vec<Class>,
+    class: ListState,
+    method: ScoreMethod,
+    scores: HashMap<Stats, u8>,
+    // Abilities given the +1s of the race's choice.
+    ability_choices: Vec<Stats>,
+    stat: ListState,
+    backgrounds: Vec<Background>,
+    background: ListState,
+    skills: Vec<Skill>,
+    languages: Vec<&'static str>,
+    choice: ListState,
+    picker: Option<EquipmentPicker>,
+    roller: Roller,
+    theme: Theme,
+}
+
+impl CreationWizard {
+    pub fn new(theme: Theme) -> CreationWizard {
+        let races = srd::races();
+        let race_options = races
+            .iter()
+            .enumerate()
+            .flat_map(|(i, race)| match race.subraces.len() {
+                0 => vec![(i, None)],
+                count => (0..count).map(|j| (i, Some(j))).collect(),
+            })
+            .collect();
+        let selected = || {
+            let mut state = ListState::default();
+            state.select(Some(0));
+            state
+        };
+        let mut wizard = CreationWizard {
+            step: Step::Name,
+            error: None,
+            name: String::new(),
+            races,
+            race_options,
+            race: selected(),
+            classes: srd::classes(),
+            class: selected(),
+            method: ScoreMethod::StandardArray,
+            scores: HashMap::new(),
+            ability_choices: Vec::new(),
+            stat: selected(),
+            backgrounds: srd::backgrounds(),
+            background: selected(),
+            skills: Vec::new(),
+            languages: Vec::new(),
+            choice: selected(),
+            picker: None,
+            roller: Roller::new(),
+            theme,
+        };
+        wizard.reset_scores();
+        wizard
+    }
+
+    fn selected_race(&self) -> (&Race, Option<&Subrace>) {
+        let (race, subrace) = self.race_options[self.race.selected().unwrap_or(0)];
+        let race = &self.races[race];
+        (race, subrace.map(|subrace| &race.subraces[subrace]))
+    }
+
+    fn race_name(&self) -> String {
+        match self.selected_race() {
+            (_, Some(subrace)) => subrace.name.clone(),
+            (race, None) => race.name.clone(),
+        }
+    }
+
+    fn selected_class(&self) -> &Class {
+        &self.classes[self.class.selected().unwrap_or(0)]
+    }
+
+    fn selected_background(&self) -> &Background {
+        &self.backgrounds[self.background.selected().unwrap_or(0)]
+    }
+
+    fn reset_scores(&mut self) {
+        self.scores = match self.method {
+            ScoreMethod::StandardArray => ALL_STATS
+                .iter()
+                .copied()
+                .zip(STANDARD_ARRAY.iter().copied())
+                .collect(),
+            ScoreMethod::PointBuy => ALL_STATS.iter().map(|stat| (*stat, 8)).collect(),
+        };
+    }
+
+    fn points_spent(&self) -> u8 {
+        self.scores.values().map(|score| point_cost(*score)).sum()
+    }
+
+    fn selected_stat(&self) -> Stats {
+        ALL_STATS[self.stat.selected().unwrap_or(0)]
+    }
+
+    // Racial increase to the stat, including any chosen by the player.
+    fn racial_increase(&self, stat: &Stats) -> u8 {
+        let (race, subrace) = self.selected_race();
+        let fixed: u8 = race
+            .ability_increases
+            .iter()
+            .chain(
+                subrace
+                    .map(|s| s.ability_increases.iter())
+                    .into_iter()
+                    .flatten(),
+            )
+            .filter(|(increased, _)| increased == stat)
+            .map(|(_, increase)| increase)
+            .sum();
+        fixed
+            + self
+                .ability_choices
+                .iter()
+                .filter(|chosen| *chosen == stat)
+                .count() as u8
+    }
+
+    fn final_score(&self, stat: &Stats) -> u8 {
+        self.scores.get(stat).unwrap_or(&8) + self.racial_increase(stat)
+    }
+
+    // Raises or lowers the selected ability's score. With the
+    // standard array this swaps scores with the ability holding
+    // the next higher or lower one, so each is always used once.
+    fn adjust_score(&mut self, raise: bool) {
+        let stat = self.selected_stat();
+        let score = *self.scores.get(&stat).unwrap_or(&8);
+        match self.method {
+            ScoreMethod::StandardArray => {
+                let i = STANDARD_ARRAY.iter().position(|s| *s == score).unwrap_or(0);
+                let target = match raise {
+                    true if i > 0 => STANDARD_ARRAY[i - 1],
+                    false if i + 1 < STANDARD_ARRAY.len() => STANDARD_ARRAY[i + 1],
+                    _ => return,
+                };
+                if let Some(other) = ALL_STATS
+                    .iter()
+                    .find(|other| self.scores.get(other) == Some(&target))
+                {
+                    self.scores.insert(*other, score);
+                }
+                self.scores.insert(stat, target);
+            }
+            ScoreMethod::PointBuy => {
+                if raise && score < 15 {
+                    let cost = point_cost(score + 1) - point_cost(score);
+                    if self.points_spent() + cost <= POINT_BUY_BUDGET {
+                        self.scores.insert(stat, score + 1);
+                    }
+                } else if !raise && score > 8 {
+                    self.scores.insert(stat, score - 1);
+                }
+            }
+        }
+    }
+
+    // Gives or takes back one of the race's +1s of the player's
+    // choice on the selected ability. They can't go to an ability
+    // the race already increases.
+    fn toggle_ability_choice(&mut self) {
+        let stat = self.selected_stat();
+        if let Some(i) = self
+            .ability_choices
+            .iter()
+            .position(|chosen| *chosen == stat)
+        {
+            self.ability_choices.remove(i);
+            return;
+        }
+        let (race, _) = self.selected_race();
+        if self.ability_choices.len() >= race.ability_choices as usize {
+            self.error = Some(format!(
+                "{} only gets {} increases of your choice",
+                race.name, race.ability_choices
+            ));
+        } else if race
+            .ability_increases
+            .iter()
+            .any(|(increased, _)| *increased == stat)
+        {
+            self.error = Some(format!("{} already increases {}", race.name, stat));
+        } else {
+            self.ability_choices.push(stat);
+        }
+    }
+
+    // Skills the character already has from their race and background.
+    fn granted_skills(&self) -> Vec<String> {
+        let (race, subrace) = self.selected_race();
+        race.proficiencies
+            .iter()
+            .chain(
+                subrace
+                    .map(|s| s.proficiencies.iter())
+                    .into_iter()
+                    .flatten(),
+            )
+            .chain(self.selected_background().proficiencies.iter().flatten())
+            .filter(|proficiency| matches!(proficiency.class, Some(ProficiencyClass::Skill)))
+            .filter_map(|proficiency| proficiency.name.clone())
+            .collect()
+    }
+
+    fn known_languages(&self) -> Vec<String> {
+        let (race, _) = self.selected_race();
+        race.languages
+            .iter()
+            .chain(self.selected_background().languages.iter().flatten())
+            .map(|language| language.name.clone())
+            .collect()
+    }
+
+    fn skill_choices(&self) -> usize {
+        (self.selected_class().skill_choices + self.selected_race().0.skill_choices) as usize
+    }
+
+    fn language_choices(&self) -> usize {
+        let (race, subrace) = self.selected_race();
+        (race.language_choices
+            + subrace.map(|s| s.language_choices).unwrap_or(0)
+            + self.selected_background().language_choices) as usize
+    }
+
+    fn choices(&self) -> Vec<Choice> {
+        let known = self.known_languages();
+        Skill::get_all_skills()
+            .into_iter()
+            .map(Choice::Skill)
+            .chain(
+                srd::all_languages()
+                    .into_iter()
+                    .filter(|language| !known.iter().any(|known| known == language))
+                    .map(Choice::Language),
+            )
+            .collect()
+    }
+
+    // Whether the skill can be picked on top of those already
+    // chosen: class skills from the class's options, and any skill
+    // for the race's picks, ie. the Half-Elf's Skill Versatility.
+    fn can_choose_skill(&self, skill: &Skill) -> Result<(), String> {
+        let class = self.selected_class();
+        let (race, _) = self.selected_race();
+        if self.granted_skills().contains(&skill.to_string()) {
+            return Err(format!("Already proficient in {}", skill));
+        }
+        if self.skills.len() >= self.skill_choices() {
+            return Err(format!(
+                "Only {} skills can be chosen",
+                self.skill_choices()
+            ));
+        }
+        let outside_class = self
+            .skills
+            .iter()
+            .filter(|chosen| !class.skill_options.contains(chosen))
+            .count();
+        if !class.skill_options.contains(skill) && outside_class >= race.skill_choices as usize {
+            return Err(format!("{} isn't a {} skill", skill, class.name));
+        }
+        Ok(())
+    }
+
+    fn toggle_choice(&mut self) {
+        let choices = self.choices();
+        let choice = match choices.get(self.choice.selected().unwrap_or(0)) {
+            Some(choice) => *choice,
+            None => return,
+        };
+        match choice {
+            Choice::Skill(skill) => {
+                if let Some(i) = self.skills.iter().position(|chosen| *chosen == skill) {
+                    self.skills.remove(i);
+                } else if let Err(error) = self.can_choose_skill(&skill) {
+                    self.error = Some(error);
+                } else {
+                    self.skills.push(skill);
+                }
+            }
+            Choice::Language(language) => {
+                if let Some(i) = self.languages.iter().position(|chosen| *chosen == language) {
+                    self.languages.remove(i);
+                } else if self.languages.len() >= self.language_choices() {
+                    self.error = Some(format!(
+                        "Only {} languages can be chosen",
+                        self.language_choices()
+                    ));
+                } else {
+                    self.languages.push(language);
+                }
+            }
+        }
+    }
+
+    // Drops anything chosen for an earlier race, class or background
+    // that's no longer allowed, when going back and changing them.
+    fn prune_choices(&mut self) {
+        let (race, _) = self.selected_race();
+        let increases = race.ability_increases.clone();
+        let allowed = race.ability_choices as usize;
+        self.ability_choices
+            .retain(|stat| !increases.iter().any(|(increased, _)| increased == stat));
+        self.ability_choices.truncate(allowed);
+
+        let skills = std::mem::take(&mut self.skills);
+        for skill in skills {
+            if self.can_choose_skill(&skill).is_ok() {
+                self.skills.push(skill);
+            }
+        }
+        let known = self.known_languages();
+        self.languages
+            .retain(|language| !known.iter().any(|known| known == language));
+        self.languages.truncate(self.language_choices());
+    }
+
+    // Checks the current step before moving on from it.
+    fn validate(&self) -> Result<(), String> {
+        match self.step {
+            Step::Name if self.name.trim().is_empty() => Err("Enter a name".to_string()),
+            Step::AbilityScores => {
+                let mut scores = self.scores.values().copied().collect::<Vec<_>>();
+                scores.sort_unstable_by(|a, b| b.cmp(a));
+                let race = self.selected_race().0;
+                if self.method == ScoreMethod::StandardArray && scores != STANDARD_ARRAY {
+                    Err("Each score of the standard array must be used once".to_string())
+                } else if self.points_spent() > POINT_BUY_BUDGET {
+                    Err(format!("Only {} points can be spent", POINT_BUY_BUDGET))
+                } else if self.ability_choices.len() != race.ability_choices as usize {
+                    Err(format!(
+                        "Choose {} abilities to increase by 1 (b)",
+                        race.ability_choices
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            Step::Choices if self.skills.len() != self.skill_choices() => {
+                Err(format!("Choose {} skills", self.skill_choices()))
+            }
+            Step::Choices if self.languages.len() != self.language_choices() => {
+                Err(format!("Choose {} languages", self.language_choices()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn forward(&mut self) -> HandleKeyboardInput {
+        if let Err(error) = self.validate() {
+            self.error = Some(error);
+            return Input;
+        }
+        let mut load = Vec::new();
+        match self.step {
+            Step::Review => return SaveCharacter(Box::new(self.finish())),
+            // Entering the steps that depend on earlier ones
+            // clears out choices those no longer allow.
+            Step::Class | Step::Background => self.prune_choices(),
+            Step::Choices => {
+                let mut picker =
+                    EquipmentPicker::new(self.selected_class(), self.selected_background());
+                load = picker.start();
+                self.picker = Some(picker);
+            }
+            _ => {}
+        }
+        let steps = Step::into_enum_iter().collect::<Vec<_>>();
+        let i = steps
+            .iter()
+            .position(|step| *step == self.step)
+            .unwrap_or(0);
+        self.step = steps[(i + 1).min(steps.len() - 1)];
+        load_items(load)
+    }
+
+    fn back(&mut self) -> HandleKeyboardInput {
+        let steps = Step::into_enum_iter().collect::<Vec<_>>();
+        match steps.iter().position(|step| *step == self.step) {
+            Some(0) | None => ChangeState(SelectScreen),
+            Some(i) => {
+                self.step = steps[i - 1];
+                Input
+            }
+        }
+    }
+
+    // The character as chosen so far, without their starting equipment.
+    fn build(&self) -> Character {
+        let (race, subrace) = self.selected_race();
+        let class = self.selected_class();
+        let background = self.selected_background();
+
+        let mut character = Character::new();
+        character.name = self.name.trim().to_string();
+        character.race = self.race_name();
+        character.class = class.name.clone();
+        character.level = 1;
+        character.proficiency_bonus = 2;
+        character.speed = race.speed;
+        for stat in ALL_STATS {
+            character.stats.insert(stat, self.final_score(&stat));
+            character
+                .saving_throws
+                .insert(stat, *class.saving_throws.get(&stat).unwrap_or(&false));
+        }
+
+        character.proficiencies = race
+            .proficiencies
+            .iter()
+            .chain(
+                subrace
+                    .map(|s| s.proficiencies.iter())
+                    .into_iter()
+                    .flatten(),
+            )
+            .chain(class.proficiencies.iter())
+            .chain(background.proficiencies.iter().flatten())
+            .cloned()
+            .collect();
+        character.proficiencies.extend(
+            self.skills
+                .iter()
+                .map(|skill| srd::proficiency(ProficiencyClass::Skill, &skill.to_string())),
+        );
+        character.languages = race
+            .languages
+            .iter()
+            .chain(background.languages.iter().flatten())
+            .cloned()
+            .collect();
+        character.languages.extend(
+            self.languages
+                .iter()
+                .map(|language| srd::language(language)),
+        );
+
+        // Hit points at first level are the hit die's maximum plus the
+        // CON modifier, and AC without armor is 10 plus DEX.
+        let hit_points = class.hit_dice.1 as i16 + character.modifier(&CON) as i16;
+        character.max_hit_points = hit_points.max(1) as u16;
+        character.hit_points = character.max_hit_points;
+        character.armor_class = (10 + character.modifier(&DEX)) as u8;
+        character.initiative = character.modifier(&DEX).max(0) as u8;
+        let perception = match self
+            .granted_skills()
+            .contains(&Skill::Perception.to_string())
+            || self.skills.contains(&Skill::Perception)
+        {
+            true => character.proficiency_bonus as i8,
+            false => 0,
+        };
+        character.passive_perception = (10 + character.modifier(&WIS) + perception) as u8;
+        character
+    }
+
+    // The finished character, given their starting equipment
+    // or their rolled starting gold.
+    fn finish(&mut self) -> Character {
+        let mut character = self.build();
+        if let Some(picker) = &self.picker {
+            picker.apply(&mut character, &mut self.roller);
+        }
+        character
+    }
+
+    fn handle_step(&mut self, event: KeyEvent) -> HandleKeyboardInput {
+        let down = matches!(event.code, KeyCode::Char('j') | KeyCode::Down);
+        let up = matches!(event.code, KeyCode::Char('k') | KeyCode::Up);
+        match self.step {
+            Step::Name => match event.code {
+                KeyCode::Char(c) if self.name.len() < 40 => self.name.push(c),
+                KeyCode::Backspace => {
+                    self.name.pop();
+                }
+                _ => return Void,
+            },
+            Step::Race if down || up => {
+                move_selection(&mut self.race, self.race_options.len(), down)
+            }
+            Step::Class if down || up => move_selection(&mut self.class, self.classes.len(), down),
+            Step::Background if down || up => {
+                move_selection(&mut self.background, self.backgrounds.len(), down)
+            }
+            Step::AbilityScores => match event.code {
+                _ if down || up => move_selection(&mut self.stat, ALL_STATS.len(), down),
+                KeyCode::Char('l') | KeyCode::Right => self.adjust_score(true),
+                KeyCode::Char('h') | KeyCode::Left => self.adjust_score(false),
+                KeyCode::Char('m') => {
+                    self.method = match self.method {
+                        ScoreMethod::StandardArray => ScoreMethod::PointBuy,
+                        ScoreMethod::PointBuy => ScoreMethod::StandardArray,
+                    };
+                    self.reset_scores();
+                }
+                KeyCode::Char('b') => self.toggle_ability_choice(),
+                _ => return Void,
+            },
+            Step::Choices => match event.code {
+                _ if down || up => {
+                    let len = self.choices().len();
+                    move_selection(&mut self.choice, len, down)
+                }
+                KeyCode::Char(' ') => self.toggle_choice(),
+                _ => return Void,
+            },
+            Step::Equipment => {
+                let picker = match self.picker.as_mut() {
+                    Some(picker) => picker,
+                    None => return Void,
+                };
+                match event.code {
+                    _ if down => picker.next(),
+                    _ if up => picker.previous(),
+                    KeyCode::Char('l') | KeyCode::Right => return load_items(picker.next_option()),
+                    KeyCode::Char('h') | KeyCode::Left => {
+                        return load_items(picker.previous_option())
+                    }
+                    KeyCode::Char('p') => return load_items(picker.next_pick()),
+                    KeyCode::Char('g') => picker.toggle_gold(),
+                    _ => return Void,
+                }
+            }
+            _ => return Void,
+        }
+        Input
+    }
+
+    fn help(&self) -> &'static str {
+        match self.step {
+            Step::Name => "Type a name - Enter: next, Esc: cancel",
+            Step::Race | Step::Class | Step::Background => "j/k: select - Enter: next, Esc: back",
+            Step::AbilityScores => {
+                "j/k: ability, h/l: lower/raise, m: method, b: racial +1 - Enter: next, Esc: back"
+            }
+            Step::Choices => "j/k: select, Space: choose - Enter: next, Esc: back",
+            Step::Equipment => {
+                "j/k: group, h/l: option, p: pick item, g: take gold - Enter: next, Esc: back"
+            }
+            Step::Review => "Enter: save character, Esc: back",
+        }
+    }
+
+    fn render_list<B: Backend>(
+        frame: &mut Frame<B>,
+        area: Rect,
+        title: &str,
+        items: Vec<String>,
+        state: &mut ListState,
+        theme: &Theme,
+    ) {
+        let list = List::new(items.into_iter().map(ListItem::new).collect::<Vec<_>>())
+            .block(
+                Block::default()
+                    .title(title.to_string())
+                    .borders(Borders::ALL),
+            )
+            .highlight_style(theme.highlight)
+            .highlight_symbol(">");
+        frame.render_stateful_widget(list, area, state);
+    }
+
+    fn render_details<B: Backend>(frame: &mut Frame<B>, area: Rect, details: Vec<Spans>) {
+        frame.render_widget(
+            Paragraph::new(details)
+                .block(Block::default().title("Details").borders(Borders::ALL))
+                .wrap(Wrap { trim: true }),
+            area,
+        );
+    }
+
+    fn render_step<B: Backend>(&mut self, frame: &mut Frame<B>, area: Rect) {
+        let theme = self.theme;
+        let key_style = theme.key;
+        let line = |key: &str, value: String| {
+            Spans::from(vec![
+                Span::styled(format!("{}: ", key), key_style),
+                Span::raw(value),
+            ])
+        };
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .split(area);
+
+        match self.step {
+            Step::Name => {
+                frame.render_widget(
+                    Paragraph::new(format!("{}_", self.name))
+                        .block(Block::default().title("Name").borders(Borders::ALL)),
+                    area,
+                );
+            }
+            Step::Race => {
+                let items = self
+                    .race_options
+                    .iter()
+                    .map(|(race, subrace)| match subrace {
+                        Some(subrace) => self.races[*race].subraces[*subrace].name.clone(),
+                        None => self.races[*race].name.clone(),
+                    })
+                    .collect();
+                let (race, subrace) = self.selected_race();
+                let increases = ALL_STATS
+                    .iter()
+                    .filter(|stat| self.racial_increase(stat) > 0)
+                    .map(|stat| format!("{} +{}", stat, self.racial_increase(stat)))
+                    .collect::<Vec<_>>();
+                let details = vec![
+                    line("Speed", format!("{} ft", race.speed)),
+                    line("Ability increases", increases.join(", ")),
+                    line(
+                        "Languages",
+                        race.languages
+                            .iter()
+                            .map(|language| language.name.clone())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ),
+                    line(
+                        "Proficiencies",
+                        race.proficiencies
+                            .iter()
+                            .chain(
+                                subrace
+                                    .map(|s| s.proficiencies.iter())
+                                    .into_iter()
+                                    .flatten(),
+                            )
+                            .filter_map(|proficiency| proficiency.name.clone())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ),
+                    line(
+                        "Choices",
+                        format!(
+                            "{} ability +1s, {} languages, {} skills",
+                            race.ability_choices,
+                            race.language_choices
+                                + subrace.map(|s| s.language_choices).unwrap_or(0),
+                            race.skill_choices
+                        ),
+                    ),
+                ];
+                Self::render_list(frame, columns[0], "Race", items, &mut self.race, &theme);
+                Self::render_details(frame, columns[1], details);
+            }
+            Step::Class => {
+                let items = self
+                    .classes
+                    .iter()
+                    .map(|class| class.name.clone())
+                    .collect();
+                let class = self.selected_class();
+                let mut saves = ALL_STATS
+                    .iter()
+                    .filter(|stat| *class.saving_throws.get(stat).unwrap_or(&false))
+                    .map(|stat| stat.to_string())
+                    .collect::<Vec<_>>();
+                saves.sort();
+                let details = vec![
+                    line("Hit die", format!("d{}", class.hit_dice.1)),
+                    line("Saving throws", saves.join(", ")),
+                    line(
+                        "Proficiencies",
+                        class
+                            .proficiencies
+                            .iter()
+                            .filter_map(|proficiency| proficiency.name.clone())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ),
+                    line(
+                        "Skills",
+                        format!(
+                            "Choose {} from {}",
+                            class.skill_choices,
+                            class
+                                .skill_options
+                                .iter()
+                                .map(|skill| skill.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    ),
+                ];
+                Self::render_list(frame, columns[0], "Class", items, &mut self.class, &theme);
+                Self::render_details(frame, columns[1], details);
+            }
+            Step::AbilityScores => {
+                let items = ALL_STATS
+                    .iter()
+                    .map(|stat| {
+                        let score = self.final_score(stat);
+                        let modifier = (score as i8 - 10).div_euclid(2);
+                        let chosen = match self.ability_choices.contains(stat) {
+                            true => " (+1 chosen)",
+                            false => "",
+                        };
+                        format!(
+                            "{}: {:2} + {} = {:2} ({:+}){}",
+                            stat,
+                            self.scores.get(stat).unwrap_or(&8),
+                            self.racial_increase(stat),
+                            score,
+                            modifier,
+                            chosen
+                        )
+                    })
+                    .collect();
+                let mut details = vec![line("Method", self.method.to_string())];
+                if self.method == ScoreMethod::PointBuy {
+                    details.push(line(
+                        "Points",
+                        format!("{}/{} spent", self.points_spent(), POINT_BUY_BUDGET),
+                    ));
+                }
+                let race = self.selected_race().0;
+                if race.ability_choices > 0 {
+                    details.push(line(
+                        "Racial choices",
+                        format!(
+                            "{}/{} abilities chosen to increase by 1",
+                            self.ability_choices.len(),
+                            race.ability_choices
+                        ),
+                    ));
+                }
+                Self::render_list(
+                    frame,
+                    columns[0],
+                    "Ability Scores (base + racial)",
+                    items,
+                    &mut self.stat,
+                    &theme,
+                );
+                Self::render_details(frame, columns[1], details);
+            }
+            Step::Background => {
+                let items = self
+                    .backgrounds
+                    .iter()
+                    .map(|background| background.name.clone())
+                    .collect();
+                let background = self.selected_background();
+                let details = vec![
+                    line(
+                        "Proficiencies",
+                        background
+                            .proficiencies
+                            .iter()
+                            .flatten()
+                            .filter_map(|proficiency| proficiency.name.clone())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ),
+                    line(
+                        "Languages",
+                        format!("{} of your choice", background.language_choices),
+                    ),
+                    line("Equipment", background.starting_equipment.to_string()),
+                ];
+                Self::render_list(
+                    frame,
+                    columns[0],
+                    "Background",
+                    items,
+                    &mut self.background,
+                    &theme,
+                );
+                Self::render_details(frame, columns[1], details);
+            }
+            Step::Choices => {
+                let granted = self.granted_skills();
+                let class = self.selected_class();
+                let items = self
+                    .choices()
+                    .iter()
+                    .map(|choice| match choice {
+                        Choice::Skill(skill) => {
+                            let marker = if granted.contains(&skill.to_string()) {
+                                "[=]"
+                            } else if self.skills.contains(skill) {
+                                "[x]"
+                            } else {
+                                "[ ]"
+                            };
+                            let class_skill = match class.skill_options.contains(skill) {
+                                true => format!(" ({})", class.name),
+                                false => String::new(),
+                            };
+                            format!("{} Skill: {}{}", marker, skill, class_skill)
+                        }
+                        Choice::Language(language) => {
+                            let marker = match self.languages.contains(language) {
+                                true => "[x]",
+                                false => "[ ]",
+                            };
+                            format!("{} Language: {}", marker, language)
+                        }
+                    })
+                    .collect();
+                let details = vec![
+                    line(
+                        "Skills",
+                        format!("{}/{} chosen", self.skills.len(), self.skill_choices()),
+                    ),
+                    line(
+                        "Languages",
+                        format!(
+                            "{}/{} chosen",
+                            self.languages.len(),
+                            self.language_choices()
+                        ),
+                    ),
+                    line("Already known", self.known_languages().join(", ")),
+                ];
+                Self::render_list(
+                    frame,
+                    columns[0],
+                    "Skills & Languages",
+                    items,
+                    &mut self.choice,
+                    &theme,
+                );
+                Self::render_details(frame, columns[1], details);
+            }
+            Step::Equipment => {
+                if let Some(picker) = self.picker.as_mut() {
+                    picker.render(frame, area, &theme);
+                }
+            }
+            Step::Review => {
+                let character = self.build();
+                let scores = ALL_STATS
+                    .iter()
+                    .map(|stat| {
+                        format!(
+                            "{} {} ({:+})",
+                            stat,
+                            character.ability_score(stat),
+                            character.modifier(stat)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let equipment = match &self.picker {
+                    Some(picker) if picker.take_gold => "Starting gold instead".to_string(),
+                    Some(picker) => picker
+                        .selection()
+                        .iter()
+                        .map(|(item, quantity)| match quantity {
+                            1 => item.name.clone(),
+                            quantity => format!("{} x{}", item.name, quantity),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    None => String::new(),
+                };
+                let details = vec![
+                    line("Name", character.name.clone()),
+                    line("Race", character.race.clone()),
+                    line("Class", character.class.clone()),
+                    line("Background", self.selected_background().name.clone()),
+                    line("Abilities", scores.join(", ")),
+                    line(
+                        "HP / AC / Speed",
+                        format!(
+                            "{} / {} / {} ft",
+                            character.hit_points, character.armor_class, character.speed
+                        ),
+                    ),
+                    line(
+                        "Proficiencies",
+                        character
+                            .proficiencies
+                            .iter()
+                            .filter_map(|proficiency| proficiency.name.clone())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ),
+                    line(
+                        "Languages",
+                        character
+                            .languages
+                            .iter()
+                            .map(|language| language.name.clone())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ),
+                    line("Equipment", equipment),
+                ];
+                frame.render_widget(
+                    Paragraph::new(details)
+                        .block(Block::default().title("Review").borders(Borders::ALL))
+                        .wrap(Wrap { trim: true }),
+                    area,
+                );
+            }
+        }
+    }
+}
+
+fn move_selection(state: &mut ListState, len: usize, down: bool) {
+    let selected = state.selected().unwrap_or(0);
+    let selected = match down {
+        true => (selected + 1).min(len.saturating_sub(1)),
+        false => selected.saturating_sub(1),
+    };
+    state.select(Some(selected));
+}
+
+// Asks for the items the picker wants from the catalog, if any.
+fn load_items(queries: Vec<Query<Item>>) -> HandleKeyboardInput {
+    match queries.is_empty() {
+        true => Input,
+        false => LoadItems(queries),
+    }
+}
+
+impl<B: Backend> State<B> for CreationWizard {
+    fn display_screen(&mut self, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                    Constraint::Length(1),
+                ]
+                .as_ref(),
+            )
+            .split(f.size());
+
+        // The steps along the top, with the current one highlighted.
+        let mut steps = Vec::new();
+        for (i, step) in Step::into_enum_iter().enumerate() {
+            if i > 0 {
+                steps.push(Span::raw(" > "));
+            }
+            if step == self.step {
+                steps.push(Span::styled(step.to_string(), self.theme.highlight));
+            } else {
+                steps.push(Span::styled(step.to_string(), self.theme.muted));
+            }
+        }
+        f.render_widget(Paragraph::new(Spans::from(steps)), chunks[0]);
+
+        self.render_step(f, chunks[1]);
+
+        let footer = match &self.error {
+            Some(error) => Span::styled(error.clone(), self.theme.failure),
+            None => Span::raw(self.help()),
+        };
+        f.render_widget(Paragraph::new(Spans::from(footer)), chunks[2]);
+    }
+
+    fn handle_keyboard_event(&mut self, event: KeyEvent) -> Result<HandleKeyboardInput> {
+        // Any error shown is cleared by the next key pressed.
+        self.error = None;
+        match event.code {
+            KeyCode::Enter => Ok(self.forward()),
+            KeyCode::Esc => Ok(self.back()),
+            _ => Ok(self.handle_step(event)),
+        }
+    }
+
+    fn show_error(&mut self, error: String) {
+        self.error = Some(format!("Couldn't save: {}", error));
+    }
+
+    fn items_loaded(&mut self, pages: Vec<(Vec<Item>, usize)>) {
+        if let Some(picker) = self.picker.as_mut() {
+            picker.loaded(pages);
+        }
+    }
+}
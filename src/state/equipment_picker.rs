@@ -0,0 +1,342 @@
+use crate::{
+    data::{
+        background::Background,
+        class::Class,
+        currency::Price,
+        equipment::{ChoiceGroup, EquipmentCategory, EquipmentItem, StartingGold},
+        items::Item,
+        query::{Filter, Order, Query},
+    },
+    dice::Roller,
+    state::{
+        paged::{PagedList, PAGE_SIZE},
+        theme::Theme,
+    },
+    Character,
+};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+// Lets the player pick their starting equipment during character
+// creation: one option from each of the class's choice groups, then
+// the background's. Options like "any simple weapon" are filled
+// with an item from the catalog the player cycles through.
+//
+// Instead of any of it, the player can take the class's starting
+// gold, which replaces both the class and background equipment.
+//
+// The picker doesn't have the catalog; the methods that need more
+// of it return the queries for it, which are given to LoadItems,
+// and what they find is passed on to loaded.
+pub struct EquipmentPicker {
+    state: ListState,
+    groups: Vec<ChoiceGroup>,
+    // The option chosen in each group.
+    chosen: Vec<usize>,
+    // The catalog items that can fill the "any" items of each
+    // group's chosen option, loaded a page at a time as the
+    // player cycles through them; the selected one is picked.
+    picks: Vec<PagedList<Item>>,
+    // The catalog items any of the options name.
+    named: Vec<Item>,
+    // What each query last asked for is for, in the same order;
+    // a group's picks, or None for the named items.
+    loading: Vec<Option<usize>>,
+    gold: StartingGold,
+    pub take_gold: bool,
+}
+
+impl EquipmentPicker {
+    pub fn new(class: &Class, background: &Background) -> EquipmentPicker {
+        let groups = class
+            .starting_equipment
+            .groups
+            .iter()
+            .chain(background.starting_equipment.groups.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut state = ListState::default();
+        if !groups.is_empty() {
+            state.select(Some(0));
+        }
+        EquipmentPicker {
+            state,
+            chosen: vec![0; groups.len()],
+            picks: groups.iter().map(|_| PagedList::new()).collect(),
+            groups,
+            named: Vec::new(),
+            loading: Vec::new(),
+            gold: class
+                .starting_gold
+                .clone()
+                .unwrap_or_else(|| StartingGold::for_class(&class.name)),
+            take_gold: false,
+        }
+    }
+
+    pub fn next(&mut self) {
+        self.state.select(
+            self.state
+                .selected()
+                .map(|i| (i + 1).min(self.groups.len() - 1)),
+        );
+    }
+
+    pub fn previous(&mut self) {
+        self.state
+            .select(self.state.selected().map(|i| i.saturating_sub(1)));
+    }
+
+    // Cycles through the options of the selected group.
+    pub fn next_option(&mut self) -> Vec<Query<Item>> {
+        match self.state.selected() {
+            Some(i) => {
+                self.chosen[i] = (self.chosen[i] + 1) % self.groups[i].options.len();
+                self.picks[i].clear();
+                self.load(vec![(Some(i), 0)])
+            }
+            None => Vec::new(),
+        }
+    }
+
+    pub fn previous_option(&mut self) -> Vec<Query<Item>> {
+        match self.state.selected() {
+            Some(i) => {
+                let count = self.groups[i].options.len();
+                self.chosen[i] = (self.chosen[i] + count - 1) % count;
+                self.picks[i].clear();
+                self.load(vec![(Some(i), 0)])
+            }
+            None => Vec::new(),
+        }
+    }
+
+    // Cycles through the catalog items that can fill the
+    // "any" items of the selected group's chosen option.
+    pub fn next_pick(&mut self) -> Vec<Query<Item>> {
+        let i = match self.state.selected() {
+            Some(i) => i,
+            None => return Vec::new(),
+        };
+        let picks = &mut self.picks[i];
+        if picks.at_end() {
+            picks.first();
+            return Vec::new();
+        }
+        match picks.next() {
+            Some(offset) => self.load(vec![(Some(i), offset)]),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn toggle_gold(&mut self) {
+        self.take_gold = !self.take_gold;
+    }
+
+    // The queries for everything the picker starts out needing;
+    // the named items, and the first page of picks for each group.
+    pub fn start(&mut self) -> Vec<Query<Item>> {
+        let mut wanted = vec![(None, 0)];
+        wanted.extend((0..self.groups.len()).map(|i| (Some(i), 0)));
+        self.load(wanted)
+    }
+
+    // The queries for the named items, for None, or a page at the
+    // offset of a group's picks, remembering which each is for.
+    // Groups whose chosen option hasn't any "any" items are left out.
+    fn load(&mut self, wanted: Vec<(Option<usize>, usize)>) -> Vec<Query<Item>> {
+        self.loading.clear();
+        let mut queries = Vec::new();
+        for (group, offset) in wanted {
+            let query = match group {
+                None => self.named_query(),
+                Some(i) => match self.category(i) {
+                    Some(category) => category
+                        .query()
+                        .order_by("name", Order::Ascending)
+                        .limit(PAGE_SIZE)
+                        .offset(offset),
+                    None => continue,
+                },
+            };
+            self.loading.push(group);
+            queries.push(query);
+        }
+        queries
+    }
+
+    // Gives the picker what the queries it last asked for found.
+    pub fn loaded(&mut self, pages: Vec<(Vec<Item>, usize)>) {
+        for (group, (items, total)) in self.loading.drain(..).zip(pages) {
+            match group {
+                None => self.named = items,
+                Some(i) => self.picks[i].append(items, total),
+            }
+        }
+    }
+
+    // The category of the "any" item of the group's chosen option.
+    fn category(&self, group: usize) -> Option<&EquipmentCategory> {
+        self.groups[group].options[self.chosen[group]]
+            .items
+            .iter()
+            .find_map(|item| match item {
+                EquipmentItem::Any(category, _) => Some(category),
+                EquipmentItem::Named(..) => None,
+            })
+    }
+
+    // Every item the options name, along with each name without
+    // a trailing plural; see named.
+    fn named_query(&self) -> Query<Item> {
+        let mut names = Vec::new();
+        for option in self.groups.iter().flat_map(|group| group.options.iter()) {
+            for item in &option.items {
+                if let EquipmentItem::Named(name, _) = item {
+                    names.push(name.clone());
+                    if let Some(singular) = name.strip_suffix('s') {
+                        names.push(singular.to_string());
+                    }
+                }
+            }
+        }
+        Query::new().filter("name", Filter::OneOf(names))
+    }
+
+    // Named items are looked up in the catalog, ignoring a trailing
+    // plural, ie. "20 Arrows"; ones that aren't in it yet are added
+    // to the character under just their name.
+    fn named(&self, name: &str) -> Item {
+        let singular = name.strip_suffix('s').unwrap_or(name);
+        self.named
+            .iter()
+            .find(|item| item.name.eq_ignore_ascii_case(name))
+            .or_else(|| {
+                self.named
+                    .iter()
+                    .find(|item| item.name.eq_ignore_ascii_case(singular))
+            })
+            .cloned()
+            .unwrap_or_else(|| Item {
+                name: name.to_string(),
+                ..Item::default()
+            })
+    }
+
+    // The items, and how many of each, the chosen option of the
+    // group gives. "Any" items with nothing in the catalog to
+    // fill them are left out.
+    fn resolve(&self, group: usize) -> Vec<(Item, u16)> {
+        let option = &self.groups[group].options[self.chosen[group]];
+        let picks = &self.picks[group];
+        option
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                EquipmentItem::Named(name, quantity) => Some((self.named(name), *quantity)),
+                EquipmentItem::Any(_, quantity) => picks
+                    .items()
+                    .get(picks.selected())
+                    .map(|pick| (pick.clone(), *quantity)),
+            })
+            .collect()
+    }
+
+    // Everything the current choices give, or nothing when
+    // taking the starting gold instead.
+    pub fn selection(&self) -> Vec<(Item, u16)> {
+        if self.take_gold {
+            return Vec::new();
+        }
+        (0..self.groups.len())
+            .flat_map(|group| self.resolve(group))
+            .collect()
+    }
+
+    // Gives the character their chosen equipment, or rolls their
+    // starting gold and puts it in their purse. The gold rolled
+    // is returned so it can be shown to the player.
+    pub fn apply(&self, character: &mut Character, roller: &mut Roller) -> Option<Price> {
+        if self.take_gold {
+            let gold = self.gold.roll(roller);
+            character.purse.add(gold);
+            return Some(gold);
+        }
+        for (item, quantity) in self.selection() {
+            character.add_item(item, quantity);
+        }
+        None
+    }
+
+    pub fn render<B: Backend>(&mut self, frame: &mut Frame<B>, area: Rect, theme: &Theme) {
+        let key_style = theme.key;
+        let title = "Starting Equipment (←/→: option, p: pick item, g: take gold)";
+
+        if self.take_gold {
+            let text = vec![Spans::from(vec![
+                Span::styled("Starting gold: ", key_style),
+                Span::raw(format!("{} instead of equipment", self.gold)),
+            ])];
+            frame.render_widget(
+                Paragraph::new(text).block(Block::default().title(title).borders(Borders::ALL)),
+                area,
+            );
+            return;
+        }
+
+        let groups = self
+            .groups
+            .iter()
+            .enumerate()
+            .map(|(i, group)| {
+                let mut spans = Vec::new();
+                for (j, option) in group.options.iter().enumerate() {
+                    if j > 0 {
+                        spans.push(Span::raw(" or "));
+                    }
+                    let label = match group.options.len() {
+                        1 => option.to_string(),
+                        _ => format!("({}) {}", (b'a' + j as u8) as char, option),
+                    };
+                    if j == self.chosen[i] {
+                        spans.push(Span::styled(label, key_style));
+                    } else {
+                        spans.push(Span::raw(label));
+                    }
+                }
+
+                // Show what the chosen option's "any" items are filled with.
+                let option = &group.options[self.chosen[i]];
+                if option
+                    .items
+                    .iter()
+                    .any(|item| matches!(item, EquipmentItem::Any(..)))
+                {
+                    let picked = self
+                        .resolve(i)
+                        .into_iter()
+                        .map(|(item, _)| item.name)
+                        .collect::<Vec<_>>();
+                    let picked = match picked.is_empty() {
+                        true => "nothing in the catalog".to_string(),
+                        false => picked.join(", "),
+                    };
+                    spans.push(Span::raw(format!(" [{}]", picked)));
+                }
+                ListItem::new(Spans::from(spans))
+            })
+            .collect::<Vec<_>>();
+
+        let groups = List::new(groups)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">");
+        frame.render_stateful_widget(groups, area, &mut self.state);
+    }
+}
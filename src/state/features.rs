@@ -0,0 +1,168 @@
+use crate::{
+    data::feature::{Feature, FeatureClass},
+    state::{
+        keymap::{Action, Keymap, Screen},
+        theme::Theme,
+    },
+    Character,
+};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+// The order feature groups are listed in on the Features tab.
+const GROUPS: [&str; 5] = ["Racial", "Class", "Background", "Feat", "Other"];
+
+fn group(feature: &Feature) -> usize {
+    let class = feature
+        .class
+        .as_ref()
+        .unwrap_or(&FeatureClass::Other)
+        .to_string();
+    GROUPS
+        .iter()
+        .position(|group| *group == class)
+        .unwrap_or(GROUPS.len() - 1)
+}
+
+// The character's features, feats included, in the order
+// they're listed; grouped by where they come from.
+pub fn sorted_features(character: &Character) -> Vec<Feature> {
+    let mut features = character.all_features();
+    features.sort_by_key(group);
+    features
+}
+
+// Lists the character's features under a heading for each kind,
+// with the selected feature's description in a pane beside it
+// that can be scrolled through.
+pub struct FeaturePanel {
+    selected: usize,
+    scroll: u16,
+    // Where the description was drawn, for scrolling it with the mouse.
+    pub description_area: Rect,
+}
+
+impl FeaturePanel {
+    pub fn new() -> FeaturePanel {
+        FeaturePanel {
+            selected: 0,
+            scroll: 0,
+            description_area: Rect::default(),
+        }
+    }
+
+    pub fn next(&mut self, character: &Character) {
+        let count = character.all_features().len();
+        self.selected = (self.selected + 1).min(count.saturating_sub(1));
+        self.scroll = 0;
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+        self.scroll = 0;
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.scroll = self.scroll.saturating_add(1);
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    pub fn selected(&self, character: &Character) -> Option<Feature> {
+        sorted_features(character).get(self.selected).cloned()
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        area: Rect,
+        character: &Character,
+        keymap: &Keymap,
+        theme: &Theme,
+    ) {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .split(area);
+        self.description_area = chunks[1];
+
+        let key_style = theme.key;
+
+        // Headings take up rows of the list too, so the selected
+        // feature's row is found while the list is built.
+        let features = sorted_features(character);
+        let mut items = Vec::new();
+        let mut selected_row = None;
+        let mut last_group = None;
+        for (i, feature) in features.iter().enumerate() {
+            if last_group != Some(group(feature)) {
+                last_group = Some(group(feature));
+                items.push(ListItem::new(Span::styled(
+                    GROUPS[group(feature)],
+                    key_style,
+                )));
+            }
+            if i == self.selected {
+                selected_row = Some(items.len());
+            }
+            let uses = match (character.current_uses(feature), character.max_uses(feature)) {
+                (Some(current), Some(max)) => format!(" ({}/{})", current, max),
+                _ => String::new(),
+            };
+            items.push(ListItem::new(format!("  {}{}", feature.name, uses)));
+        }
+
+        let mut state = ListState::default();
+        state.select(selected_row);
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(format!(
+                        "Features ({})",
+                        keymap.hints(
+                            Screen::Sheet,
+                            &[(&[Action::Use, Action::Restore], "use/restore")]
+                        )
+                    ))
+                    .borders(Borders::RIGHT),
+            )
+            .highlight_style(theme.highlight)
+            .highlight_symbol(">");
+        frame.render_stateful_widget(list, chunks[0], &mut state);
+
+        let details = match features.get(self.selected) {
+            Some(feature) => {
+                let mut lines = vec![Spans::from(vec![
+                    Span::styled("Source: ", key_style),
+                    Span::raw(GROUPS[group(feature)]),
+                ])];
+                if let Some(resource) = &feature.resource {
+                    lines.push(Spans::from(vec![
+                        Span::styled("Uses: ", key_style),
+                        Span::raw(format!(
+                            "{}/{} ({})",
+                            character.current_uses(feature).unwrap_or(0),
+                            character.max_uses(feature).unwrap_or(0),
+                            resource.recharge
+                        )),
+                    ]));
+                }
+                lines.push(Spans::from(""));
+                lines.push(Spans::from(feature.description.as_str()));
+                Paragraph::new(lines)
+                    .block(Block::default().title(format!(" {} (PgUp/PgDn: scroll)", feature.name)))
+                    .wrap(Wrap { trim: false })
+                    .scroll((self.scroll, 0))
+            }
+            None => Paragraph::new("No features").block(Block::default().title(" Features")),
+        };
+        frame.render_widget(details, chunks[1]);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::{
+    state::{
+        keymap::{Action, Keymap, Screen},
+        theme::Theme,
+    },
+    Character,
+};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+
+// The keys of the character's stacks in the order they're listed,
+// each followed by whatever is stored inside it, with how deeply
+// it's nested.
+fn listed(character: &Character) -> Vec<(u32, usize)> {
+    fn push(character: &Character, key: u32, depth: usize, rows: &mut Vec<(u32, usize)>) {
+        // A bad save could have left containers inside each other.
+        if rows.iter().any(|(listed, _)| *listed == key) {
+            return;
+        }
+        rows.push((key, depth));
+        for entry in character.contents(key) {
+            push(character, entry.key, depth + 1, rows);
+        }
+    }
+
+    let mut rows = Vec::new();
+    for entry in character
+        .inventory
+        .iter()
+        .filter(|entry| entry.container.is_none())
+    {
+        push(character, entry.key, 0, &mut rows);
+    }
+    rows
+}
+
+// The Inventory tab; the character's equipment with its weight,
+// value and whether it's equipped or attuned to, and the selected
+// item's description below.
+pub struct InventoryPanel {
+    state: TableState,
+}
+
+impl InventoryPanel {
+    pub fn new() -> InventoryPanel {
+        let mut state = TableState::default();
+        state.select(Some(0));
+        InventoryPanel { state }
+    }
+
+    fn selected(&self, character: &Character) -> Option<u32> {
+        listed(character)
+            .get(self.state.selected().unwrap_or(0))
+            .map(|(key, _)| *key)
+    }
+
+    // Returns whether the action changed the character, or
+    // why what was asked for couldn't be done.
+    pub fn handle_key(
+        &mut self,
+        action: Option<Action>,
+        character: &mut Character,
+    ) -> Result<bool, String> {
+        let count = listed(character).len();
+        let selected = self.state.selected().unwrap_or(0);
+        let key = match self.selected(character) {
+            Some(key) => key,
+            None => return Ok(false),
+        };
+        match action {
+            Some(Action::Down) => {
+                self.state.select(Some((selected + 1).min(count - 1)));
+                Ok(false)
+            }
+            Some(Action::Up) => {
+                self.state.select(Some(selected.saturating_sub(1)));
+                Ok(false)
+            }
+            Some(Action::Equip) => {
+                if let Some(entry) = character.entry_mut(key) {
+                    entry.equipped = !entry.equipped;
+                }
+                Ok(true)
+            }
+            Some(Action::Attune) => {
+                match character.entry(key).map(|entry| entry.attuned) {
+                    Some(true) => character.end_attunement(key),
+                    _ => character.attune(key).map_err(|error| error.to_string())?,
+                }
+                Ok(true)
+            }
+            // Drops one of the stack, or the whole stack.
+            Some(Action::Drop | Action::DropAll) => {
+                let quantity = match action {
+                    Some(Action::Drop) => 1,
+                    _ => u16::MAX,
+                };
+                character.remove_item(key, quantity);
+                let count = listed(character).len();
+                self.state
+                    .select(Some(selected.min(count.saturating_sub(1))));
+                Ok(true)
+            }
+            // Spends a charge from the item.
+            Some(Action::Use) => match character.entry(key) {
+                Some(entry) if entry.item.charges.is_none() => {
+                    Err(format!("{} has no charges", entry.item.name))
+                }
+                Some(entry) if entry.current_charges() == Some(0) => {
+                    Err(format!("{} has no charges left", entry.item.name))
+                }
+                _ => Ok(character.spend_charges(key, 1)),
+            },
+            // Sells one of the stack for half its value; see Character::sell.
+            Some(Action::Sell) => {
+                match character.entry(key) {
+                    Some(entry) if entry.item.value.is_none() => {
+                        return Err(format!("{} can't be sold", entry.item.name))
+                    }
+                    _ => character.sell(key, 1),
+                }
+                let count = listed(character).len();
+                self.state
+                    .select(Some(selected.min(count.saturating_sub(1))));
+                Ok(true)
+            }
+            // Splits half the stack, rounded down, off into a new
+            // stack in the same place.
+            Some(Action::Split) => {
+                let half = character.entry(key).map_or(0, |entry| entry.quantity / 2);
+                match character.split_stack(key, half) {
+                    Some(_) => Ok(true),
+                    None => Err("Only stacks of more than one can be split".to_string()),
+                }
+            }
+            // Merges the stack into the next stack of the same item
+            // listed after it, going back round to the top of the list.
+            Some(Action::Merge) => {
+                let rows = listed(character);
+                let entry = character.entry(key).cloned();
+                let into = rows
+                    .iter()
+                    .cycle()
+                    .skip(selected + 1)
+                    .take(count.saturating_sub(1))
+                    .map(|(other, _)| *other)
+                    .find(|other| {
+                        let other = character.entry(*other);
+                        matches!((&entry, other), (Some(entry), Some(other)) if entry.same_item(other))
+                    })
+                    .ok_or_else(|| "There's no other stack to merge with".to_string())?;
+                character.merge_stacks(key, into);
+                let row = listed(character)
+                    .iter()
+                    .position(|(listed, _)| *listed == into);
+                self.state.select(row.or(Some(0)));
+                Ok(true)
+            }
+            // Moves the stack into the next container listed after the
+            // one it's in that has room for it, or out of its container
+            // once there aren't any more.
+            Some(Action::Stow) => {
+                let current = character.entry(key).and_then(|entry| entry.container);
+                let containers = listed(character)
+                    .into_iter()
+                    .map(|(other, _)| other)
+                    .filter(|other| {
+                        character
+                            .entry(*other)
+                            .is_some_and(|entry| entry.item.container.is_some())
+                    })
+                    .collect::<Vec<_>>();
+                let next = current
+                    .and_then(|current| containers.iter().position(|other| *other == current))
+                    .map_or(0, |i| i + 1);
+                // Moving changes nothing when there isn't room.
+                let moved = containers[next..]
+                    .iter()
+                    .any(|container| character.move_to_container(key, Some(*container)));
+                if !moved {
+                    if current.is_none() {
+                        return Err("There's no container with room for it".to_string());
+                    }
+                    character.move_to_container(key, None);
+                }
+                let row = listed(character)
+                    .iter()
+                    .position(|(listed, _)| *listed == key);
+                self.state.select(row.or(Some(0)));
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        area: Rect,
+        character: &Character,
+        keymap: &Keymap,
+        theme: &Theme,
+    ) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(5)].as_ref())
+            .split(area);
+
+        let key_style = theme.key;
+
+        let rows = listed(character)
+            .into_iter()
+            .filter_map(|(key, depth)| {
+                let entry = character.entry(key)?;
+                let mut markers = String::new();
+                if entry.equipped {
+                    markers.push('E');
+                }
+                if entry.attuned {
+                    markers.push('A');
+                }
+                let charges = match (entry.current_charges(), entry.max_charges()) {
+                    (Some(current), Some(max)) => format!("{}/{}", current, max),
+                    _ => String::new(),
+                };
+                Some(Row::new(vec![
+                    format!("{}{}", "  ".repeat(depth), entry.item.name),
+                    entry.quantity.to_string(),
+                    format!("{} lb", character.stack_weight(key)),
+                    entry
+                        .item
+                        .value
+                        .map(|value| value.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                    markers,
+                    charges,
+                ]))
+            })
+            .collect::<Vec<_>>();
+
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec!["Item", "Qty", "Weight", "Value", "", "Charges"]).style(key_style),
+            )
+            .block(
+                Block::default()
+                    .title(format!(
+                        "Inventory ({})",
+                        keymap.hints(
+                            Screen::Sheet,
+                            &[
+                                (&[Action::Equip], "equip"),
+                                (&[Action::Attune], "attune"),
+                                (&[Action::Use], "use"),
+                                (&[Action::Drop, Action::DropAll], "drop one/all"),
+                                (&[Action::Split, Action::Merge], "split/merge"),
+                                (&[Action::Stow], "stow"),
+                                (&[Action::Sell], "sell"),
+                            ]
+                        )
+                    ))
+                    .borders(Borders::BOTTOM),
+            )
+            .highlight_style(theme.highlight)
+            .highlight_symbol(">")
+            .widths(&[
+                Constraint::Percentage(40),
+                Constraint::Length(5),
+                Constraint::Length(8),
+                Constraint::Length(10),
+                Constraint::Length(3),
+                Constraint::Length(8),
+            ]);
+        frame.render_stateful_widget(table, chunks[0], &mut self.state);
+
+        let description = self
+            .selected(character)
+            .and_then(|key| character.entry(key))
+            .map(|entry| match entry.notes.is_empty() {
+                true => entry.item.description.clone(),
+                false => format!("{}\n{}", entry.item.description, entry.notes),
+            })
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(description).wrap(Wrap { trim: true }),
+            chunks[1],
+        );
+    }
+}
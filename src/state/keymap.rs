@@ -0,0 +1,493 @@
+use crate::state::theme::Theme;
+use anyhow::{anyhow, bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use enum_iterator::IntoEnumIterator;
+use std::{collections::HashMap, fmt};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Rect},
+    widgets::{Block, Borders, Cell, Clear, Row, Table},
+    Frame,
+};
+
+// Everything a key can be bound to. States match on these rather
+// than on the keys themselves, so keys can be changed in the config
+// file; see Keymap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoEnumIterator)]
+pub enum Action {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Select,
+    Back,
+    Quit,
+    NextTab,
+    PreviousTab,
+    Help,
+    Search,
+    Edit,
+    Write,
+    Conditions,
+    EndRound,
+    ShortRest,
+    LongRest,
+    Roll,
+    Advantage,
+    Disadvantage,
+    RollHistory,
+    Filter,
+    Use,
+    Restore,
+    Equip,
+    Attune,
+    Drop,
+    DropAll,
+    Split,
+    Merge,
+    Stow,
+    Sell,
+    Delete,
+    Duplicate,
+    Rename,
+    Sort,
+}
+
+impl Action {
+    pub fn get_all_actions() -> Vec<Action> {
+        Action::into_enum_iter().collect()
+    }
+
+    // The action's name in the config file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::Select => "select",
+            Action::Back => "back",
+            Action::Quit => "quit",
+            Action::NextTab => "next_tab",
+            Action::PreviousTab => "previous_tab",
+            Action::Help => "help",
+            Action::Search => "search",
+            Action::Edit => "edit",
+            Action::Write => "write",
+            Action::Conditions => "conditions",
+            Action::EndRound => "end_round",
+            Action::ShortRest => "short_rest",
+            Action::LongRest => "long_rest",
+            Action::Roll => "roll",
+            Action::Advantage => "advantage",
+            Action::Disadvantage => "disadvantage",
+            Action::RollHistory => "roll_history",
+            Action::Filter => "filter",
+            Action::Use => "use",
+            Action::Restore => "restore",
+            Action::Equip => "equip",
+            Action::Attune => "attune",
+            Action::Drop => "drop",
+            Action::DropAll => "drop_all",
+            Action::Split => "split",
+            Action::Merge => "merge",
+            Action::Stow => "stow",
+            Action::Sell => "sell",
+            Action::Delete => "delete",
+            Action::Duplicate => "duplicate",
+            Action::Rename => "rename",
+            Action::Sort => "sort",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Up => write!(f, "Move up"),
+            Action::Down => write!(f, "Move down"),
+            Action::PageUp => write!(f, "Scroll up"),
+            Action::PageDown => write!(f, "Scroll down"),
+            Action::Select => write!(f, "Select"),
+            Action::Back => write!(f, "Back to character select"),
+            Action::Quit => write!(f, "Quit"),
+            Action::NextTab => write!(f, "Next tab"),
+            Action::PreviousTab => write!(f, "Previous tab"),
+            Action::Help => write!(f, "Show this help"),
+            Action::Search => write!(f, "Search everything"),
+            Action::Edit => write!(f, "Edit name, alignment, XP and HP"),
+            Action::Write => write!(f, "Write changes"),
+            Action::Conditions => write!(f, "Conditions"),
+            Action::EndRound => write!(f, "End the round"),
+            Action::ShortRest => write!(f, "Take a short rest"),
+            Action::LongRest => write!(f, "Take a long rest"),
+            Action::Roll => write!(f, "Roll"),
+            Action::Advantage => write!(f, "Toggle advantage"),
+            Action::Disadvantage => write!(f, "Toggle disadvantage"),
+            Action::RollHistory => write!(f, "Roll history"),
+            Action::Filter => write!(f, "Filter the list"),
+            Action::Use => write!(f, "Use feature / spend spell slot"),
+            Action::Restore => write!(f, "Restore feature use / spell slot"),
+            Action::Equip => write!(f, "Equip item"),
+            Action::Attune => write!(f, "Attune to item"),
+            Action::Drop => write!(f, "Drop one of item"),
+            Action::DropAll => write!(f, "Drop all of item"),
+            Action::Split => write!(f, "Split stack in half"),
+            Action::Merge => write!(f, "Merge stack into the next"),
+            Action::Stow => write!(f, "Move item to the next container"),
+            Action::Sell => write!(f, "Sell one of item for half its value"),
+            Action::Delete => write!(f, "Delete character"),
+            Action::Duplicate => write!(f, "Duplicate character"),
+            Action::Rename => write!(f, "Rename character"),
+            Action::Sort => write!(f, "Change sort order"),
+        }
+    }
+}
+
+// The screens with their own bindings, and their sections
+// under [keys] in the config file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoEnumIterator)]
+pub enum Screen {
+    Select,
+    Sheet,
+}
+
+impl Screen {
+    fn name(&self) -> &'static str {
+        match self {
+            Screen::Select => "select",
+            Screen::Sheet => "sheet",
+        }
+    }
+
+    // Each screen's actions along with the keys they're bound
+    // to when the config file doesn't say otherwise.
+    fn defaults(&self) -> Vec<(Action, Vec<&'static str>)> {
+        let navigation = vec![
+            (Action::Up, vec!["k", "Up"]),
+            (Action::Down, vec!["j", "Down"]),
+            (Action::Select, vec!["Enter"]),
+            (Action::Quit, vec!["Esc"]),
+            (Action::Help, vec!["?"]),
+            (Action::Search, vec!["/", "Ctrl+p"]),
+        ];
+        match self {
+            Screen::Select => navigation
+                .into_iter()
+                .chain(vec![
+                    (Action::Delete, vec!["d"]),
+                    (Action::Duplicate, vec!["c"]),
+                    (Action::Rename, vec!["r"]),
+                    (Action::Sort, vec!["s"]),
+                    (Action::Filter, vec!["f"]),
+                ])
+                .collect(),
+            Screen::Sheet => navigation
+                .into_iter()
+                .chain(vec![
+                    (Action::PageUp, vec!["PageUp"]),
+                    (Action::PageDown, vec!["PageDown"]),
+                    (Action::Back, vec!["q"]),
+                    (Action::NextTab, vec!["Tab"]),
+                    (Action::PreviousTab, vec!["BackTab"]),
+                    (Action::Edit, vec!["e"]),
+                    (Action::Write, vec!["w"]),
+                    (Action::Conditions, vec!["c"]),
+                    (Action::EndRound, vec!["n"]),
+                    (Action::ShortRest, vec!["s"]),
+                    (Action::LongRest, vec!["S"]),
+                    (Action::Roll, vec!["r"]),
+                    (Action::Advantage, vec!["a"]),
+                    (Action::Disadvantage, vec!["d"]),
+                    (Action::RollHistory, vec!["h"]),
+                    (Action::Filter, vec!["f"]),
+                    (Action::Use, vec!["u"]),
+                    (Action::Restore, vec!["U"]),
+                    (Action::Equip, vec!["Space"]),
+                    (Action::Attune, vec!["t"]),
+                    (Action::Drop, vec!["x"]),
+                    (Action::DropAll, vec!["X"]),
+                    (Action::Split, vec!["v"]),
+                    (Action::Merge, vec!["m"]),
+                    (Action::Stow, vec!["i"]),
+                    (Action::Sell, vec!["$"]),
+                ])
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Screen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Screen::Select => write!(f, "character select"),
+            Screen::Sheet => write!(f, "character sheet"),
+        }
+    }
+}
+
+// A key as it's written in the config file, ie. "j", "Enter",
+// "PageDown" or "Ctrl+s". Shift isn't part of a binding, since
+// it's already in the character typed; "U" rather than "Shift+u".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 14] = [
+    ("Enter", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("Tab", KeyCode::Tab),
+    ("BackTab", KeyCode::BackTab),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+];
+
+impl KeyBinding {
+    fn from_event(event: KeyEvent) -> KeyBinding {
+        KeyBinding {
+            code: event.code,
+            modifiers: event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<KeyBinding> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut key = text;
+        // A lone "+" is the plus key rather than a modifier.
+        while let Some((modifier, rest)) = key.split_once('+').filter(|(_, rest)| !rest.is_empty())
+        {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                _ => bail!("Unknown modifier '{}' in key '{}'", modifier, text),
+            };
+            key = rest;
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ if key.eq_ignore_ascii_case("Space") => KeyCode::Char(' '),
+            _ => NAMED_KEYS
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                .map(|(_, code)| *code)
+                .ok_or_else(|| anyhow!("Unknown key '{}'", text))?,
+        };
+        Ok(KeyBinding { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            code => match NAMED_KEYS.iter().find(|(_, named)| *named == code) {
+                Some((name, _)) => write!(f, "{}", name),
+                None => write!(f, "{:?}", code),
+            },
+        }
+    }
+}
+
+// The keys bound to each action on each screen. These are the
+// defaults above with any changes from the [keys] table of the
+// config file, ie.
+//
+//     [keys.sheet]
+//     roll = ["r", "Space"]
+//     equip = "E"
+//
+// An action given in the config file only has the keys listed for
+// it there. A key can't be bound to two actions on one screen.
+#[derive(Clone)]
+pub struct Keymap {
+    bindings: HashMap<Screen, Vec<(Action, Vec<KeyBinding>)>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Screen::into_enum_iter()
+            .map(|screen| {
+                let actions = screen
+                    .defaults()
+                    .into_iter()
+                    .map(|(action, keys)| {
+                        let keys = keys
+                            .into_iter()
+                            .map(|key| KeyBinding::parse(key).unwrap())
+                            .collect();
+                        (action, keys)
+                    })
+                    .collect();
+                (screen, actions)
+            })
+            .collect();
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    // Builds the keymap from the [keys] table of the config file.
+    pub fn from_config(keys: Option<&toml::Value>) -> Result<Keymap> {
+        let mut keymap = Keymap::default();
+        let keys = match keys {
+            Some(keys) => keys
+                .as_table()
+                .ok_or_else(|| anyhow!("[keys] should be a table of screens"))?,
+            None => return Ok(keymap),
+        };
+
+        for (screen_name, actions) in keys {
+            let screen = Screen::into_enum_iter()
+                .find(|screen| screen.name() == screen_name)
+                .ok_or_else(|| anyhow!("Unknown screen [keys.{}]", screen_name))?;
+            let actions = actions
+                .as_table()
+                .ok_or_else(|| anyhow!("[keys.{}] should be a table", screen_name))?;
+            let bound = keymap.bindings.get_mut(&screen).unwrap();
+
+            for (action_name, value) in actions {
+                let entry = bound
+                    .iter_mut()
+                    .find(|(action, _)| action.name() == action_name)
+                    .ok_or_else(|| {
+                        anyhow!("Unknown action '{}' in [keys.{}]", action_name, screen_name)
+                    })?;
+                let keys = match value {
+                    toml::Value::String(key) => vec![key.as_str()],
+                    toml::Value::Array(keys) => keys
+                        .iter()
+                        .map(|key| key.as_str())
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| anyhow!("Keys for '{}' should be strings", action_name))?,
+                    _ => bail!("Keys for '{}' should be a string or a list", action_name),
+                };
+                entry.1 = keys
+                    .into_iter()
+                    .map(KeyBinding::parse)
+                    .collect::<Result<_>>()?;
+            }
+        }
+
+        keymap.check_conflicts()?;
+        Ok(keymap)
+    }
+
+    fn check_conflicts(&self) -> Result<()> {
+        for screen in Screen::into_enum_iter() {
+            let mut seen: HashMap<KeyBinding, Action> = HashMap::new();
+            for (action, keys) in &self.bindings[&screen] {
+                for key in keys {
+                    if let Some(other) = seen.insert(*key, *action) {
+                        bail!(
+                            "'{}' is bound to both {} and {} on the {}",
+                            key,
+                            other.name(),
+                            action.name(),
+                            screen
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // The action the key is bound to on the screen, if any.
+    pub fn action(&self, screen: Screen, event: KeyEvent) -> Option<Action> {
+        let key = KeyBinding::from_event(event);
+        self.bindings[&screen]
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    // Hints for a title, ie. "u/U: use/restore, t: attune", with the
+    // first key bound to each action. Actions sharing a hint have
+    // their keys joined with "/", and hints for actions without any
+    // keys are left out.
+    pub fn hints(&self, screen: Screen, hints: &[(&[Action], &str)]) -> String {
+        hints
+            .iter()
+            .filter_map(|(actions, hint)| {
+                let keys = actions
+                    .iter()
+                    .map(|action| {
+                        self.bindings[&screen]
+                            .iter()
+                            .find(|(bound, _)| bound == action)
+                            .and_then(|(_, keys)| keys.first())
+                            .map(|key| key.to_string())
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(format!("{}: {}", keys.join("/"), hint))
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // Help for the screen; every action with the keys bound to it,
+    // in the order the actions are declared.
+    pub fn render_help<B: Backend>(&self, frame: &mut Frame<B>, screen: Screen, theme: &Theme) {
+        let key_style = theme.key;
+
+        let rows = Action::get_all_actions()
+            .into_iter()
+            .filter_map(|action| {
+                let (_, keys) = self.bindings[&screen]
+                    .iter()
+                    .find(|(bound, _)| *bound == action)?;
+                let keys = keys
+                    .iter()
+                    .map(|key| key.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                Some(Row::new(vec![
+                    Cell::from(keys).style(key_style),
+                    Cell::from(action.to_string()),
+                ]))
+            })
+            .collect::<Vec<_>>();
+
+        let area = frame.size();
+        let height = (rows.len() as u16 + 2).min(area.height);
+        let width = 56.min(area.width);
+        let area = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+        let widths = [
+            Constraint::Length(16),
+            Constraint::Length(width.saturating_sub(18)),
+        ];
+        let help = Table::new(rows)
+            .block(
+                Block::default()
+                    .title(format!("Keys - {} (any key to close)", screen))
+                    .borders(Borders::ALL),
+            )
+            .widths(&widths);
+        frame.render_widget(Clear, area);
+        frame.render_widget(help, area);
+    }
+}
@@ -1,4 +1,22 @@
 pub mod app;
+mod biography;
 mod character_sheet;
+mod creation_wizard;
+mod equipment_picker;
+mod features;
+mod inventory;
+pub mod keymap;
+mod mouse;
+mod notes;
+mod paged;
+mod palette;
+mod roll_history;
+mod rolls;
 mod select_screen;
+mod sheet_fields;
+mod spells;
 mod tabs;
+#[cfg(test)]
+mod tests;
+mod text_area;
+pub mod theme;
@@ -0,0 +1,72 @@
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use tui::layout::Rect;
+
+// What the user did with the mouse, reduced to what the states
+// care about; a click or a turn of the wheel at a position.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mouse {
+    Click(u16, u16),
+    ScrollUp(u16, u16),
+    ScrollDown(u16, u16),
+}
+
+impl Mouse {
+    // Only the left button is used; everything else is ignored.
+    pub fn from_event(event: MouseEvent) -> Option<Mouse> {
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => Some(Mouse::Click(event.column, event.row)),
+            MouseEventKind::ScrollUp => Some(Mouse::ScrollUp(event.column, event.row)),
+            MouseEventKind::ScrollDown => Some(Mouse::ScrollDown(event.column, event.row)),
+            _ => None,
+        }
+    }
+
+    pub fn position(&self) -> (u16, u16) {
+        match *self {
+            Mouse::Click(column, row)
+            | Mouse::ScrollUp(column, row)
+            | Mouse::ScrollDown(column, row) => (column, row),
+        }
+    }
+
+    pub fn is_in(&self, area: Rect) -> bool {
+        let (column, row) = self.position();
+        column >= area.x && column < area.right() && row >= area.y && row < area.bottom()
+    }
+}
+
+// Where a list was last drawn and how far it had scrolled, to work
+// out which item a click landed on. tui doesn't give the scroll
+// offset back, so it's followed here the same way tui moves it to
+// keep the selected item on screen. Every item is one line high.
+#[derive(Clone, Copy, Default)]
+pub struct ListArea {
+    area: Rect,
+    offset: usize,
+}
+
+impl ListArea {
+    // Called with the area inside the list's block each time it's drawn.
+    pub fn update(&mut self, area: Rect, selected: Option<usize>, len: usize) {
+        let height = area.height as usize;
+        let selected = selected.unwrap_or(0).min(len.saturating_sub(1));
+        let mut offset = self.offset.min(len.saturating_sub(1));
+        if height > 0 && selected >= offset + height {
+            offset = selected + 1 - height;
+        }
+        if selected < offset {
+            offset = selected;
+        }
+        self.area = area;
+        self.offset = offset;
+    }
+
+    // The index of the item under the mouse, which may be past the
+    // end of a list that doesn't fill its area.
+    pub fn item_at(&self, mouse: Mouse) -> Option<usize> {
+        if !mouse.is_in(self.area) {
+            return None;
+        }
+        Some(self.offset + (mouse.position().1 - self.area.y) as usize)
+    }
+}
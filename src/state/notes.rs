@@ -0,0 +1,92 @@
+use crate::{
+    state::{
+        keymap::{Action, Keymap, Screen},
+        text_area::{TextArea, TextInput},
+    },
+    Character,
+};
+use crossterm::event::KeyEvent;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    widgets::{Block, Paragraph, Wrap},
+    Frame,
+};
+
+// The Notes tab; a single page of free-form text for anything the
+// player wants to keep track of. Enter starts editing the notes
+// and Esc finishes, writing them to the character.
+pub struct NotesPanel {
+    editor: Option<TextArea>,
+    scroll: u16,
+}
+
+impl NotesPanel {
+    pub fn new() -> NotesPanel {
+        NotesPanel {
+            editor: None,
+            scroll: 0,
+        }
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.editor.is_some()
+    }
+
+    // Returns whether the key changed the character. While text is
+    // being typed the key itself is used, otherwise its action.
+    pub fn handle_key(
+        &mut self,
+        event: KeyEvent,
+        action: Option<Action>,
+        character: &mut Character,
+    ) -> bool {
+        if let Some(editor) = self.editor.as_mut() {
+            if let TextInput::Finished = editor.handle_key(event) {
+                let changed = editor.text != character.notes;
+                character.notes = editor.text.clone();
+                self.editor = None;
+                return changed;
+            }
+            return false;
+        }
+
+        match action {
+            Some(Action::Select) => self.editor = Some(TextArea::new(&character.notes)),
+            Some(Action::Down | Action::PageDown) => self.scroll = self.scroll.saturating_add(1),
+            Some(Action::Up | Action::PageUp) => self.scroll = self.scroll.saturating_sub(1),
+            _ => {}
+        }
+        false
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        area: Rect,
+        character: &Character,
+        keymap: &Keymap,
+    ) {
+        let (title, text) = match &self.editor {
+            Some(editor) => ("Notes (Esc: done)".to_string(), editor.display()),
+            None => (
+                format!(
+                    "Notes ({})",
+                    keymap.hints(
+                        Screen::Sheet,
+                        &[
+                            (&[Action::Select], "edit"),
+                            (&[Action::Down, Action::Up], "scroll")
+                        ]
+                    )
+                ),
+                character.notes.clone(),
+            ),
+        };
+        let notes = Paragraph::new(text)
+            .block(Block::default().title(title))
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0));
+        frame.render_widget(notes, area);
+    }
+}
@@ -0,0 +1,81 @@
+// How many rows are loaded at a time.
+pub const PAGE_SIZE: usize = 50;
+
+// A list loaded from the database a page at a time, rather than all
+// at once. The next page is asked for as the selection gets near the
+// end of what's been loaded, so long lists open straight away.
+pub struct PagedList<T> {
+    items: Vec<T>,
+    // How many items there are in all, loaded or not.
+    total: usize,
+    selected: usize,
+}
+
+impl<T> PagedList<T> {
+    pub fn new() -> PagedList<T> {
+        PagedList {
+            items: Vec::new(),
+            total: 0,
+            selected: 0,
+        }
+    }
+
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    // Empties the list for it to be loaded again from the first page.
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.total = 0;
+        self.selected = 0;
+    }
+
+    // Adds a page of items after those already loaded.
+    pub fn append(&mut self, page: Vec<T>, total: usize) {
+        self.items.extend(page);
+        self.total = total.max(self.items.len());
+    }
+
+    // Adds an item before the rest, ie. one just saved in a list
+    // loaded newest first. Later pages are offset by the one added.
+    pub fn push_front(&mut self, item: T) {
+        self.items.insert(0, item);
+        self.total += 1;
+    }
+
+    // The offset of the next page to load, when the selection is
+    // within half a page of the last item loaded and there's more.
+    pub fn wanted(&self) -> Option<usize> {
+        let loaded = self.items.len();
+        match loaded < self.total && self.selected + PAGE_SIZE / 2 >= loaded {
+            true => Some(loaded),
+            false => None,
+        }
+    }
+
+    // Moves the selection down, returning the offset of the
+    // next page if it should be loaded.
+    pub fn next(&mut self) -> Option<usize> {
+        self.selected = (self.selected + 1).min(self.items.len().saturating_sub(1));
+        self.wanted()
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn first(&mut self) {
+        self.selected = 0;
+    }
+
+    // Whether the last item is selected and there
+    // aren't any more to load after it.
+    pub fn at_end(&self) -> bool {
+        self.selected + 1 >= self.total
+    }
+}
@@ -0,0 +1,320 @@
+use crate::{
+    data::{
+        catalog::{CatalogEntry, SearchHit},
+        character::SavedCharacter,
+        items::Item,
+    },
+    state::theme::Theme,
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+// A popup for finding anything in the database by typing part of
+// its name, opened over either screen. The characters typed don't
+// have to be next to each other in the name, only in the same order,
+// so "mgmsl" finds Magic Missile. Entries whose descriptions have
+// the query's words in them are listed after the names that match.
+// Only a page of each kind of entry is loaded at a time, so the
+// palette is given what matches again as the query changes.
+pub struct Palette {
+    catalog: Vec<CatalogEntry>,
+    query: String,
+    // The entries matching the query, best match first.
+    matches: Vec<Match>,
+    state: ListState,
+    // Set while the selected entry's description is shown
+    // in place of the list.
+    detail: bool,
+    scroll: u16,
+    // Entries can only be added on the character sheet.
+    can_add: bool,
+}
+
+struct Match {
+    // The entry's index in the catalog.
+    entry: usize,
+    // Set when it was found by its description rather than its name.
+    hit: Option<SearchHit>,
+}
+
+pub enum PaletteInput {
+    Handled,
+    Closed,
+    // The query changed; what it matches is to be found, for found.
+    Query(String),
+    // The character picked, to open their sheet.
+    Open(SavedCharacter),
+    // The entry picked to be added to the character on the sheet.
+    Add(CatalogEntry),
+    // The item picked to be bought, paying for it out of the purse.
+    Buy(Box<Item>),
+}
+
+impl Palette {
+    pub fn new(catalog: Vec<CatalogEntry>, can_add: bool) -> Palette {
+        let mut palette = Palette {
+            catalog,
+            query: String::new(),
+            matches: Vec::new(),
+            state: ListState::default(),
+            detail: false,
+            scroll: 0,
+            can_add,
+        };
+        palette.update_matches();
+        palette
+    }
+
+    fn selected(&self) -> Option<&CatalogEntry> {
+        let found = self.matches.get(self.state.selected()?)?;
+        self.catalog.get(found.entry)
+    }
+
+    // Ties are listed with the shortest names first, so an exact
+    // match comes before longer names that contain it.
+    fn update_matches(&mut self) {
+        let mut scored = self
+            .catalog
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| Some((fuzzy_score(&self.query, entry.name())?, i)))
+            .collect::<Vec<_>>();
+        let catalog = &self.catalog;
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then(catalog[*a].name().len().cmp(&catalog[*b].name().len()))
+                .then(catalog[*a].name().cmp(catalog[*b].name()))
+        });
+        self.matches = scored
+            .into_iter()
+            .map(|(_, entry)| Match { entry, hit: None })
+            .collect();
+        self.state.select(match self.matches.is_empty() {
+            true => None,
+            false => Some(0),
+        });
+    }
+
+    // Replaces the entries with those found for the query; the ones
+    // whose names match it, then the ones found in a search of
+    // descriptions, leaving out any already listed by name.
+    pub fn found(&mut self, catalog: Vec<CatalogEntry>, hits: Vec<(CatalogEntry, SearchHit)>) {
+        self.catalog = catalog;
+        self.update_matches();
+        for (found, hit) in hits {
+            let key = Some((hit.kind, hit.id));
+            let entry = match self.catalog.iter().position(|e| e.search_key() == key) {
+                Some(entry) => entry,
+                None => {
+                    self.catalog.push(found);
+                    self.catalog.len() - 1
+                }
+            };
+            if self.matches.iter().any(|found| found.entry == entry) {
+                continue;
+            }
+            self.matches.push(Match {
+                entry,
+                hit: Some(hit),
+            });
+        }
+        if self.state.selected().is_none() && !self.matches.is_empty() {
+            self.state.select(Some(0));
+        }
+    }
+
+    // Typed keys always go to the query, so the list is moved
+    // through with the arrow keys, or Ctrl+n and Ctrl+p.
+    pub fn handle_key(&mut self, event: KeyEvent) -> PaletteInput {
+        let control = event.modifiers.contains(KeyModifiers::CONTROL);
+        if self.detail {
+            match event.code {
+                KeyCode::Esc | KeyCode::Backspace => self.detail = false,
+                KeyCode::Enter if self.can_add => {
+                    if let Some(entry) = self.selected() {
+                        return PaletteInput::Add(entry.clone());
+                    }
+                }
+                // Only items with a value can be bought.
+                KeyCode::Char('b') if self.can_add => {
+                    if let Some(CatalogEntry::Item(item)) = self.selected() {
+                        if item.value.is_some() {
+                            return PaletteInput::Buy(item.clone());
+                        }
+                    }
+                }
+                KeyCode::Down | KeyCode::PageDown => self.scroll = self.scroll.saturating_add(1),
+                KeyCode::Up | KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(1),
+                _ => {}
+            }
+            return PaletteInput::Handled;
+        }
+
+        match event.code {
+            KeyCode::Esc => return PaletteInput::Closed,
+            // Characters are opened straight away, since there's
+            // nothing more to show about them here.
+            KeyCode::Enter => match self.selected() {
+                Some(CatalogEntry::Character(character)) => {
+                    return PaletteInput::Open(character.clone())
+                }
+                Some(_) => {
+                    self.detail = true;
+                    self.scroll = 0;
+                }
+                None => {}
+            },
+            KeyCode::Down => self.next(),
+            KeyCode::Char('n') if control => self.next(),
+            KeyCode::Up => self.previous(),
+            KeyCode::Char('p') if control => self.previous(),
+            KeyCode::Char(_) if control => {}
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.update_matches();
+                return PaletteInput::Query(self.query.clone());
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.update_matches();
+                return PaletteInput::Query(self.query.clone());
+            }
+            _ => {}
+        }
+        PaletteInput::Handled
+    }
+
+    fn next(&mut self) {
+        self.state.select(
+            self.state
+                .selected()
+                .map(|i| (i + 1).min(self.matches.len().saturating_sub(1))),
+        );
+    }
+
+    fn previous(&mut self) {
+        self.state
+            .select(self.state.selected().map(|i| i.saturating_sub(1)));
+    }
+
+    pub fn render<B: Backend>(&mut self, frame: &mut Frame<B>, theme: &Theme) {
+        let area = frame.size();
+        let width = (area.width * 3 / 5).max(40).min(area.width);
+        let height = (area.height * 3 / 5).max(10).min(area.height);
+        let area = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+        frame.render_widget(Clear, area);
+
+        if self.detail {
+            if let Some(entry) = self.selected() {
+                let hint = match (self.can_add, entry) {
+                    (true, CatalogEntry::Item(item)) if item.value.is_some() => {
+                        format!("Enter: add, b: buy for {}, Esc: back", item.value.unwrap())
+                    }
+                    (true, _) => "Enter: add to character, Esc: back".to_string(),
+                    (false, _) => "Esc: back".to_string(),
+                };
+                let lines = vec![
+                    Spans::from(Span::styled(entry.summary(), theme.key)),
+                    Spans::from(""),
+                    Spans::from(entry.description()),
+                ];
+                let detail = Paragraph::new(lines)
+                    .block(
+                        Block::default()
+                            .title(format!("{} ({})", entry.name(), hint))
+                            .borders(Borders::ALL),
+                    )
+                    .wrap(Wrap { trim: false })
+                    .scroll((self.scroll, 0));
+                frame.render_widget(detail, area);
+                return;
+            }
+        }
+
+        let block = Block::default()
+            .title("Search (Enter: open, Esc: close)")
+            .borders(Borders::ALL);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+            .split(inner);
+
+        let query = Paragraph::new(Spans::from(vec![
+            Span::styled("> ", theme.key),
+            Span::raw(format!("{}_", self.query)),
+        ]));
+        frame.render_widget(query, chunks[0]);
+
+        if self.matches.is_empty() {
+            frame.render_widget(
+                Paragraph::new(Span::styled("No matches", theme.muted)),
+                chunks[1],
+            );
+            return;
+        }
+        // Entries found by their description have the part that
+        // matched on a second line, with the matched words picked out.
+        let items = self
+            .matches
+            .iter()
+            .map(|found| {
+                let entry = &self.catalog[found.entry];
+                let mut lines = vec![Spans::from(vec![
+                    Span::raw(entry.name().to_string()),
+                    Span::styled(format!("  {}", entry.kind()), theme.muted),
+                ])];
+                if let Some(hit) = &found.hit {
+                    let mut snippet = vec![Span::raw("  ")];
+                    snippet.extend(hit.snippet_parts().into_iter().map(|(text, matched)| {
+                        let text = text.replace('\n', " ");
+                        match matched {
+                            true => Span::styled(text, theme.key),
+                            false => Span::styled(text, theme.muted),
+                        }
+                    }));
+                    lines.push(Spans::from(snippet));
+                }
+                ListItem::new(Text::from(lines))
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .highlight_style(theme.highlight)
+            .highlight_symbol(">");
+        frame.render_stateful_widget(list, chunks[1], &mut self.state);
+    }
+}
+
+// How well the query matches the text, or None if it doesn't. Every
+// character of the query has to be in the text, in order, ignoring
+// case and spaces. Characters at the start of words and runs of
+// them score highest, and characters skipped over count against it.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut position = 0;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..text.len()).find(|i| text[*i] == c)?;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 8;
+        } else if found == position && position > 0 {
+            score += 5;
+        }
+        score -= (found - position) as i32;
+        position = found + 1;
+    }
+    Some(score)
+}
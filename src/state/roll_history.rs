@@ -0,0 +1,264 @@
+use crate::{
+    data::{
+        query::{Filter, Order, Query},
+        rolls::{Roll, RollKind, RollStatistics},
+    },
+    state::{
+        keymap::{Action, Keymap, Screen},
+        paged::{PagedList, PAGE_SIZE},
+        theme::Theme,
+    },
+};
+use chrono::{Local, TimeZone};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Tabs},
+    Frame,
+};
+
+// Every roll a character has made, newest first, with statistics
+// over the rolls matching the current filter. A saved character's
+// rolls are loaded from the database a page at a time as they're
+// scrolled through; one who hasn't been saved yet only has the
+// rolls made since their sheet was opened.
+pub struct RollHistory {
+    // None for a character who hasn't been saved.
+    character: Option<i64>,
+    // Rolls made by a character who hasn't been saved, which
+    // aren't in the database, newest first.
+    unsaved: Vec<Roll>,
+    rolls: PagedList<Roll>,
+    statistics: RollStatistics,
+    // None shows every kind of roll.
+    filter: Option<RollKind>,
+    state: TableState,
+}
+
+impl RollHistory {
+    pub fn new(character: Option<i64>) -> RollHistory {
+        let mut state = TableState::default();
+        state.select(Some(0));
+        RollHistory {
+            character,
+            unsaved: Vec::new(),
+            rolls: PagedList::new(),
+            statistics: RollStatistics::default(),
+            filter: None,
+            state,
+        }
+    }
+
+    fn shows(&self, roll: &Roll) -> bool {
+        self.filter.is_none() || roll.kind == self.filter
+    }
+
+    pub fn push(&mut self, roll: Roll) {
+        if self.shows(&roll) {
+            self.statistics.add(&roll);
+            self.rolls.push_front(roll.clone());
+        }
+        if self.character.is_none() {
+            self.unsaved.insert(0, roll);
+        }
+    }
+
+    // Empties the history to be loaded again from the first page, ie.
+    // when it's opened or filtered, returning the query for that page.
+    pub fn reload(&mut self) -> Option<Query<Roll>> {
+        self.rolls.clear();
+        self.statistics = RollStatistics::default();
+        if self.character.is_none() {
+            let rolls = self
+                .unsaved
+                .iter()
+                .filter(|roll| self.shows(roll))
+                .cloned()
+                .collect::<Vec<_>>();
+            rolls.iter().for_each(|roll| self.statistics.add(roll));
+            let total = rolls.len();
+            self.rolls.append(rolls, total);
+        }
+        self.page(0)
+    }
+
+    // The query for the page of rolls starting at the offset,
+    // or None if the character's rolls aren't saved.
+    fn page(&self, offset: usize) -> Option<Query<Roll>> {
+        let mut query = Query::new().filter("character", Filter::Equals(self.character?.into()));
+        if let Some(kind) = self.filter {
+            query = query.filter("kind", Filter::Equals(kind.to_string().into()));
+        }
+        Some(
+            query
+                .order_by("timestamp", Order::Descending)
+                .order_by("id", Order::Descending)
+                .limit(PAGE_SIZE)
+                .offset(offset),
+        )
+    }
+
+    // Called with each page of rolls loaded, along with the
+    // statistics over every roll matching the filter.
+    pub fn loaded(&mut self, rolls: Vec<Roll>, statistics: RollStatistics) {
+        self.statistics = statistics;
+        self.rolls.append(rolls, statistics.rolls);
+    }
+
+    // Cycles the filter through All, then each kind of roll in turn.
+    pub fn next_filter(&mut self) -> Option<Query<Roll>> {
+        let kinds = RollKind::get_all_kinds();
+        self.filter = match self.filter {
+            None => kinds.first().copied(),
+            Some(kind) => kinds.iter().skip_while(|k| **k != kind).nth(1).copied(),
+        };
+        self.reload()
+    }
+
+    // Moves down the list, returning the query for the next
+    // page when it's time for it to be loaded.
+    pub fn next(&mut self) -> Option<Query<Roll>> {
+        let offset = self.rolls.next()?;
+        self.page(offset)
+    }
+
+    pub fn previous(&mut self) {
+        self.rolls.previous();
+    }
+
+    fn statistics(&self, theme: &Theme) -> Vec<Spans<'_>> {
+        let key_style = theme.key;
+        let statistics = &self.statistics;
+        let average = |average: Option<f64>| match average {
+            Some(average) => format!("{:.2}", average),
+            None => "-".to_string(),
+        };
+
+        vec![
+            Spans::from(vec![
+                Span::styled("Rolls: ", key_style),
+                Span::raw(format!("{}  ", statistics.rolls)),
+                Span::styled("Average total: ", key_style),
+                Span::raw(average(statistics.average_total())),
+            ]),
+            Spans::from(vec![
+                Span::styled("d20 rolls: ", key_style),
+                Span::raw(format!("{}  ", statistics.d20s)),
+                Span::styled("Average d20: ", key_style),
+                Span::raw(format!(
+                    "{} (expected 10.50)  ",
+                    average(statistics.average_d20())
+                )),
+                Span::styled("Natural 20s: ", key_style),
+                Span::raw(format!("{}  ", statistics.natural_20s)),
+                Span::styled("Natural 1s: ", key_style),
+                Span::raw(statistics.natural_1s.to_string()),
+            ]),
+        ]
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        area: Rect,
+        keymap: &Keymap,
+        theme: &Theme,
+    ) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(1),
+                    Constraint::Length(3),
+                    Constraint::Min(0),
+                ]
+                .as_ref(),
+            )
+            .split(area);
+
+        let key_style = theme.key;
+
+        let mut filters = vec![Spans::from("All")];
+        filters.extend(
+            RollKind::get_all_kinds()
+                .into_iter()
+                .map(|kind| Spans::from(kind.to_string())),
+        );
+        let selected_filter = match self.filter {
+            None => 0,
+            Some(kind) => {
+                1 + RollKind::get_all_kinds()
+                    .iter()
+                    .position(|k| *k == kind)
+                    .unwrap_or(0)
+            }
+        };
+        let filters = Tabs::new(filters)
+            .select(selected_filter)
+            .style(theme.muted)
+            .highlight_style(theme.highlight)
+            .divider("|");
+
+        let statistics =
+            Paragraph::new(self.statistics(theme)).block(Block::default().borders(Borders::BOTTOM));
+
+        let rows = self
+            .rolls
+            .items()
+            .iter()
+            .map(|roll| {
+                let style = match roll.natural_d20 {
+                    Some(20) => theme.success,
+                    Some(1) => theme.failure,
+                    _ => Style::default(),
+                };
+                Row::new(vec![
+                    Cell::from(
+                        Local
+                            .timestamp_opt(roll.timestamp, 0)
+                            .single()
+                            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                            .unwrap_or_default(),
+                    ),
+                    Cell::from(roll.kind.map(|kind| kind.to_string()).unwrap_or_default()),
+                    Cell::from(roll.label.clone()),
+                    Cell::from(roll.expression.clone()),
+                    Cell::from(roll.breakdown.clone()),
+                    Cell::from(roll.total.to_string()).style(style),
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec!["Time", "Kind", "Roll", "Dice", "Breakdown", "Total"])
+                    .style(key_style),
+            )
+            .block(Block::default().title(format!(
+                "Roll History ({})",
+                keymap.hints(
+                    Screen::Sheet,
+                    &[
+                        (&[Action::Filter], "filter"),
+                        (&[Action::RollHistory], "close")
+                    ]
+                )
+            )))
+            .widths(&[
+                Constraint::Length(16),
+                Constraint::Length(6),
+                Constraint::Percentage(30),
+                Constraint::Percentage(15),
+                Constraint::Percentage(25),
+                Constraint::Length(5),
+            ])
+            .highlight_style(theme.highlight);
+
+        frame.render_widget(filters, chunks[0]);
+        frame.render_widget(statistics, chunks[1]);
+        self.state.select(Some(self.rolls.selected()));
+        frame.render_stateful_widget(table, chunks[2], &mut self.state);
+    }
+}
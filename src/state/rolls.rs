@@ -0,0 +1,366 @@
+use crate::{
+    data::{
+        rolls::{Roll, RollKind},
+        rules::{
+            ability_check_bonus, attack_bonus, damage_expression, derived_stats,
+            saving_throw_bonus, saving_throw_proficient, skill_bonus, skill_proficient,
+            weapon_proficient, RollMode,
+        },
+        skills::Skill,
+        stats::Stats,
+    },
+    dice::{Dice, DiceExpression, Keep, RollResult, Roller, Term},
+    state::{
+        keymap::{Action, Keymap, Screen},
+        mouse::{ListArea, Mouse},
+        theme::Theme,
+    },
+    Character,
+};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+// Anything on the sheet that can be rolled. Attacks and damage
+// hold the index of the weapon in the character's inventory, and
+// damage whether a versatile weapon is used two-handed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RollTarget {
+    Ability(Stats),
+    Save(Stats),
+    Skill(Skill),
+    Attack(usize),
+    Damage(usize, bool),
+}
+
+impl RollTarget {
+    pub fn get_all_targets(character: &Character) -> Vec<RollTarget> {
+        let stats = [
+            Stats::STR,
+            Stats::DEX,
+            Stats::CON,
+            Stats::INT,
+            Stats::WIS,
+            Stats::CHA,
+        ];
+        let mut targets = Vec::new();
+        for (i, entry) in character.inventory.iter().enumerate() {
+            if let Some(weapon) = &entry.item.weapon {
+                targets.push(RollTarget::Attack(i));
+                targets.push(RollTarget::Damage(i, false));
+                if weapon.versatile().is_some() {
+                    targets.push(RollTarget::Damage(i, true));
+                }
+            }
+        }
+        targets.extend(stats.iter().map(|stat| RollTarget::Ability(*stat)));
+        targets.extend(stats.iter().map(|stat| RollTarget::Save(*stat)));
+        targets.extend(Skill::get_all_skills().into_iter().map(RollTarget::Skill));
+        targets
+    }
+
+    pub fn label(&self, character: &Character) -> String {
+        let weapon = |i: &usize| {
+            character
+                .inventory
+                .get(*i)
+                .map(|entry| &entry.item)
+                .map(|item| item.name.clone())
+                .unwrap_or_default()
+        };
+        match self {
+            RollTarget::Ability(stat) => format!("{} check", stat),
+            RollTarget::Save(stat) => format!("{} save", stat),
+            RollTarget::Skill(skill) => format!("{} ({})", skill, skill.ability()),
+            RollTarget::Attack(i) => format!("{} attack", weapon(i)),
+            RollTarget::Damage(i, false) => format!("{} damage", weapon(i)),
+            RollTarget::Damage(i, true) => format!("{} damage (two-handed)", weapon(i)),
+        }
+    }
+
+    pub fn kind(&self) -> RollKind {
+        match self {
+            RollTarget::Ability(_) | RollTarget::Skill(_) => RollKind::Check,
+            RollTarget::Save(_) => RollKind::Save,
+            RollTarget::Attack(_) => RollKind::Attack,
+            RollTarget::Damage(..) => RollKind::Damage,
+        }
+    }
+
+    // The flat bonus added to the d20; damage isn't rolled
+    // with a d20, so its bonus is part of its expression instead.
+    pub fn bonus(&self, character: &Character) -> i8 {
+        match self {
+            RollTarget::Ability(stat) => ability_check_bonus(character, stat),
+            RollTarget::Save(stat) => saving_throw_bonus(character, stat),
+            RollTarget::Skill(skill) => skill_bonus(character, skill),
+            RollTarget::Attack(i) => character
+                .inventory
+                .get(*i)
+                .and_then(|entry| attack_bonus(character, entry))
+                .unwrap_or(0),
+            RollTarget::Damage(..) => 0,
+        }
+    }
+
+    pub fn proficient(&self, character: &Character) -> bool {
+        match self {
+            RollTarget::Ability(_) | RollTarget::Damage(..) => false,
+            RollTarget::Save(stat) => saving_throw_proficient(character, stat),
+            RollTarget::Skill(skill) => skill_proficient(character, skill),
+            RollTarget::Attack(i) => character
+                .inventory
+                .get(*i)
+                .map(|entry| &entry.item)
+                .map(|item| weapon_proficient(character, item))
+                .unwrap_or(false),
+        }
+    }
+
+    // The dice rolled for the target, given the mode
+    // it's being rolled with.
+    pub fn expression(&self, character: &Character, mode: RollMode) -> DiceExpression {
+        match self {
+            RollTarget::Damage(i, two_handed) => character
+                .inventory
+                .get(*i)
+                .and_then(|entry| damage_expression(character, entry, *two_handed))
+                .unwrap_or_else(|| d20_expression(0, RollMode::Normal)),
+            _ => d20_expression(self.bonus(character), mode),
+        }
+    }
+
+    // The mode the rules engine says this roll is made with,
+    // before the player's own advantage/disadvantage toggle.
+    fn mode(&self, character: &Character) -> RollMode {
+        let derived = derived_stats(character);
+        match self {
+            RollTarget::Ability(stat) => derived.ability_check(stat),
+            RollTarget::Save(stat) => derived.saving_throw(stat),
+            RollTarget::Skill(skill) => derived.ability_check(&skill.ability()),
+            RollTarget::Attack(_) => derived.attack_rolls,
+            RollTarget::Damage(..) => RollMode::Normal,
+        }
+    }
+
+    fn auto_fails(&self, character: &Character) -> bool {
+        match self {
+            RollTarget::Save(stat) => derived_stats(character).auto_fail_saves.contains(stat),
+            _ => false,
+        }
+    }
+}
+
+// A d20 plus a flat bonus, rolled with the given mode.
+pub fn d20_expression(bonus: i8, mode: RollMode) -> DiceExpression {
+    let keep = match mode {
+        RollMode::Normal => None,
+        RollMode::Advantage => Some(Keep::Highest(1)),
+        RollMode::Disadvantage => Some(Keep::Lowest(1)),
+    };
+    let mut terms = vec![Term::Dice(Dice {
+        count: if keep.is_some() { 2 } else { 1 },
+        sides: 20,
+        keep,
+        negative: false,
+    })];
+    if bonus != 0 {
+        terms.push(Term::Modifier(bonus as i32));
+    }
+    DiceExpression {
+        terms,
+        damage_type: None,
+    }
+}
+
+pub struct LoggedRoll {
+    pub label: String,
+    // None when the roll was an automatic failure,
+    // ie. a STR save while paralyzed.
+    pub result: Option<RollResult>,
+}
+
+// The list of rollable targets shown in the Stats tab and the log
+// of rolls made from it. The player's advantage/disadvantage toggle
+// is combined with any the rules engine applies from conditions.
+pub struct RollPanel {
+    state: ListState,
+    targets: Vec<RollTarget>,
+    pub mode: RollMode,
+    pub log: Vec<LoggedRoll>,
+    roller: Roller,
+    // Where the list of targets was drawn, for clicks.
+    list_area: ListArea,
+}
+
+impl RollPanel {
+    pub fn new(character: &Character) -> RollPanel {
+        let mut state = ListState::default();
+        state.select(Some(0));
+        RollPanel {
+            state,
+            targets: RollTarget::get_all_targets(character),
+            mode: RollMode::Normal,
+            log: Vec::new(),
+            roller: Roller::new(),
+            list_area: ListArea::default(),
+        }
+    }
+
+    // The target under the mouse, if any.
+    pub fn target_at(&self, mouse: Mouse) -> Option<usize> {
+        self.list_area
+            .item_at(mouse)
+            .filter(|index| *index < self.targets.len())
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.state.select(Some(index));
+    }
+
+    pub fn next(&mut self) {
+        self.state.select(
+            self.state
+                .selected()
+                .map(|i| (i + 1).min(self.targets.len() - 1)),
+        );
+    }
+
+    pub fn previous(&mut self) {
+        self.state
+            .select(self.state.selected().map(|i| i.saturating_sub(1)));
+    }
+
+    pub fn toggle_mode(&mut self, mode: RollMode) {
+        self.mode = if self.mode == mode {
+            RollMode::Normal
+        } else {
+            mode
+        };
+    }
+
+    // Rolls the selected target for the character, adding the result
+    // to the log. The roll is returned so it can be kept in the
+    // character's roll history; automatic failures aren't.
+    pub fn roll(&mut self, character: &Character) -> Option<Roll> {
+        let target = self.targets[self.state.selected()?];
+
+        // Advantage and disadvantage don't apply to damage.
+        let mode = match target {
+            RollTarget::Damage(..) => RollMode::Normal,
+            _ => self.mode.with(target.mode(character)),
+        };
+        let label = match mode {
+            RollMode::Normal => target.label(character),
+            mode => format!("{} ({})", target.label(character), mode),
+        };
+        let result = match target.auto_fails(character) {
+            true => None,
+            false => Some(self.roller.roll(&target.expression(character, mode))),
+        };
+        let roll = result
+            .as_ref()
+            .map(|result| Roll::from_result(character.id, target.kind(), label.clone(), result));
+        self.log.push(LoggedRoll { label, result });
+        roll
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        area: Rect,
+        character: &Character,
+        keymap: &Keymap,
+        theme: &Theme,
+    ) {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .split(area);
+
+        let key_style = theme.key;
+
+        let targets = self
+            .targets
+            .iter()
+            .map(|target| {
+                let marker = if target.proficient(character) {
+                    "*"
+                } else {
+                    " "
+                };
+                match target {
+                    RollTarget::Damage(..) => ListItem::new(format!(
+                        "{} {}: {}",
+                        marker,
+                        target.label(character),
+                        target.expression(character, RollMode::Normal)
+                    )),
+                    _ => ListItem::new(format!(
+                        "{} {:+3} {}",
+                        marker,
+                        target.bonus(character),
+                        target.label(character)
+                    )),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let block = Block::default()
+            .title(format!(
+                "Rolls ({})",
+                keymap.hints(
+                    Screen::Sheet,
+                    &[
+                        (&[Action::Roll], "roll"),
+                        (&[Action::Advantage], "adv"),
+                        (&[Action::Disadvantage], "dis"),
+                    ]
+                )
+            ))
+            .borders(Borders::RIGHT);
+        self.list_area
+            .update(block.inner(chunks[0]), self.state.selected(), targets.len());
+        let targets = List::new(targets)
+            .block(block)
+            .highlight_style(theme.highlight)
+            .highlight_symbol(">");
+
+        // The newest rolls are at the top of the log.
+        let mut log = vec![Spans::from(vec![
+            Span::styled("Mode: ", key_style),
+            Span::raw(self.mode.to_string()),
+        ])];
+        for logged in self.log.iter().rev() {
+            let line = match &logged.result {
+                Some(result) => {
+                    let style = match result.natural_d20() {
+                        Some(20) => theme.success,
+                        Some(1) => theme.failure,
+                        _ => Style::default(),
+                    };
+                    Spans::from(vec![
+                        Span::styled(format!("{}: ", logged.label), key_style),
+                        Span::styled(format!("{} = {}", result.breakdown(), result.total), style),
+                    ])
+                }
+                None => Spans::from(vec![
+                    Span::styled(format!("{}: ", logged.label), key_style),
+                    Span::styled("automatic failure", theme.failure),
+                ]),
+            };
+            log.push(line);
+        }
+
+        frame.render_stateful_widget(targets, chunks[0], &mut self.state);
+        frame.render_widget(
+            Paragraph::new(log).block(Block::default().title("Roll Log")),
+            chunks[1],
+        );
+    }
+}
@@ -1,96 +1,188 @@
 use crate::{
-    state::app::{HandleKeyboardInput, HandleKeyboardInput::*, State, States::*},
-    data::character::SavedCharacter,
+    data::{
+        catalog::{CatalogEntry, SearchHit},
+        character::SavedCharacter,
+    },
+    state::{
+        app::{HandleKeyboardInput, HandleKeyboardInput::*, State, States::*},
+        keymap::{Action, Keymap, Screen},
+        mouse::{ListArea, Mouse},
+        palette::{Palette, PaletteInput},
+        theme::Theme,
+    },
 };
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent};
-use std::io::Stdout;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
+use std::{cmp::Reverse, fmt};
 use tui::{
-    backend::CrosstermBackend,
-    style::{Color, Modifier, Style},
-    widgets::{Block, List, ListItem, ListState},
-    Terminal,
+    backend::Backend,
+    layout::{Constraint, Direction, Layout},
+    text::{Span, Spans},
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+    Frame,
 };
 
 pub struct SelectScreen {
     saved_characters: Vec<SavedCharacter>,
+    // Indices into saved_characters of the characters listed,
+    // in the order they're listed in.
+    shown: Vec<usize>,
     state: ListState,
+    order: CharacterOrder,
+    // Only characters whose name, race or class contain
+    // this are listed.
+    filter: String,
+    prompt: Option<Prompt>,
+    // Why the last change asked for couldn't be made, shown
+    // in the status line until the next key.
+    error: Option<String>,
+    // Some while the search palette is open over the list.
+    palette: Option<Palette>,
+    keymap: Keymap,
+    theme: Theme,
+    show_help: bool,
+    // Where the list was drawn, for clicks.
+    list_area: ListArea,
+}
+
+// The orders the characters can be listed in, changed in turn
+// with the sort key. Ties are broken by name.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CharacterOrder {
+    Name,
+    Class,
+    // Highest level first.
+    Level,
+    // Most recently written first.
+    Modified,
+}
+
+impl CharacterOrder {
+    fn next(self) -> CharacterOrder {
+        match self {
+            CharacterOrder::Name => CharacterOrder::Class,
+            CharacterOrder::Class => CharacterOrder::Level,
+            CharacterOrder::Level => CharacterOrder::Modified,
+            CharacterOrder::Modified => CharacterOrder::Name,
+        }
+    }
+}
+
+impl fmt::Display for CharacterOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CharacterOrder::Name => write!(f, "name"),
+            CharacterOrder::Class => write!(f, "class"),
+            CharacterOrder::Level => write!(f, "level"),
+            CharacterOrder::Modified => write!(f, "last modified"),
+        }
+    }
+}
+
+// Shown in the line under the list while the user is asked for
+// something. Every key goes to the prompt until it's closed.
+enum Prompt {
+    // Deleting the character waits for 'y'; any other key keeps them.
+    Delete(SavedCharacter),
+    // The character's new name, as it's typed.
+    Rename(SavedCharacter, String),
+    // The filter is changed as it's typed.
+    Filter,
 }
 
 impl SelectScreen {
     // When instantiating a new SelectScreen, a vector of all characters
     // currently saved in the sqlite db is passed.
-    pub fn new(saved_characters: Vec<SavedCharacter>) -> SelectScreen {
+    pub fn new(
+        saved_characters: Vec<SavedCharacter>,
+        keymap: Keymap,
+        theme: Theme,
+    ) -> SelectScreen {
         let mut state = ListState::default();
         state.select(Some(0));
-        SelectScreen {
+        let mut screen = SelectScreen {
             saved_characters,
+            shown: Vec::new(),
             state,
-        }
+            order: CharacterOrder::Name,
+            filter: String::new(),
+            prompt: None,
+            error: None,
+            palette: None,
+            keymap,
+            theme,
+            show_help: false,
+            list_area: ListArea::default(),
+        };
+        screen.update_shown(None);
+        screen.state.select(Some(0));
+        screen
+    }
+
+    // The character selected in the list, if it isn't
+    // the New Character Sheet at the end of it.
+    fn selected_character(&self) -> Option<&SavedCharacter> {
+        let index = self.shown.get(self.state.selected()?)?;
+        self.saved_characters.get(*index)
     }
-}
 
-impl State for SelectScreen {
-    fn display_screen(&mut self, stdout: &mut Stdout) -> Result<()> {
-        // Define the backend for our tui terminal and instantiate it
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
-        terminal.clear()?;
+    fn selected_id(&self) -> Option<i64> {
+        self.selected_character().and_then(|c| c.id)
+    }
 
-        // This is a vector of tui-rs ListItems which contain the name,
-        // race and class of each character saved in the database.
-        // Each of these corresponds with a SavedCharacter struct saved
-        // in the saved_characters vector, which have the corresponding
-        // id to load the full character struct from the sqlite db.
-        let mut selections = self
-            .saved_characters
-            .iter()
-            .map(|c| {
-                ListItem::new(format!(
-                    "{} {} {}",
-                    c.name.as_str(),
-                    c.race.as_str(),
-                    c.class.as_str()
-                ))
+    // Sorts and filters the characters again, keeping the one with
+    // the given id selected if they're still listed, or else the
+    // first. Without an id, the New Character Sheet stays selected.
+    fn update_shown(&mut self, selected_id: Option<i64>) {
+        let characters = &self.saved_characters;
+        let filter = self.filter.to_lowercase();
+        let mut shown = (0..characters.len())
+            .filter(|i| {
+                let c = &characters[*i];
+                [&c.name, &c.race, &c.class]
+                    .iter()
+                    .any(|field| field.to_lowercase().contains(&filter))
             })
             .collect::<Vec<_>>();
+        let name = |i: &usize| characters[*i].name.to_lowercase();
+        match self.order {
+            CharacterOrder::Name => shown.sort_by_key(name),
+            CharacterOrder::Class => {
+                shown.sort_by_key(|i| (characters[*i].class.to_lowercase(), name(i)))
+            }
+            CharacterOrder::Level => {
+                shown.sort_by_key(|i| (Reverse(characters[*i].level), name(i)))
+            }
+            CharacterOrder::Modified => {
+                shown.sort_by_key(|i| (Reverse(characters[*i].modified), name(i)))
+            }
+        }
 
-        // Here we add an additional ListItem to select a New or Blank
-        // character sheet from the main selection screen
-        selections.push(ListItem::new("New Character Sheet"));
+        let index = match selected_id {
+            Some(id) => shown
+                .iter()
+                .position(|i| characters[*i].id == Some(id))
+                .unwrap_or(0),
+            None => shown.len(),
+        };
+        self.shown = shown;
+        self.state.select(Some(index));
+    }
 
-        // Call the .draw() method on the terminal instance to format
-        // and display the vector of ListItems to the terminal.
-        terminal.draw(|f| {
-            let size = f.size();
-            let all_selections = List::new(selections)
-                .block(Block::default().title("Character Sheets"))
-                .style(Style::default().fg(Color::White))
-                .highlight_style(
-                    Style::default()
-                        .fg(Color::Green)
-                        .add_modifier(Modifier::BOLD),
-                )
-                .highlight_symbol(">");
-
-            // Redering occurs here
-            f.render_stateful_widget(all_selections, size, &mut self.state)
-        })?;
-        Ok(())
-    }
-
-    fn handle_keyboard_event(
-        &mut self,
-        _stdout: &Stdout,
-        event: KeyEvent,
-    ) -> Result<HandleKeyboardInput> {
-        let all_characters_length = self.saved_characters.len() + 1;
-        match event.code {
-            // On matching the Esc key, return false to the caller.
+    // What each action does, whether it came from a key or the mouse.
+    fn handle_action(&mut self, action: Option<Action>) -> Result<HandleKeyboardInput> {
+        let all_characters_length = self.shown.len() + 1;
+        match action {
+            // On matching Quit (Esc by default), return Exit to the caller.
             // This will end the main loop and the application.
-            KeyCode::Esc => Ok(Exit),
+            Some(Action::Quit) => Ok(Exit),
+            Some(Action::Help) => {
+                self.show_help = true;
+                Ok(Input)
+            }
+            Some(Action::Search) => Ok(Search),
 
-            // Vim key-binds for up/down navigation as well as arrow-keys. When 'down' is detected,
+            // Up/down navigation, bound to vim keys and arrow-keys by default. When 'down' is detected,
             // we call the .select() method on the list state, and pass
             // the results from the .selected() method on itself to obtian the
             // next ListItem in the ListState. When 'up' is detected, the
@@ -98,7 +190,7 @@ impl State for SelectScreen {
             //
             // The argument for .selected() is clamped to keep the caller in the
             // bounds of the ListItem vector.
-            KeyCode::Char('j') | KeyCode::Down if all_characters_length > 1 => {
+            Some(Action::Down) if all_characters_length > 1 => {
                 self.state.select(
                     self.state
                         .selected()
@@ -106,35 +198,274 @@ impl State for SelectScreen {
                 );
                 Ok(Input)
             }
-            KeyCode::Char('k') | KeyCode::Up if all_characters_length > 1 => {
-                self.state.select(self.state.selected().map(|x| {
-                    x.checked_sub(1)
-                        .unwrap_or(0)
-                        .clamp(0, all_characters_length)
-                }));
+            Some(Action::Up) if all_characters_length > 1 => {
+                self.state.select(
+                    self.state
+                        .selected()
+                        .map(|x| x.saturating_sub(1).clamp(0, all_characters_length)),
+                );
                 Ok(Input)
             }
 
-            // When enter is detected, we check the saved_characters
+            // When Select (enter by default) is detected, we check the saved_characters
             // index that corresponds with the ListState index; if the last
-            // index is currently 'selected' then we return a SavedCharacter::new(),
-            // or a blank character; else, we return the SavedCharacter at the selected
-            // index. The call will then instantiate a full Character struct.
+            // index is currently 'selected' then we open the creation wizard
+            // to make a new character; else, we return the SavedCharacter at the
+            // selected index. The call will then instantiate a full Character struct.
             //
             // This returns a ChangeState to the caller 'App' which handles
             // state changes internally.
-            KeyCode::Enter => {
-                if self.state.selected() == Some(all_characters_length - 1) {
-                    Ok(ChangeState(CharacterSheet(SavedCharacter::new())))
-                } else {
-                    Ok(ChangeState(CharacterSheet(
-                        self.saved_characters
-                            [self.state.selected().unwrap_or(all_characters_length - 1)]
-                        .clone(),
-                    )))
+            Some(Action::Select) => match self.selected_character() {
+                Some(character) => Ok(ChangeState(CharacterSheet(character.clone()))),
+                None => Ok(ChangeState(CreationWizard)),
+            },
+
+            // Deleting, duplicating and renaming are done by App, which
+            // hands back the changed list through characters_changed.
+            Some(Action::Delete) => {
+                if let Some(character) = self.selected_character() {
+                    self.prompt = Some(Prompt::Delete(character.clone()));
                 }
+                Ok(Input)
+            }
+            Some(Action::Duplicate) => match self.selected_character().and_then(|c| c.id) {
+                Some(id) => Ok(DuplicateCharacter(id)),
+                None => Ok(Void),
+            },
+            Some(Action::Rename) => {
+                if let Some(character) = self.selected_character() {
+                    self.prompt = Some(Prompt::Rename(character.clone(), character.name.clone()));
+                }
+                Ok(Input)
+            }
+            Some(Action::Sort) => {
+                self.order = self.order.next();
+                self.update_shown(self.selected_id());
+                Ok(Input)
+            }
+            Some(Action::Filter) => {
+                self.prompt = Some(Prompt::Filter);
+                Ok(Input)
             }
             _ => Ok(Void),
         }
     }
+
+    // Text is typed into the prompt as it is on the sheet; Enter
+    // finishes and Esc cancels.
+    fn handle_prompt(&mut self, prompt: Prompt, event: KeyEvent) -> HandleKeyboardInput {
+        match (prompt, event.code) {
+            (Prompt::Delete(character), KeyCode::Char('y')) => match character.id {
+                Some(id) => DeleteCharacter(id),
+                None => Input,
+            },
+            (Prompt::Delete(_), _) => Input,
+
+            (Prompt::Rename(character, name), KeyCode::Enter) => {
+                match (character.id, name.trim()) {
+                    (Some(_), "") => {
+                        self.prompt = Some(Prompt::Rename(character, name));
+                        Input
+                    }
+                    (Some(id), name) => RenameCharacter(id, name.to_string()),
+                    (None, _) => Input,
+                }
+            }
+            (Prompt::Rename(..), KeyCode::Esc) => Input,
+            (Prompt::Rename(character, mut name), code) => {
+                match code {
+                    KeyCode::Char(c) => name.push(c),
+                    KeyCode::Backspace => {
+                        name.pop();
+                    }
+                    _ => {}
+                }
+                self.prompt = Some(Prompt::Rename(character, name));
+                Input
+            }
+
+            // Esc clears the filter, where Enter keeps it.
+            (Prompt::Filter, KeyCode::Enter) => Input,
+            (Prompt::Filter, KeyCode::Esc) => {
+                self.filter.clear();
+                self.update_shown(self.selected_id());
+                Input
+            }
+            (Prompt::Filter, code) => {
+                match code {
+                    KeyCode::Char(c) => self.filter.push(c),
+                    KeyCode::Backspace => {
+                        self.filter.pop();
+                    }
+                    _ => {}
+                }
+                self.prompt = Some(Prompt::Filter);
+                self.update_shown(self.selected_id());
+                Input
+            }
+        }
+    }
+
+    // The line under the list; the prompt when there is one, or an
+    // error, otherwise how the list is sorted and filtered.
+    fn status_line(&self) -> Spans<'_> {
+        if let (None, Some(error)) = (&self.prompt, &self.error) {
+            return Spans::from(Span::styled(error.as_str(), self.theme.failure));
+        }
+        match &self.prompt {
+            Some(Prompt::Delete(character)) => Spans::from(vec![
+                Span::styled(format!("Delete {}?", character.name), self.theme.failure),
+                Span::raw(" y: yes, any other key: no"),
+            ]),
+            Some(Prompt::Rename(_, name)) => Spans::from(vec![
+                Span::styled("New name: ", self.theme.key),
+                Span::raw(format!("{}_", name)),
+            ]),
+            Some(Prompt::Filter) => Spans::from(vec![
+                Span::styled("Filter: ", self.theme.key),
+                Span::raw(format!("{}_", self.filter)),
+            ]),
+            None if self.filter.is_empty() => Spans::from(Span::styled(
+                format!("Sorted by {}", self.order),
+                self.theme.muted,
+            )),
+            None => Spans::from(Span::styled(
+                format!("Sorted by {}, filtered by '{}'", self.order, self.filter),
+                self.theme.muted,
+            )),
+        }
+    }
+}
+
+impl<B: Backend> State<B> for SelectScreen {
+    fn display_screen(&mut self, f: &mut Frame<B>) {
+        // This is a vector of tui-rs ListItems which contain the name,
+        // level, race and class of each character saved in the database.
+        // Each of these corresponds with a SavedCharacter struct saved
+        // in the saved_characters vector, which have the corresponding
+        // id to load the full character struct from the sqlite db.
+        let mut selections = self
+            .shown
+            .iter()
+            .map(|i| &self.saved_characters[*i])
+            .map(|c| {
+                ListItem::new(format!(
+                    "{} - level {} {} {}",
+                    c.name.as_str(),
+                    c.level,
+                    c.race.as_str(),
+                    c.class.as_str()
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        // Here we add an additional ListItem to select a New or Blank
+        // character sheet from the main selection screen
+        selections.push(ListItem::new("New Character Sheet"));
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+            .split(f.size());
+        let block = Block::default().title("Character Sheets");
+        self.list_area.update(
+            block.inner(chunks[0]),
+            self.state.selected(),
+            selections.len(),
+        );
+        let all_selections = List::new(selections)
+            .block(block)
+            .style(self.theme.text)
+            .highlight_style(self.theme.highlight)
+            .highlight_symbol(">");
+
+        // Redering occurs here
+        f.render_stateful_widget(all_selections, chunks[0], &mut self.state);
+        f.render_widget(Paragraph::new(self.status_line()), chunks[1]);
+
+        if let Some(palette) = self.palette.as_mut() {
+            palette.render(f, &self.theme);
+        }
+        if self.show_help {
+            self.keymap.render_help(f, Screen::Select, &self.theme);
+        }
+    }
+
+    fn handle_keyboard_event(&mut self, event: KeyEvent) -> Result<HandleKeyboardInput> {
+        self.error = None;
+        // Any key closes the help.
+        if self.show_help {
+            self.show_help = false;
+            return Ok(Input);
+        }
+        if let Some(prompt) = self.prompt.take() {
+            return Ok(self.handle_prompt(prompt, event));
+        }
+        if let Some(palette) = self.palette.as_mut() {
+            return Ok(match palette.handle_key(event) {
+                PaletteInput::Closed => {
+                    self.palette = None;
+                    Input
+                }
+                PaletteInput::Open(character) => ChangeState(CharacterSheet(character)),
+                PaletteInput::Query(query) => SearchCatalog(query),
+                PaletteInput::Handled | PaletteInput::Add(_) | PaletteInput::Buy(_) => Input,
+            });
+        }
+
+        // Keys are matched by the action they're bound to in the
+        // keymap, rather than by the key itself.
+        let action = self.keymap.action(Screen::Select, event);
+        self.handle_action(action)
+    }
+
+    // Clicking a character selects it, and clicking it again opens
+    // it; the wheel moves the selection like the up and down keys.
+    fn handle_mouse_event(&mut self, event: MouseEvent) -> Result<HandleKeyboardInput> {
+        let mouse = match Mouse::from_event(event) {
+            Some(mouse) => mouse,
+            None => return Ok(Void),
+        };
+        if self.show_help {
+            self.show_help = false;
+            return Ok(Input);
+        }
+        if self.prompt.is_some() || self.palette.is_some() {
+            return Ok(Void);
+        }
+        match mouse {
+            Mouse::ScrollUp(..) => self.handle_action(Some(Action::Up)),
+            Mouse::ScrollDown(..) => self.handle_action(Some(Action::Down)),
+            Mouse::Click(..) => match self.list_area.item_at(mouse) {
+                Some(index) if self.state.selected() == Some(index) => {
+                    self.handle_action(Some(Action::Select))
+                }
+                Some(index) if index <= self.shown.len() => {
+                    self.state.select(Some(index));
+                    Ok(Input)
+                }
+                _ => Ok(Void),
+            },
+        }
+    }
+
+    fn open_search(&mut self, catalog: Vec<CatalogEntry>) {
+        self.palette = Some(Palette::new(catalog, false));
+    }
+
+    fn search_results(&mut self, catalog: Vec<CatalogEntry>, hits: Vec<(CatalogEntry, SearchHit)>) {
+        if let Some(palette) = self.palette.as_mut() {
+            palette.found(catalog, hits);
+        }
+    }
+
+    fn characters_changed(&mut self, characters: &[SavedCharacter]) {
+        let selected_id = self.selected_id();
+        self.saved_characters = characters.to_vec();
+        self.update_shown(selected_id);
+    }
+
+    fn show_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
 }
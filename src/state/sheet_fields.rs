@@ -0,0 +1,286 @@
+use crate::{
+    data::alignments::Alignment,
+    state::{keymap::Action, theme::Theme},
+    Character,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use enum_iterator::IntoEnumIterator;
+use std::fmt;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState},
+    Frame,
+};
+
+// The fields of the character sheet that can be edited in place,
+// in the order focus moves through them.
+#[derive(Clone, Copy, PartialEq, IntoEnumIterator)]
+pub enum SheetField {
+    Name,
+    Alignment,
+    Gender,
+    Experience,
+    HitPoints,
+    MaxHitPoints,
+    TempHitPoints,
+}
+
+impl SheetField {
+    pub fn get_all_fields() -> Vec<SheetField> {
+        SheetField::into_enum_iter().collect()
+    }
+
+    pub fn value(&self, character: &Character) -> String {
+        match self {
+            SheetField::Name => character.name.clone(),
+            SheetField::Alignment => character.alignment.to_string(),
+            SheetField::Gender => character.gender.clone(),
+            SheetField::Experience => character.xp.to_string(),
+            SheetField::HitPoints => character.hit_points.to_string(),
+            SheetField::MaxHitPoints => character.max_hit_points.to_string(),
+            SheetField::TempHitPoints => character.temp_hit_points.to_string(),
+        }
+    }
+
+    // Text fields are typed into, numbers are stepped
+    // up and down and alignments are picked from a list.
+    fn editor(&self, character: &Character) -> FieldEditor {
+        match self {
+            SheetField::Name | SheetField::Gender => FieldEditor::Text(self.value(character)),
+            SheetField::Alignment => {
+                let mut state = ListState::default();
+                state.select(
+                    Alignment::get_all_alignments()
+                        .iter()
+                        .position(|alignment| *alignment == character.alignment),
+                );
+                FieldEditor::Choice(state)
+            }
+            _ => FieldEditor::Number(self.value(character).parse().unwrap_or(0)),
+        }
+    }
+
+    fn maximum(&self) -> u64 {
+        match self {
+            SheetField::HitPoints | SheetField::MaxHitPoints | SheetField::TempHitPoints => {
+                u16::MAX as u64
+            }
+            _ => u32::MAX as u64,
+        }
+    }
+
+    // Writes the edited value to the character, returning
+    // whether it changed anything.
+    fn apply(&self, editor: &FieldEditor, character: &mut Character) -> bool {
+        let before = self.value(character);
+        match (self, editor) {
+            (SheetField::Name, FieldEditor::Text(text)) => character.name = text.trim().to_string(),
+            (SheetField::Gender, FieldEditor::Text(text)) => {
+                character.gender = text.trim().to_string()
+            }
+            (SheetField::Alignment, FieldEditor::Choice(state)) => {
+                if let Some(i) = state.selected() {
+                    character.alignment = Alignment::get_all_alignments()[i];
+                }
+            }
+            (SheetField::Experience, FieldEditor::Number(number)) => character.xp = *number,
+            (SheetField::HitPoints, FieldEditor::Number(number)) => {
+                character.hit_points = *number as u16
+            }
+            (SheetField::MaxHitPoints, FieldEditor::Number(number)) => {
+                character.max_hit_points = *number as u16
+            }
+            (SheetField::TempHitPoints, FieldEditor::Number(number)) => {
+                character.temp_hit_points = *number as u16
+            }
+            _ => {}
+        }
+        before != self.value(character)
+    }
+}
+
+impl fmt::Display for SheetField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SheetField::Name => write!(f, "Name"),
+            SheetField::Alignment => write!(f, "Alignment"),
+            SheetField::Gender => write!(f, "Gender"),
+            SheetField::Experience => write!(f, "Experience"),
+            SheetField::HitPoints => write!(f, "HP"),
+            SheetField::MaxHitPoints => write!(f, "Max HP"),
+            SheetField::TempHitPoints => write!(f, "Temp HP"),
+        }
+    }
+}
+
+enum FieldEditor {
+    Text(String),
+    Number(u64),
+    Choice(ListState),
+}
+
+// What a key pressed while fields are focused did, so the
+// sheet knows whether to handle the key itself.
+pub enum FieldInput {
+    Handled,
+    Changed,
+    Unhandled,
+}
+
+// Focus and edit state for the sheet's fields. While focused,
+// j/k move between the fields and Enter starts editing the one
+// focused; Enter again commits the edit and Esc abandons it.
+//
+// Changes are made to the sheet's copy of the character, which
+// is marked dirty until it's written back to the database.
+#[derive(Default)]
+pub struct SheetFields {
+    focus: Option<usize>,
+    editor: Option<FieldEditor>,
+    pub dirty: bool,
+}
+
+impl SheetFields {
+    pub fn new() -> SheetFields {
+        SheetFields::default()
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focus.is_some()
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.editor.is_some()
+    }
+
+    pub fn focus(&mut self) {
+        self.focus = Some(self.focus.unwrap_or(0));
+    }
+
+    pub fn focused(&self) -> Option<SheetField> {
+        self.focus.map(|i| SheetField::get_all_fields()[i])
+    }
+
+    // The field's value as it should be shown on the sheet,
+    // with the text being typed in place of the saved value.
+    pub fn display(&self, field: SheetField, character: &Character) -> String {
+        match (&self.editor, self.focused() == Some(field)) {
+            (Some(FieldEditor::Text(text)), true) => format!("{}_", text),
+            (Some(FieldEditor::Number(number)), true) => format!("< {} >", number),
+            _ => field.value(character),
+        }
+    }
+
+    pub fn style(&self, field: SheetField, theme: &Theme) -> Style {
+        let style = theme.key;
+        match (self.focused() == Some(field), self.is_editing()) {
+            (true, true) => style.patch(theme.editing),
+            (true, false) => style.add_modifier(Modifier::REVERSED),
+            _ => style,
+        }
+    }
+
+    pub fn handle_key(
+        &mut self,
+        event: KeyEvent,
+        action: Option<Action>,
+        character: &mut Character,
+    ) -> FieldInput {
+        let field = match self.focused() {
+            Some(field) => field,
+            None => return FieldInput::Unhandled,
+        };
+        let editor = match self.editor.as_mut() {
+            Some(editor) => editor,
+            None => return self.handle_focus(action, field, character),
+        };
+
+        match (editor, event.code) {
+            (_, KeyCode::Esc) => self.editor = None,
+            (_, KeyCode::Enter) => {
+                if let Some(editor) = self.editor.take() {
+                    if field.apply(&editor, character) {
+                        self.dirty = true;
+                        return FieldInput::Changed;
+                    }
+                }
+            }
+            (FieldEditor::Text(text), KeyCode::Char(c)) if text.len() < 40 => text.push(c),
+            (FieldEditor::Text(text), KeyCode::Backspace) => {
+                text.pop();
+            }
+
+            // Numbers step by one, or by ten with PageUp and PageDown,
+            // and can be typed in directly.
+            (FieldEditor::Number(number), code) => {
+                let step = match code {
+                    KeyCode::Char('+') | KeyCode::Char('l') | KeyCode::Up | KeyCode::Right => 1,
+                    KeyCode::Char('-') | KeyCode::Char('h') | KeyCode::Down | KeyCode::Left => -1,
+                    KeyCode::PageUp => 10,
+                    KeyCode::PageDown => -10,
+                    KeyCode::Char(digit) if digit.is_ascii_digit() => {
+                        *number = (*number * 10 + digit.to_digit(10).unwrap() as u64)
+                            .min(field.maximum());
+                        0
+                    }
+                    KeyCode::Backspace => {
+                        *number /= 10;
+                        0
+                    }
+                    _ => return FieldInput::Unhandled,
+                };
+                *number = (*number as i64 + step).clamp(0, field.maximum() as i64) as u64;
+            }
+            (FieldEditor::Choice(state), _) if action == Some(Action::Down) => {
+                let last = Alignment::get_all_alignments().len() - 1;
+                state.select(state.selected().map(|i| (i + 1).min(last)));
+            }
+            (FieldEditor::Choice(state), _) if action == Some(Action::Up) => {
+                state.select(state.selected().map(|i| i.saturating_sub(1)));
+            }
+            _ => return FieldInput::Unhandled,
+        }
+        FieldInput::Handled
+    }
+
+    fn handle_focus(
+        &mut self,
+        action: Option<Action>,
+        field: SheetField,
+        character: &Character,
+    ) -> FieldInput {
+        let count = SheetField::get_all_fields().len();
+        let focus = self.focus.unwrap_or(0);
+        match action {
+            Some(Action::Quit) => self.focus = None,
+            Some(Action::Down | Action::NextTab) => self.focus = Some((focus + 1) % count),
+            Some(Action::Up | Action::PreviousTab) => {
+                self.focus = Some((focus + count - 1) % count)
+            }
+            Some(Action::Select | Action::Edit) => self.editor = Some(field.editor(character)),
+            _ => return FieldInput::Unhandled,
+        }
+        FieldInput::Handled
+    }
+
+    // The dropdown for picking an alignment, drawn over
+    // the sheet while one is being edited.
+    pub fn render<B: Backend>(&mut self, frame: &mut Frame<B>, area: Rect, theme: &Theme) {
+        let state = match self.editor.as_mut() {
+            Some(FieldEditor::Choice(state)) => state,
+            _ => return,
+        };
+        let items = Alignment::get_all_alignments()
+            .into_iter()
+            .map(|alignment| ListItem::new(alignment.to_string()))
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(Block::default().title("Alignment").borders(Borders::ALL))
+            .highlight_style(theme.highlight)
+            .highlight_symbol(">");
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(list, area, state);
+    }
+}
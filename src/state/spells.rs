@@ -0,0 +1,191 @@
+use crate::{
+    data::spells::Spell,
+    state::{
+        keymap::{Action, Keymap, Screen},
+        theme::Theme,
+    },
+    Character,
+};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+// The character's spells, cantrips first, then by level and name.
+pub fn sorted_spells(character: &Character) -> Vec<Spell> {
+    let mut spells = character.spells.clone();
+    spells.sort_by(|a, b| a.level.cmp(&b.level).then_with(|| a.name.cmp(&b.name)));
+    spells
+}
+
+fn level_heading(level: u8) -> String {
+    match level {
+        0 => "Cantrips".to_string(),
+        1 => "1st Level".to_string(),
+        2 => "2nd Level".to_string(),
+        3 => "3rd Level".to_string(),
+        level => format!("{}th Level", level),
+    }
+}
+
+// A pip for each of the character's slots of the level,
+// filled for the ones they have left.
+fn slot_pips(character: &Character, level: u8) -> String {
+    let total = character.available_slots(level) + character.expended_slots[level as usize - 1];
+    (0..total)
+        .map(|i| match i < character.available_slots(level) {
+            true => '●',
+            false => '○',
+        })
+        .collect()
+}
+
+// Lists the character's spells under a heading for each level,
+// along with the slots they have of that level, and the selected
+// spell's details in a pane beside it.
+pub struct SpellPanel {
+    selected: usize,
+    scroll: u16,
+    // Where the description was drawn, for scrolling it with the mouse.
+    pub description_area: Rect,
+}
+
+impl SpellPanel {
+    pub fn new() -> SpellPanel {
+        SpellPanel {
+            selected: 0,
+            scroll: 0,
+            description_area: Rect::default(),
+        }
+    }
+
+    pub fn next(&mut self, character: &Character) {
+        let count = character.spells.len();
+        self.selected = (self.selected + 1).min(count.saturating_sub(1));
+        self.scroll = 0;
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+        self.scroll = 0;
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.scroll = self.scroll.saturating_add(1);
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    pub fn selected(&self, character: &Character) -> Option<Spell> {
+        sorted_spells(character).get(self.selected).cloned()
+    }
+
+    pub fn render<B: Backend>(
+        &mut self,
+        frame: &mut Frame<B>,
+        area: Rect,
+        character: &Character,
+        keymap: &Keymap,
+        theme: &Theme,
+    ) {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .split(area);
+        self.description_area = chunks[1];
+
+        let key_style = theme.key;
+
+        // As on the Features tab, headings take up rows of the list.
+        // Levels the character has slots of are listed even when
+        // they don't have any spells of that level.
+        let spells = sorted_spells(character);
+        let mut items = Vec::new();
+        let mut selected_row = None;
+        for level in 0..=9 {
+            let pips = match level {
+                0 => String::new(),
+                level => slot_pips(character, level),
+            };
+            if pips.is_empty() && !spells.iter().any(|spell| spell.level == level) {
+                continue;
+            }
+            items.push(ListItem::new(Spans::from(vec![
+                Span::styled(level_heading(level), key_style),
+                Span::raw(format!("  {}", pips)),
+            ])));
+            for (i, spell) in spells.iter().enumerate() {
+                if spell.level != level {
+                    continue;
+                }
+                if i == self.selected {
+                    selected_row = Some(items.len());
+                }
+                items.push(ListItem::new(format!("  {}", spell.name)));
+            }
+        }
+
+        let mut state = ListState::default();
+        state.select(selected_row);
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(format!(
+                        "Spells ({})",
+                        keymap.hints(
+                            Screen::Sheet,
+                            &[(&[Action::Use, Action::Restore], "spend/restore slot")]
+                        )
+                    ))
+                    .borders(Borders::RIGHT),
+            )
+            .highlight_style(theme.highlight)
+            .highlight_symbol(">");
+        frame.render_stateful_widget(list, chunks[0], &mut state);
+
+        let details = match spells.get(self.selected) {
+            Some(spell) => {
+                let school = spell
+                    .school
+                    .as_ref()
+                    .map(|school| school.to_string())
+                    .unwrap_or_default();
+                let lines = vec![
+                    Spans::from(vec![
+                        Span::styled(format!("{} ", level_heading(spell.level)), key_style),
+                        Span::raw(school),
+                    ]),
+                    Spans::from(vec![
+                        Span::styled("Casting Time: ", key_style),
+                        Span::raw(spell.casting_time.to_string()),
+                    ]),
+                    Spans::from(vec![
+                        Span::styled("Range: ", key_style),
+                        Span::raw(spell.range.to_string()),
+                    ]),
+                    Spans::from(vec![
+                        Span::styled("Components: ", key_style),
+                        Span::raw(spell.components.as_str()),
+                    ]),
+                    Spans::from(vec![
+                        Span::styled("Duration: ", key_style),
+                        Span::raw(spell.duration.to_string()),
+                    ]),
+                    Spans::from(""),
+                    Spans::from(spell.description.as_str()),
+                ];
+                Paragraph::new(lines)
+                    .block(Block::default().title(format!(" {} (PgUp/PgDn: scroll)", spell.name)))
+                    .wrap(Wrap { trim: false })
+                    .scroll((self.scroll, 0))
+            }
+            None => Paragraph::new("No spells").block(Block::default().title(" Spells")),
+        };
+        frame.render_widget(details, chunks[1]);
+    }
+}
@@ -1,20 +1,68 @@
-use crate::Character;
+use crate::{
+    data::{
+        rules::{
+            attack_bonus, damage_expression, proficiency_bonus, saving_throw_bonus,
+            saving_throw_proficient, weapon_proficient,
+        },
+        stats::Stats,
+    },
+    state::{
+        biography::BiographyPanel, features::FeaturePanel, inventory::InventoryPanel,
+        keymap::Keymap, notes::NotesPanel, rolls::RollPanel, spells::SpellPanel, theme::Theme,
+    },
+    Character,
+};
 use core::fmt;
 use enum_iterator::IntoEnumIterator;
-use std::io::Stdout;
 use tui::{
-    backend::CrosstermBackend,
-    layout::Rect,
-    style::Style,
-    widgets::{Block, Borders},
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::{Block, Borders, Row, Table},
     Frame,
 };
 
-#[derive(Clone, Copy, IntoEnumIterator)]
+#[derive(Clone, Copy, PartialEq, IntoEnumIterator)]
 pub enum CharacterSheetTab {
     Stats,
     Features,
     Spells,
+    Inventory,
+    Biography,
+    Notes,
+}
+
+// The state each tab keeps between draws, ie. what's selected in it.
+pub struct TabPanels {
+    pub rolls: RollPanel,
+    pub features: FeaturePanel,
+    pub spells: SpellPanel,
+    pub inventory: InventoryPanel,
+    pub biography: BiographyPanel,
+    pub notes: NotesPanel,
+}
+
+impl TabPanels {
+    pub fn new(character: &Character) -> TabPanels {
+        TabPanels {
+            rolls: RollPanel::new(character),
+            features: FeaturePanel::new(),
+            spells: SpellPanel::new(),
+            inventory: InventoryPanel::new(),
+            biography: BiographyPanel::new(),
+            notes: NotesPanel::new(),
+        }
+    }
+
+    // The part of the tab with a description that scrolls
+    // separately from its list, for tabs that have one.
+    pub fn description_area(&self, tab: CharacterSheetTab) -> Option<Rect> {
+        match tab {
+            CharacterSheetTab::Features => Some(self.features.description_area),
+            CharacterSheetTab::Spells => Some(self.spells.description_area),
+            CharacterSheetTab::Biography => Some(self.biography.description_area),
+            _ => None,
+        }
+    }
 }
 
 impl CharacterSheetTab {
@@ -31,15 +79,71 @@ impl CharacterSheetTab {
         all_tabs_strings
     }
 
-    pub fn display_tab(
+    // The index of the tab whose title is at the column, laid out as
+    // tui's Tabs draws them from the left of the area; each title has
+    // a space either side of it and a one column divider after it.
+    pub fn index_at(area: Rect, column: u16) -> Option<usize> {
+        let mut x = area.x;
+        for (index, title) in CharacterSheetTab::get_all_tab_strings().iter().enumerate() {
+            let width = title.len() as u16 + 2;
+            if column >= x && column < x + width {
+                return Some(index);
+            }
+            x += width + 1;
+        }
+        None
+    }
+
+    pub fn display_tab<B: Backend>(
         self,
-        frame: &mut Frame<CrosstermBackend<&mut Stdout>>,
+        frame: &mut Frame<B>,
         area: Rect,
         character: &Character,
+        panels: &mut TabPanels,
+        keymap: &Keymap,
+        theme: &Theme,
     ) {
         match self {
-            CharacterSheetTab::Stats => {}
-            _ => {}
+            CharacterSheetTab::Stats => {
+                let weapons = character
+                    .inventory
+                    .iter()
+                    .map(|entry| &entry.item)
+                    .filter(|item| item.weapon.is_some())
+                    .count() as u16;
+                // The Attacks section is left out for characters
+                // without any weapons.
+                let attacks_height = if weapons == 0 { 0 } else { weapons + 3 };
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(attacks_height), Constraint::Min(0)].as_ref())
+                    .split(area);
+                render_attacks(frame, chunks[0], character, theme);
+
+                // Ability scores sit beside the rolls, which
+                // list the character's saves and skills.
+                let chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Length(30), Constraint::Min(0)].as_ref())
+                    .split(chunks[1]);
+                render_abilities(frame, chunks[0], character, theme);
+                panels
+                    .rolls
+                    .render(frame, chunks[1], character, keymap, theme);
+            }
+            CharacterSheetTab::Features => panels
+                .features
+                .render(frame, area, character, keymap, theme),
+            CharacterSheetTab::Spells => {
+                panels.spells.render(frame, area, character, keymap, theme)
+            }
+            CharacterSheetTab::Inventory => panels
+                .inventory
+                .render(frame, area, character, keymap, theme),
+            CharacterSheetTab::Biography => panels
+                .biography
+                .render(frame, area, character, keymap, theme),
+            CharacterSheetTab::Notes => panels.notes.render(frame, area, character, keymap),
         }
     }
 }
@@ -50,6 +154,120 @@ impl std::fmt::Display for CharacterSheetTab {
             CharacterSheetTab::Stats => write!(formatter, "Stats"),
             CharacterSheetTab::Features => write!(formatter, "Features"),
             CharacterSheetTab::Spells => write!(formatter, "Spells"),
+            CharacterSheetTab::Inventory => write!(formatter, "Inventory"),
+            CharacterSheetTab::Biography => write!(formatter, "Biography"),
+            CharacterSheetTab::Notes => write!(formatter, "Notes"),
         }
     }
 }
+
+fn render_abilities<B: Backend>(
+    frame: &mut Frame<B>,
+    area: Rect,
+    character: &Character,
+    theme: &Theme,
+) {
+    let key_style = theme.key;
+
+    let mut rows = [
+        Stats::STR,
+        Stats::DEX,
+        Stats::CON,
+        Stats::INT,
+        Stats::WIS,
+        Stats::CHA,
+    ]
+    .iter()
+    .map(|stat| {
+        let marker = match saving_throw_proficient(character, stat) {
+            true => "*",
+            false => " ",
+        };
+        Row::new(vec![
+            stat.to_string(),
+            character.ability_score(stat).to_string(),
+            format!("{:+}", character.modifier(stat)),
+            format!("{}{:+}", marker, saving_throw_bonus(character, stat)),
+        ])
+    })
+    .collect::<Vec<_>>();
+    rows.push(Row::new(vec![String::new()]));
+    rows.push(Row::new(vec![
+        "Prof".to_string(),
+        format!("{:+}", proficiency_bonus(character.level)),
+    ]));
+
+    let abilities = Table::new(rows)
+        .header(Row::new(vec!["", "Score", "Mod", "Save"]).style(key_style))
+        .block(Block::default().title("Abilities").borders(Borders::RIGHT))
+        .widths(&[
+            Constraint::Length(5),
+            Constraint::Length(6),
+            Constraint::Length(5),
+            Constraint::Length(5),
+        ]);
+
+    frame.render_widget(abilities, area);
+}
+
+fn render_attacks<B: Backend>(
+    frame: &mut Frame<B>,
+    area: Rect,
+    character: &Character,
+    theme: &Theme,
+) {
+    let key_style = theme.key;
+
+    let rows = character
+        .inventory
+        .iter()
+        .filter_map(|entry| {
+            let item = &entry.item;
+            let weapon = item.weapon.as_ref()?;
+            let name = match weapon_proficient(character, item) {
+                true => format!("* {}", item.name),
+                false => format!("  {}", item.name),
+            };
+            let range = match weapon.range {
+                Some(range) => format!("{} ft.", range),
+                None if weapon.category.is_ranged() => "-".to_string(),
+                None => "Melee".to_string(),
+            };
+            let damage = match (
+                damage_expression(character, entry, false),
+                damage_expression(character, entry, true).filter(|_| weapon.versatile().is_some()),
+            ) {
+                (Some(one), Some(two)) => format!("{} ({})", one, two),
+                (Some(one), None) => one.to_string(),
+                _ => String::new(),
+            };
+            Some(Row::new(vec![
+                name,
+                format!("{:+}", attack_bonus(character, entry).unwrap_or(0)),
+                damage,
+                range,
+                weapon
+                    .properties
+                    .iter()
+                    .map(|property| property.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ]))
+        })
+        .collect::<Vec<_>>();
+
+    let attacks = Table::new(rows)
+        .header(
+            Row::new(vec!["Weapon", "Attack", "Damage", "Range", "Properties"]).style(key_style),
+        )
+        .block(Block::default().title("Attacks").borders(Borders::BOTTOM))
+        .widths(&[
+            Constraint::Percentage(20),
+            Constraint::Length(6),
+            Constraint::Percentage(30),
+            Constraint::Length(12),
+            Constraint::Percentage(35),
+        ]);
+
+    frame.render_widget(attacks, area);
+}
@@ -0,0 +1,1064 @@
+// Snapshot tests for the screens. Each state is drawn into a
+// TestBackend rather than the terminal, driven with the same
+// KeyEvents crossterm would give it, and the buffer it rendered
+// is checked against what the user should see.
+use crate::{
+    config::Config,
+    data::{
+        catalog::{CatalogEntry, SearchHit, SearchKind},
+        character::SavedCharacter,
+        conditions::Condition,
+        feat::Feat,
+        feature::{Feature, FeatureResource, Recharge, ResourceMax},
+        items::{Container, Item, ItemCharges},
+        rolls::{Roll, RollKind, RollStatistics},
+        spells::Spell,
+        srd,
+    },
+    state::{
+        app::{HandleKeyboardInput, HandleKeyboardInput::*, State, States},
+        character_sheet::CharacterSheet,
+        equipment_picker::EquipmentPicker,
+        keymap::Keymap,
+        select_screen::SelectScreen,
+        theme::Theme,
+    },
+    Character,
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use tui::{
+    backend::TestBackend,
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    Terminal,
+};
+
+fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+// Sends each key to the state in turn, returning what the last one did.
+fn press<S: State<TestBackend>>(state: &mut S, codes: &[KeyCode]) -> HandleKeyboardInput {
+    let mut result = Void;
+    for code in codes {
+        result = state.handle_keyboard_event(key(*code)).unwrap();
+    }
+    result
+}
+
+fn mouse<S: State<TestBackend>>(
+    state: &mut S,
+    kind: MouseEventKind,
+    column: u16,
+    row: u16,
+) -> HandleKeyboardInput {
+    let event = MouseEvent {
+        kind,
+        column,
+        row,
+        modifiers: KeyModifiers::NONE,
+    };
+    state.handle_mouse_event(event).unwrap()
+}
+
+fn click<S: State<TestBackend>>(state: &mut S, column: u16, row: u16) -> HandleKeyboardInput {
+    mouse(state, MouseEventKind::Down(MouseButton::Left), column, row)
+}
+
+fn type_text<S: State<TestBackend>>(state: &mut S, text: &str) {
+    for c in text.chars() {
+        state.handle_keyboard_event(key(KeyCode::Char(c))).unwrap();
+    }
+}
+
+fn draw<S: State<TestBackend>>(state: &mut S, width: u16, height: u16) -> Buffer {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|f| state.display_screen(f)).unwrap();
+    terminal.backend().buffer().clone()
+}
+
+// The rendered text, one string per line with trailing space trimmed,
+// for checks that don't care about styling or exact positions.
+fn lines(buffer: &Buffer) -> Vec<String> {
+    let width = buffer.area.width as usize;
+    buffer
+        .content
+        .chunks(width)
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.symbol.as_str())
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+fn contains(buffer: &Buffer, text: &str) -> bool {
+    lines(buffer).iter().any(|line| line.contains(text))
+}
+
+fn saved(id: i64, name: &str, race: &str, class: &str) -> SavedCharacter {
+    SavedCharacter {
+        id: Some(id),
+        name: name.to_string(),
+        race: race.to_string(),
+        class: class.to_string(),
+        level: 3,
+        modified: id,
+    }
+}
+
+fn select_screen() -> SelectScreen {
+    SelectScreen::new(
+        vec![
+            saved(1, "Mira", "Half-Elf", "Rogue"),
+            saved(2, "Tordek", "Hill Dwarf", "Fighter"),
+        ],
+        Keymap::default(),
+        Theme::default(),
+    )
+}
+
+fn character() -> Character {
+    Character {
+        name: "Mira".to_string(),
+        race: "Half-Elf".to_string(),
+        class: "Rogue".to_string(),
+        level: 3,
+        hit_points: 17,
+        max_hit_points: 17,
+        gender: "Female".to_string(),
+        ..Character::new()
+    }
+}
+
+fn character_sheet() -> CharacterSheet {
+    CharacterSheet::new(character(), Keymap::default(), Theme::default())
+}
+
+#[test]
+fn select_screen_lists_saved_characters() {
+    let buffer = draw(&mut select_screen(), 40, 5);
+
+    let mut expected = Buffer::with_lines(vec![
+        "Character Sheets                        ",
+        ">Mira - level 3 Half-Elf Rogue          ",
+        " Tordek - level 3 Hill Dwarf Fighter    ",
+        " New Character Sheet                    ",
+        "Sorted by name                          ",
+    ]);
+    expected.set_style(Rect::new(0, 0, 40, 4), Style::default().fg(Color::White));
+    expected.set_style(
+        Rect::new(0, 1, 40, 1),
+        Style::default()
+            .fg(Color::Green)
+            .add_modifier(Modifier::BOLD),
+    );
+    expected.set_style(Rect::new(0, 4, 14, 1), Style::default().fg(Color::Gray));
+    assert_eq!(buffer, expected);
+}
+
+#[test]
+fn select_screen_moves_selection_within_bounds() {
+    let mut screen = select_screen();
+    press(
+        &mut screen,
+        &[KeyCode::Down, KeyCode::Char('j'), KeyCode::Down],
+    );
+    assert_eq!(lines(&draw(&mut screen, 32, 5))[3], ">New Character Sheet");
+
+    press(&mut screen, &[KeyCode::Up, KeyCode::Char('k'), KeyCode::Up]);
+    assert_eq!(
+        lines(&draw(&mut screen, 40, 5))[1],
+        ">Mira - level 3 Half-Elf Rogue"
+    );
+}
+
+#[test]
+fn select_screen_opens_the_selected_character() {
+    let mut screen = select_screen();
+    match press(&mut screen, &[KeyCode::Down, KeyCode::Enter]) {
+        ChangeState(States::CharacterSheet(character)) => assert_eq!(character.id, Some(2)),
+        _ => panic!("expected the character sheet to be opened"),
+    }
+}
+
+#[test]
+fn select_screen_opens_the_wizard_from_new_character_sheet() {
+    let mut screen = select_screen();
+    let result = press(&mut screen, &[KeyCode::Down, KeyCode::Down, KeyCode::Enter]);
+    assert!(matches!(result, ChangeState(States::CreationWizard)));
+    assert!(matches!(press(&mut screen, &[KeyCode::Esc]), Exit));
+}
+
+#[test]
+fn character_sheet_shows_details_and_tabs() {
+    let buffer = draw(&mut character_sheet(), 100, 40);
+    assert!(contains(&buffer, "Name: Mira"));
+    assert!(contains(&buffer, "Gender: Female"));
+    assert!(contains(&buffer, "Level: 3"));
+    assert!(contains(&buffer, "HP: 17"));
+    assert_eq!(
+        lines(&buffer)[16],
+        " Stats | Features | Spells | Inventory | Biography | Notes"
+    );
+    assert!(contains(&buffer, "│STR   10     +0     +0"));
+}
+
+#[test]
+fn character_sheet_switches_tabs() {
+    let mut sheet = character_sheet();
+    press(&mut sheet, &[KeyCode::Tab]);
+    assert!(contains(&draw(&mut sheet, 100, 40), "No features"));
+
+    press(&mut sheet, &[KeyCode::BackTab, KeyCode::BackTab]);
+    let buffer = draw(&mut sheet, 100, 40);
+    assert!(contains(&buffer, "Notes (Enter: edit, j/k: scroll)"));
+}
+
+#[test]
+fn character_sheet_edits_and_writes_the_name() {
+    let mut sheet = character_sheet();
+    press(&mut sheet, &[KeyCode::Char('e'), KeyCode::Enter]);
+    for _ in 0.."Mira".len() {
+        press(&mut sheet, &[KeyCode::Backspace]);
+    }
+    type_text(&mut sheet, "Mirabel");
+    press(&mut sheet, &[KeyCode::Enter, KeyCode::Esc]);
+
+    let buffer = draw(&mut sheet, 100, 40);
+    assert!(contains(&buffer, "Name: Mirabel"));
+    assert!(contains(&buffer, "Modified - w: write"));
+
+    // Rolls made before the character has an id aren't saved.
+    assert!(matches!(press(&mut sheet, &[KeyCode::Char('r')]), Input));
+    press(&mut sheet, &[KeyCode::Char('h')]);
+    assert!(contains(&draw(&mut sheet, 100, 40), "1d20"));
+    press(&mut sheet, &[KeyCode::Char('h')]);
+    let written = written(&mut sheet);
+    assert_eq!(written.name, "Mirabel");
+
+    // Once saved the sheet has the character's id, and starts a
+    // history of its own, so its rolls are saved from then on.
+    State::<TestBackend>::character_saved(
+        &mut sheet,
+        Character {
+            id: Some(7),
+            ..written
+        },
+    );
+    let buffer = draw(&mut sheet, 100, 40);
+    assert!(contains(&buffer, "Written"));
+    assert!(!contains(&buffer, "Modified"));
+    press(&mut sheet, &[KeyCode::Char('h')]);
+    assert!(!contains(&draw(&mut sheet, 100, 40), "1d20"));
+    press(&mut sheet, &[KeyCode::Char('h')]);
+    assert!(matches!(
+        press(&mut sheet, &[KeyCode::Char('r')]),
+        SaveRoll(_)
+    ));
+}
+
+// What the sheet asks to write when 'w' is pressed.
+fn written(sheet: &mut CharacterSheet) -> Character {
+    match press(sheet, &[KeyCode::Char('w')]) {
+        WriteCharacter(character) => *character,
+        _ => panic!("expected the character to be written"),
+    }
+}
+
+#[test]
+fn character_sheet_warns_before_discarding_changes() {
+    let mut sheet = character_sheet();
+    press(&mut sheet, &[KeyCode::Char('n')]);
+
+    assert!(matches!(press(&mut sheet, &[KeyCode::Char('q')]), Input));
+    assert!(contains(
+        &draw(&mut sheet, 100, 40),
+        "Unsaved changes - w: write, again to discard"
+    ));
+    assert!(matches!(
+        press(&mut sheet, &[KeyCode::Char('q')]),
+        ChangeState(States::SelectScreen)
+    ));
+}
+
+#[test]
+fn character_sheet_rests_and_leaves_the_write_to_the_user() {
+    let mut character = character();
+    character.hit_points = 4;
+    character.temp_hit_points = 5;
+    character.add_condition(Condition::Exhaustion(4), None);
+    for (name, recharge) in [
+        ("Second Wind", Recharge::ShortRest),
+        ("Indomitable", Recharge::LongRest),
+    ] {
+        character.features.push(Feature {
+            name: name.to_string(),
+            resource: Some(FeatureResource {
+                max: ResourceMax::Fixed(1),
+                recharge,
+            }),
+            ..Feature::default()
+        });
+        character.expended_uses.insert(name.to_string(), 1);
+    }
+    let mut sheet = CharacterSheet::new(character, Keymap::default(), Theme::default());
+
+    assert!(matches!(press(&mut sheet, &[KeyCode::Char('s')]), Input));
+    assert!(contains(
+        &draw(&mut sheet, 100, 40),
+        "Finished a short rest"
+    ));
+    let rested = written(&mut sheet);
+    assert_eq!(rested.expended_uses.get("Second Wind"), None);
+    assert_eq!(rested.expended_uses.get("Indomitable"), Some(&1));
+    assert_eq!(rested.hit_points, 4);
+
+    // A long rest takes a level of exhaustion off before the hit points
+    // come back, so from level 4 they're back to the whole maximum.
+    press(&mut sheet, &[KeyCode::Char('S')]);
+    let rested = written(&mut sheet);
+    assert!(rested.expended_uses.is_empty());
+    assert_eq!(rested.exhaustion(), 3);
+    assert_eq!(rested.hit_points, 17);
+    assert_eq!(rested.temp_hit_points, 0);
+}
+
+fn inventory_sheet(items: &[(&str, u16)]) -> CharacterSheet {
+    let mut character = character();
+    for (name, quantity) in items {
+        character.add_item(
+            Item {
+                name: name.to_string(),
+                ..Item::new()
+            },
+            *quantity,
+        );
+    }
+    let mut sheet = CharacterSheet::new(character, Keymap::default(), Theme::default());
+    press(&mut sheet, &[KeyCode::Tab, KeyCode::Tab, KeyCode::Tab]);
+    sheet
+}
+
+// The item and quantity columns of the Inventory tab's rows,
+// with ">" marking the one selected.
+fn inventory_rows(sheet: &mut CharacterSheet) -> Vec<String> {
+    lines(&draw(sheet, 100, 40))
+        .into_iter()
+        .skip_while(|line| !line.contains("│ Item"))
+        .skip(1)
+        .map(|line| line.trim_matches('│').chars().collect::<Vec<_>>())
+        .take_while(|row| row.iter().any(|c| !c.is_whitespace()))
+        .map(|row| {
+            let item = row[..41].iter().collect::<String>();
+            let quantity = row[41..46].iter().collect::<String>();
+            format!("{} x{}", item.trim_end(), quantity.trim())
+        })
+        .collect()
+}
+
+#[test]
+fn inventory_splits_and_merges_stacks() {
+    let mut sheet = inventory_sheet(&[("Arrow", 21), ("Rope", 1), ("Torch", 1)]);
+    assert_eq!(
+        inventory_rows(&mut sheet),
+        vec![">Arrow x21", " Rope x1", " Torch x1"]
+    );
+
+    press(&mut sheet, &[KeyCode::Char('v')]);
+    assert_eq!(
+        inventory_rows(&mut sheet),
+        vec![">Arrow x11", " Rope x1", " Torch x1", " Arrow x10"]
+    );
+
+    // Stacks of one can't be split, and there's nothing to merge
+    // them with without another stack of the same item.
+    press(&mut sheet, &[KeyCode::Char('j'), KeyCode::Char('v')]);
+    assert!(contains(
+        &draw(&mut sheet, 100, 40),
+        "Only stacks of more than one can be split"
+    ));
+    press(&mut sheet, &[KeyCode::Char('m')]);
+    assert!(contains(
+        &draw(&mut sheet, 100, 40),
+        "There's no other stack to merge with"
+    ));
+
+    // Merging looks for the next stack down, going back round to
+    // the top, and the selection follows the stack merged into.
+    press(&mut sheet, &[KeyCode::Char('j'), KeyCode::Char('j')]);
+    assert!(matches!(press(&mut sheet, &[KeyCode::Char('m')]), Input));
+    assert_eq!(
+        inventory_rows(&mut sheet),
+        vec![">Arrow x21", " Rope x1", " Torch x1"]
+    );
+}
+
+#[test]
+fn inventory_stows_items_in_each_container_in_turn() {
+    let mut sheet = inventory_sheet(&[("Rope", 1)]);
+    press(&mut sheet, &[KeyCode::Char('i')]);
+    assert!(contains(
+        &draw(&mut sheet, 100, 40),
+        "There's no container with room for it"
+    ));
+
+    let mut character = character();
+    for (name, capacity) in [("Rope", None), ("Pouch", Some(6)), ("Backpack", Some(30))] {
+        character.add_item(
+            Item {
+                name: name.to_string(),
+                weight: 1,
+                container: capacity.map(|capacity| Container {
+                    capacity,
+                    weightless: false,
+                }),
+                ..Item::new()
+            },
+            1,
+        );
+    }
+    let mut sheet = CharacterSheet::new(character, Keymap::default(), Theme::default());
+    press(&mut sheet, &[KeyCode::Tab, KeyCode::Tab, KeyCode::Tab]);
+
+    press(&mut sheet, &[KeyCode::Char('i')]);
+    assert_eq!(
+        inventory_rows(&mut sheet),
+        vec![" Pouch x1", ">  Rope x1", " Backpack x1"]
+    );
+    press(&mut sheet, &[KeyCode::Char('i')]);
+    assert_eq!(
+        inventory_rows(&mut sheet),
+        vec![" Pouch x1", " Backpack x1", ">  Rope x1"]
+    );
+    // After the last container it's taken back out.
+    press(&mut sheet, &[KeyCode::Char('i')]);
+    assert_eq!(
+        inventory_rows(&mut sheet),
+        vec![">Rope x1", " Pouch x1", " Backpack x1"]
+    );
+}
+
+#[test]
+fn item_charges_are_used_from_the_inventory_and_regained_on_rests() {
+    let mut character = character();
+    character.add_item(
+        Item {
+            name: "Wand".to_string(),
+            charges: Some(ItemCharges {
+                max: 2,
+                recharge: Recharge::Dawn,
+                regain: None,
+            }),
+            ..Item::new()
+        },
+        1,
+    );
+    character.add_item(
+        Item {
+            name: "Rope".to_string(),
+            ..Item::new()
+        },
+        1,
+    );
+    let mut sheet = CharacterSheet::new(character, Keymap::default(), Theme::default());
+    press(&mut sheet, &[KeyCode::Tab, KeyCode::Tab, KeyCode::Tab]);
+
+    press(&mut sheet, &[KeyCode::Char('u')]);
+    assert!(contains(&draw(&mut sheet, 100, 40), "1/2"));
+    press(&mut sheet, &[KeyCode::Char('u'), KeyCode::Char('u')]);
+    let buffer = draw(&mut sheet, 100, 40);
+    assert!(contains(&buffer, "0/2"));
+    assert!(contains(&buffer, "Wand has no charges left"));
+    press(&mut sheet, &[KeyCode::Char('j'), KeyCode::Char('u')]);
+    assert!(contains(&draw(&mut sheet, 100, 40), "Rope has no charges"));
+
+    // The wand recharges at dawn, which a long rest sees in.
+    press(&mut sheet, &[KeyCode::Char('S')]);
+    assert!(contains(
+        &draw(&mut sheet, 100, 40),
+        "Finished a long rest, Wand regained 2"
+    ));
+    assert_eq!(written(&mut sheet).inventory[0].current_charges(), Some(2));
+}
+
+#[test]
+fn inventory_sells_items_for_half_their_value() {
+    let mut character = character();
+    for (name, value) in [("Rope", Some("1 gp")), ("Rock", None)] {
+        character.add_item(
+            Item {
+                name: name.to_string(),
+                value: value.map(|value| value.parse().unwrap()),
+                ..Item::new()
+            },
+            1,
+        );
+    }
+    let mut sheet = CharacterSheet::new(character, Keymap::default(), Theme::default());
+    press(&mut sheet, &[KeyCode::Tab, KeyCode::Tab, KeyCode::Tab]);
+
+    press(&mut sheet, &[KeyCode::Char('$')]);
+    assert_eq!(inventory_rows(&mut sheet), vec![">Rock x1"]);
+    assert!(contains(&draw(&mut sheet, 100, 40), "Coins: 5 sp"));
+    press(&mut sheet, &[KeyCode::Char('$')]);
+    assert!(contains(&draw(&mut sheet, 100, 40), "Rock can't be sold"));
+    assert_eq!(inventory_rows(&mut sheet), vec![">Rock x1"]);
+}
+
+#[test]
+fn help_lists_the_screens_bindings() {
+    let mut screen = select_screen();
+    press(&mut screen, &[KeyCode::Char('?')]);
+    let buffer = draw(&mut screen, 60, 12);
+    assert!(contains(&buffer, "Keys - character select"));
+    assert!(contains(&buffer, "│k, Up            Move up"));
+    assert!(contains(&buffer, "│Esc              Quit"));
+
+    // Any key closes the help without doing anything else.
+    assert!(matches!(press(&mut screen, &[KeyCode::Esc]), Input));
+    assert!(!contains(
+        &draw(&mut screen, 60, 12),
+        "Keys - character select"
+    ));
+}
+
+#[test]
+fn character_sheet_uses_keys_from_the_config() {
+    let config = Config::parse(
+        r#"
+        [keys.sheet]
+        next_tab = "l"
+        quit = ["Ctrl+c", "Esc"]
+        "#,
+    )
+    .unwrap();
+    let mut sheet = CharacterSheet::new(character(), config.keymap, config.theme);
+
+    press(&mut sheet, &[KeyCode::Tab]);
+    assert!(!contains(&draw(&mut sheet, 100, 40), "No features"));
+    press(&mut sheet, &[KeyCode::Char('l')]);
+    assert!(contains(&draw(&mut sheet, 100, 40), "No features"));
+
+    let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+    assert!(matches!(
+        State::<TestBackend>::handle_keyboard_event(&mut sheet, ctrl_c).unwrap(),
+        Exit
+    ));
+}
+
+#[test]
+fn key_hints_show_the_keys_from_the_config() {
+    let config = Config::parse(
+        r#"
+        [keys.sheet]
+        roll = ["R", "r"]
+        advantage = "A"
+        disadvantage = []
+        use = "g"
+        restore = "G"
+        "#,
+    )
+    .unwrap();
+    let mut sheet = CharacterSheet::new(character(), config.keymap, config.theme);
+
+    // Only the first key is shown, and hints for actions
+    // without any keys are left out.
+    assert!(contains(
+        &draw(&mut sheet, 100, 40),
+        "Rolls (R: roll, A: adv)"
+    ));
+    press(&mut sheet, &[KeyCode::Tab]);
+    assert!(contains(
+        &draw(&mut sheet, 100, 40),
+        "Features (g/G: use/restore)"
+    ));
+}
+
+#[test]
+fn config_rejects_conflicting_and_unknown_bindings() {
+    let error = Config::parse("[keys.sheet]\nroll = \"j\"").err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "'j' is bound to both down and roll on the character sheet"
+    );
+    assert!(Config::parse("[keys.select]\nwrite = \"w\"").is_err());
+    assert!(Config::parse("[keys.sheet]\nroll = \"Hyper+r\"").is_err());
+    assert!(Config::parse("[keys.select]\nup = [\"w\", \"Up\"]").is_ok());
+}
+
+#[test]
+fn monochrome_theme_draws_without_colors() {
+    let config = Config::parse("theme = \"monochrome\"").unwrap();
+    let mut screen = SelectScreen::new(
+        vec![saved(1, "Mira", "Half-Elf", "Rogue")],
+        config.keymap,
+        config.theme,
+    );
+    let buffer = draw(&mut screen, 32, 4);
+
+    let mut expected = Buffer::with_lines(vec![
+        "Character Sheets                ",
+        ">Mira - level 3 Half-Elf Rogue  ",
+        " New Character Sheet            ",
+        "Sorted by name                  ",
+    ]);
+    expected.set_style(
+        Rect::new(0, 1, 32, 1),
+        Style::default().add_modifier(Modifier::REVERSED),
+    );
+    assert_eq!(buffer, expected);
+
+    let error = Config::parse("theme = \"solarized\"").err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Unknown theme 'solarized', expected one of dark, light, high-contrast, monochrome"
+    );
+}
+
+#[test]
+fn select_screen_selects_and_opens_with_the_mouse() {
+    let mut screen = select_screen();
+    draw(&mut screen, 40, 5);
+
+    assert!(matches!(click(&mut screen, 3, 2), Input));
+    assert_eq!(
+        lines(&draw(&mut screen, 40, 5))[2],
+        ">Tordek - level 3 Hill Dwarf Fighter"
+    );
+    match click(&mut screen, 3, 2) {
+        ChangeState(States::CharacterSheet(character)) => assert_eq!(character.id, Some(2)),
+        _ => panic!("expected the clicked character to be opened"),
+    }
+
+    // The wheel moves the selection, and clicks below the list do nothing.
+    mouse(&mut screen, MouseEventKind::ScrollDown, 3, 2);
+    assert_eq!(lines(&draw(&mut screen, 32, 5))[3], ">New Character Sheet");
+    assert!(matches!(click(&mut screen, 3, 4), Void));
+}
+
+#[test]
+fn character_sheet_switches_tabs_and_rolls_with_the_mouse() {
+    let mut sheet = character_sheet();
+    let buffer = draw(&mut sheet, 100, 40);
+
+    // The Stats tab's rolls are rolled by clicking them.
+    let row = lines(&buffer)
+        .iter()
+        .position(|line| line.contains("Acrobatics"))
+        .unwrap();
+    let column = lines(&buffer)[row].find("Acrobatics").unwrap();
+    assert!(matches!(
+        click(&mut sheet, column as u16, row as u16),
+        Input
+    ));
+    assert!(contains(&draw(&mut sheet, 100, 40), "Acrobatics (DEX): ["));
+
+    // " Stats | Features | ..." is drawn on row 16.
+    click(&mut sheet, 12, 16);
+    assert!(contains(&draw(&mut sheet, 100, 40), "No features"));
+    click(&mut sheet, 55, 16);
+    assert!(contains(
+        &draw(&mut sheet, 100, 40),
+        "Notes (Enter: edit, j/k: scroll)"
+    ));
+}
+
+#[test]
+fn config_turns_the_mouse_off() {
+    assert!(Config::parse("").unwrap().mouse);
+    assert!(!Config::parse("mouse = false").unwrap().mouse);
+    assert!(Config::parse("mouse = \"no\"").is_err());
+}
+
+#[test]
+fn select_screen_sorts_and_filters_characters() {
+    let mut screen = select_screen();
+    press(&mut screen, &[KeyCode::Char('s')]);
+    let buffer = draw(&mut screen, 40, 5);
+    assert_eq!(lines(&buffer)[1], " Tordek - level 3 Hill Dwarf Fighter");
+    assert_eq!(lines(&buffer)[2], ">Mira - level 3 Half-Elf Rogue");
+    assert_eq!(lines(&buffer)[4], "Sorted by class");
+
+    press(&mut screen, &[KeyCode::Char('f')]);
+    type_text(&mut screen, "DWARF");
+    press(&mut screen, &[KeyCode::Enter]);
+    let buffer = draw(&mut screen, 40, 5);
+    assert_eq!(lines(&buffer)[1], ">Tordek - level 3 Hill Dwarf Fighter");
+    assert_eq!(lines(&buffer)[2], " New Character Sheet");
+    assert_eq!(lines(&buffer)[4], "Sorted by class, filtered by 'DWARF'");
+}
+
+#[test]
+fn select_screen_deletes_and_renames_characters() {
+    let mut screen = select_screen();
+    press(&mut screen, &[KeyCode::Char('d')]);
+    assert!(contains(&draw(&mut screen, 60, 5), "Delete Mira? y: yes"));
+    assert!(matches!(press(&mut screen, &[KeyCode::Char('n')]), Input));
+    assert!(matches!(
+        press(&mut screen, &[KeyCode::Char('d'), KeyCode::Char('y')]),
+        DeleteCharacter(1)
+    ));
+
+    press(&mut screen, &[KeyCode::Char('r'), KeyCode::Backspace]);
+    type_text(&mut screen, "abel");
+    match press(&mut screen, &[KeyCode::Enter]) {
+        RenameCharacter(1, name) => assert_eq!(name, "Mirabel"),
+        _ => panic!("expected the character to be renamed"),
+    }
+
+    // The list App gives back after a change replaces the old one.
+    State::<TestBackend>::characters_changed(
+        &mut screen,
+        &[saved(2, "Tordek", "Hill Dwarf", "Fighter")],
+    );
+    assert_eq!(
+        lines(&draw(&mut screen, 40, 5))[1],
+        ">Tordek - level 3 Hill Dwarf Fighter"
+    );
+    assert!(matches!(
+        press(&mut screen, &[KeyCode::Char('c')]),
+        DuplicateCharacter(2)
+    ));
+
+    // A change the database refused is shown until the next key.
+    State::<TestBackend>::show_error(&mut screen, "database is locked".to_string());
+    assert!(contains(&draw(&mut screen, 60, 5), "database is locked"));
+    press(&mut screen, &[KeyCode::Down]);
+    assert!(!contains(&draw(&mut screen, 60, 5), "database is locked"));
+}
+
+fn catalog() -> Vec<CatalogEntry> {
+    vec![
+        CatalogEntry::Spell(Spell {
+            id: Some(1),
+            name: "Magic Missile".to_string(),
+            level: 1,
+            description: "Three glowing darts of magical force.".to_string(),
+            ..Spell::new()
+        }),
+        CatalogEntry::Spell(Spell {
+            id: Some(2),
+            name: "Mage Armor".to_string(),
+            level: 1,
+            ..Spell::new()
+        }),
+        CatalogEntry::Item(Box::new(Item {
+            id: Some(1),
+            name: "Rope, hempen (50 feet)".to_string(),
+            ..Item::new()
+        })),
+        CatalogEntry::Character(saved(2, "Tordek", "Hill Dwarf", "Fighter")),
+    ]
+}
+
+#[test]
+fn search_palette_adds_entries_to_the_character() {
+    let mut sheet = character_sheet();
+    assert!(matches!(press(&mut sheet, &[KeyCode::Char('/')]), Search));
+    State::<TestBackend>::open_search(&mut sheet, catalog());
+
+    type_text(&mut sheet, "mgms");
+    let buffer = draw(&mut sheet, 100, 40);
+    assert!(contains(&buffer, "> mgms_"));
+    assert!(contains(&buffer, ">Magic Missile  Spell"));
+    assert!(!contains(&buffer, "Mage Armor"));
+
+    press(&mut sheet, &[KeyCode::Enter]);
+    let buffer = draw(&mut sheet, 100, 40);
+    assert!(contains(
+        &buffer,
+        "Magic Missile (Enter: add to character, Esc: back)"
+    ));
+    assert!(contains(&buffer, "Three glowing darts of magical force."));
+
+    press(&mut sheet, &[KeyCode::Enter]);
+    assert!(contains(&draw(&mut sheet, 100, 40), "Added Magic Missile"));
+    press(
+        &mut sheet,
+        &[
+            KeyCode::BackTab,
+            KeyCode::BackTab,
+            KeyCode::BackTab,
+            KeyCode::BackTab,
+        ],
+    );
+    assert!(contains(&draw(&mut sheet, 100, 40), "Magic Missile"));
+}
+
+#[test]
+fn feats_are_taken_from_the_palette_when_their_prerequisites_are_met() {
+    let mut sheet = character_sheet();
+    let feat = |name: &str, prerequisites: &str| {
+        CatalogEntry::Feat(Feat {
+            id: Some(1),
+            name: name.to_string(),
+            prerequisites: prerequisites.split("; ").flat_map(str::parse).collect(),
+            ..Feat::default()
+        })
+    };
+    let mut take = |entry: CatalogEntry| {
+        press(&mut sheet, &[KeyCode::Char('/')]);
+        State::<TestBackend>::open_search(&mut sheet, vec![entry]);
+        press(&mut sheet, &[KeyCode::Enter, KeyCode::Enter]);
+        draw(&mut sheet, 100, 40)
+    };
+
+    let buffer = take(feat("Grappler", "STR 13"));
+    assert!(contains(&buffer, "Can't take Grappler. Requires STR 13"));
+    assert!(contains(
+        &take(feat("Elven Accuracy", "Race: Half-Elf")),
+        "Added Elven Accuracy"
+    ));
+    let buffer = take(feat("Elven Accuracy", "Race: Half-Elf"));
+    assert!(contains(&buffer, "Mira already has Elven Accuracy"));
+
+    press(&mut sheet, &[KeyCode::Tab]);
+    let buffer = draw(&mut sheet, 100, 40);
+    assert!(contains(&buffer, "Elven Accuracy"));
+    assert!(!contains(&buffer, "Grappler"));
+}
+
+#[test]
+fn items_are_bought_from_the_palette_and_sold_from_the_inventory() {
+    let mut character = character();
+    character.purse = "1 gp, 5 sp".parse().unwrap();
+    let mut sheet = CharacterSheet::new(character, Keymap::default(), Theme::default());
+    let rope = CatalogEntry::Item(Box::new(Item {
+        id: Some(1),
+        name: "Rope".to_string(),
+        value: Some("1 gp".parse().unwrap()),
+        ..Item::new()
+    }));
+    let buy_rope = |sheet: &mut CharacterSheet| {
+        press(sheet, &[KeyCode::Char('/')]);
+        State::<TestBackend>::open_search(sheet, vec![rope.clone()]);
+        press(sheet, &[KeyCode::Enter]);
+        assert!(contains(
+            &draw(sheet, 100, 40),
+            "Rope (Enter: add, b: buy for 1 gp, Esc: back)"
+        ));
+        press(sheet, &[KeyCode::Char('b')]);
+    };
+
+    buy_rope(&mut sheet);
+    let buffer = draw(&mut sheet, 100, 40);
+    assert!(contains(&buffer, "Bought Rope for 1 gp"));
+    assert!(contains(&buffer, "Coins: 5 sp"));
+    buy_rope(&mut sheet);
+    let buffer = draw(&mut sheet, 100, 40);
+    assert!(contains(&buffer, "Can't afford Rope for 1 gp"));
+    assert!(contains(&buffer, "Coins: 5 sp"));
+
+    press(&mut sheet, &[KeyCode::Tab, KeyCode::Tab, KeyCode::Tab]);
+    assert_eq!(inventory_rows(&mut sheet), vec![">Rope x1"]);
+    press(&mut sheet, &[KeyCode::Char('$')]);
+    assert!(inventory_rows(&mut sheet).is_empty());
+    assert!(contains(&draw(&mut sheet, 100, 40), "Coins: 10 sp"));
+}
+
+#[test]
+fn search_palette_opens_characters() {
+    let mut screen = select_screen();
+    assert!(matches!(press(&mut screen, &[KeyCode::Char('/')]), Search));
+    State::<TestBackend>::open_search(&mut screen, catalog());
+    type_text(&mut screen, "tor");
+    match press(&mut screen, &[KeyCode::Enter]) {
+        ChangeState(States::CharacterSheet(character)) => assert_eq!(character.id, Some(2)),
+        _ => panic!("expected the character to be opened"),
+    }
+    assert!(matches!(press(&mut screen, &[KeyCode::Esc]), Input));
+    assert!(matches!(press(&mut screen, &[KeyCode::Esc]), Exit));
+}
+
+#[test]
+fn search_palette_lists_description_matches_after_names() {
+    let mut screen = select_screen();
+    State::<TestBackend>::open_search(&mut screen, catalog());
+    type_text(&mut screen, "for");
+    match press(&mut screen, &[KeyCode::Char('c')]) {
+        SearchCatalog(query) => assert_eq!(query, "forc"),
+        _ => panic!("expected the catalog to be searched"),
+    }
+    // Neither name matches, so both are only found by their descriptions.
+    let hit = |id: usize, snippet: &str| {
+        let hit = SearchHit {
+            kind: SearchKind::Spell,
+            id: id as i64,
+            snippet: snippet.to_string(),
+        };
+        (catalog()[id - 1].clone(), hit)
+    };
+    State::<TestBackend>::search_results(
+        &mut screen,
+        Vec::new(),
+        vec![
+            hit(1, "Three glowing darts of magical \u{2}force\u{3}."),
+            hit(2, "A protective magical \u{2}force\u{3} surrounds you"),
+        ],
+    );
+
+    let buffer = draw(&mut screen, 100, 40);
+    assert!(contains(&buffer, ">Magic Missile  Spell"));
+    assert!(contains(
+        &buffer,
+        "   Three glowing darts of magical force."
+    ));
+    assert!(contains(&buffer, " Mage Armor  Spell"));
+    let lines = lines(&buffer);
+    let row = lines
+        .iter()
+        .position(|line| line.contains("protective"))
+        .unwrap();
+    let line = &lines[row];
+    let column = line[..line.find("force").unwrap()].chars().count() as u16;
+    let theme = Theme::default();
+    assert_eq!(buffer.get(column, row as u16).fg, theme.key.fg.unwrap());
+    assert_eq!(
+        buffer.get(column - 2, row as u16).fg,
+        theme.muted.fg.unwrap()
+    );
+}
+
+#[test]
+fn roll_history_loads_a_page_at_a_time() {
+    // A character who hasn't been saved has no rolls to load.
+    assert!(matches!(
+        press(&mut character_sheet(), &[KeyCode::Char('h')]),
+        Input
+    ));
+
+    let saved = Character {
+        id: Some(4),
+        ..character()
+    };
+    let mut sheet = CharacterSheet::new(saved, Keymap::default(), Theme::default());
+    match press(&mut sheet, &[KeyCode::Char('h')]) {
+        LoadRolls(query) => assert_eq!(
+            query.clauses().unwrap(),
+            " WHERE character = ? ORDER BY timestamp DESC, id DESC LIMIT 50"
+        ),
+        _ => panic!("expected the first page of rolls to be loaded"),
+    }
+    let roll = |id: i64| Roll {
+        id: Some(id),
+        character: Some(4),
+        kind: Some(RollKind::Check),
+        label: format!("Check {}", id),
+        total: 10,
+        natural_d20: Some(8),
+        ..Roll::default()
+    };
+    let statistics = RollStatistics {
+        rolls: 120,
+        total: 1200,
+        d20s: 120,
+        d20_total: 960,
+        natural_20s: 6,
+        natural_1s: 5,
+    };
+    State::<TestBackend>::rolls_loaded(&mut sheet, (0..50).map(roll).collect(), statistics);
+    let buffer = draw(&mut sheet, 100, 40);
+    assert!(contains(&buffer, "Rolls: 120  Average total: 10.00"));
+    assert!(contains(&buffer, "Average d20: 8.00"));
+    assert!(contains(&buffer, "Check 0"));
+
+    // The next page is asked for once the selection is
+    // halfway down the last one loaded.
+    for _ in 0..24 {
+        assert!(matches!(press(&mut sheet, &[KeyCode::Down]), Input));
+    }
+    match press(&mut sheet, &[KeyCode::Down]) {
+        LoadRolls(query) => assert!(query.clauses().unwrap().ends_with("LIMIT 50 OFFSET 50")),
+        _ => panic!("expected the next page of rolls to be loaded"),
+    }
+
+    // Filtering loads the history again from the first page.
+    match press(&mut sheet, &[KeyCode::Tab]) {
+        LoadRolls(query) => assert_eq!(
+            query.clauses().unwrap(),
+            " WHERE character = ? AND kind = ? ORDER BY timestamp DESC, id DESC LIMIT 50"
+        ),
+        _ => panic!("expected the filtered rolls to be loaded"),
+    }
+    assert!(!contains(&draw(&mut sheet, 100, 40), "Check 0"));
+}
+
+#[test]
+fn equipment_picker_loads_picks_a_page_at_a_time() {
+    let fighter = srd::classes()
+        .into_iter()
+        .find(|class| class.name == "Fighter")
+        .unwrap();
+    let criminal = srd::backgrounds()
+        .into_iter()
+        .find(|background| background.name == "Criminal")
+        .unwrap();
+    let mut picker = EquipmentPicker::new(&fighter, &criminal);
+    let item = |name: String| Item {
+        name,
+        ..Item::new()
+    };
+    let weapons = |range: std::ops::Range<usize>| {
+        range
+            .map(|i| item(format!("Weapon {:02}", i)))
+            .collect::<Vec<_>>()
+    };
+    let picked = |picker: &EquipmentPicker| {
+        picker
+            .selection()
+            .into_iter()
+            .map(|(item, _)| item.name)
+            .collect::<Vec<_>>()
+    };
+
+    // The named items, and the first page for the only
+    // group whose first option is "any martial weapon".
+    let queries = picker.start();
+    assert_eq!(queries.len(), 2);
+    assert!(queries[1]
+        .clauses()
+        .unwrap()
+        .ends_with("ORDER BY name ASC LIMIT 50"));
+    let shield = Item {
+        id: Some(7),
+        ..item("Shield".to_string())
+    };
+    picker.loaded(vec![(vec![shield], 1), (weapons(0..50), 60)]);
+    assert!(picked(&picker).contains(&"Weapon 00".to_string()));
+    assert!(picker
+        .selection()
+        .iter()
+        .any(|(item, _)| item.name == "Shield" && item.id == Some(7)));
+
+    picker.next();
+    for _ in 0..24 {
+        assert!(picker.next_pick().is_empty());
+    }
+    let queries = picker.next_pick();
+    assert_eq!(queries.len(), 1);
+    assert!(queries[0]
+        .clauses()
+        .unwrap()
+        .ends_with("LIMIT 50 OFFSET 50"));
+    picker.loaded(vec![(weapons(50..60), 60)]);
+    for _ in 0..34 {
+        assert!(picker.next_pick().is_empty());
+    }
+    assert!(picked(&picker).contains(&"Weapon 59".to_string()));
+    // Past the last of them, the picks start over.
+    assert!(picker.next_pick().is_empty());
+    assert!(picked(&picker).contains(&"Weapon 00".to_string()));
+
+    // The other option starts from its own first page.
+    let queries = picker.next_option();
+    assert_eq!(queries.len(), 1);
+    assert!(!picked(&picker)
+        .iter()
+        .any(|name| name.starts_with("Weapon")));
+    picker.loaded(vec![(weapons(0..3), 3)]);
+    assert!(picked(&picker).contains(&"Weapon 00".to_string()));
+}
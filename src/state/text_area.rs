@@ -0,0 +1,41 @@
+use crossterm::event::{KeyCode, KeyEvent};
+
+// A block of text being typed on the sheet, ie. the character's
+// backstory or notes. Text is only added to or taken from the
+// end; Enter starts a new line and Esc finishes editing.
+pub struct TextArea {
+    pub text: String,
+}
+
+pub enum TextInput {
+    Typed,
+    Finished,
+    Unhandled,
+}
+
+impl TextArea {
+    pub fn new(text: &str) -> TextArea {
+        TextArea {
+            text: text.to_string(),
+        }
+    }
+
+    pub fn handle_key(&mut self, event: KeyEvent) -> TextInput {
+        match event.code {
+            KeyCode::Esc => return TextInput::Finished,
+            KeyCode::Enter => self.text.push('\n'),
+            KeyCode::Tab => self.text.push_str("    "),
+            KeyCode::Char(c) => self.text.push(c),
+            KeyCode::Backspace => {
+                self.text.pop();
+            }
+            _ => return TextInput::Unhandled,
+        }
+        TextInput::Typed
+    }
+
+    // The text with a cursor at the end of it, for display.
+    pub fn display(&self) -> String {
+        format!("{}_", self.text)
+    }
+}
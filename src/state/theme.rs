@@ -0,0 +1,131 @@
+use anyhow::{bail, Result};
+use std::env;
+use tui::style::{Color, Modifier, Style};
+
+// The styles every screen draws with, by what they're used for
+// rather than what they look like, so the whole app can be
+// restyled by picking another theme in the config file:
+//
+//     theme = "light"
+//
+// The monochrome theme is always used when NO_COLOR is set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    // Plain text, ie. the list of characters.
+    pub text: Style,
+    // Labels and headings, ie. "AC: " or a table's header.
+    pub key: Style,
+    // The selected item of a list or table.
+    pub highlight: Style,
+    // Anything in the background, ie. the tabs that aren't open.
+    pub muted: Style,
+    // Good and bad results; natural 20s and 1s, or errors.
+    pub success: Style,
+    pub failure: Style,
+    // The field on the sheet that's being typed in.
+    pub editing: Style,
+}
+
+pub const THEMES: [&str; 4] = ["dark", "light", "high-contrast", "monochrome"];
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Theme {
+        let key = Style::default()
+            .fg(Color::Green)
+            .add_modifier(Modifier::BOLD);
+        Theme {
+            text: Style::default().fg(Color::White),
+            key,
+            highlight: key,
+            muted: Style::default().fg(Color::Gray),
+            success: Style::default().fg(Color::Green),
+            failure: Style::default().fg(Color::Red),
+            editing: Style::default().fg(Color::Black).bg(Color::Yellow),
+        }
+    }
+
+    // For terminals with a light background, where white and
+    // yellow text can't be read.
+    pub fn light() -> Theme {
+        let key = Style::default()
+            .fg(Color::Blue)
+            .add_modifier(Modifier::BOLD);
+        Theme {
+            text: Style::default().fg(Color::Black),
+            key,
+            highlight: key,
+            muted: Style::default().fg(Color::DarkGray),
+            success: Style::default().fg(Color::Green),
+            failure: Style::default().fg(Color::Red),
+            editing: Style::default().fg(Color::White).bg(Color::Blue),
+        }
+    }
+
+    pub fn high_contrast() -> Theme {
+        Theme {
+            text: Style::default().fg(Color::White),
+            key: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            highlight: Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            muted: Style::default().fg(Color::White),
+            success: Style::default()
+                .fg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD),
+            failure: Style::default()
+                .fg(Color::LightRed)
+                .add_modifier(Modifier::BOLD),
+            editing: Style::default().fg(Color::Black).bg(Color::White),
+        }
+    }
+
+    // No colors at all, only bold, reversed and underlined text.
+    pub fn monochrome() -> Theme {
+        Theme {
+            text: Style::default(),
+            key: Style::default().add_modifier(Modifier::BOLD),
+            highlight: Style::default().add_modifier(Modifier::REVERSED),
+            muted: Style::default(),
+            success: Style::default().add_modifier(Modifier::BOLD),
+            failure: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            editing: Style::default().add_modifier(Modifier::UNDERLINED | Modifier::REVERSED),
+        }
+    }
+
+    pub fn named(name: &str) -> Result<Theme> {
+        match name {
+            "dark" => Ok(Theme::dark()),
+            "light" => Ok(Theme::light()),
+            "high-contrast" => Ok(Theme::high_contrast()),
+            "monochrome" => Ok(Theme::monochrome()),
+            _ => bail!(
+                "Unknown theme '{}', expected one of {}",
+                name,
+                THEMES.join(", ")
+            ),
+        }
+    }
+
+    // The theme named in the config file, if any, unless NO_COLOR
+    // asks for no colors (see no-color.org).
+    pub fn from_config(name: Option<&toml::Value>) -> Result<Theme> {
+        let theme = match name {
+            Some(toml::Value::String(name)) => Theme::named(name)?,
+            Some(_) => bail!("theme should be one of {}", THEMES.join(", ")),
+            None => Theme::default(),
+        };
+        match env::var_os("NO_COLOR") {
+            Some(no_color) if !no_color.is_empty() => Ok(Theme::monochrome()),
+            _ => Ok(theme),
+        }
+    }
+}
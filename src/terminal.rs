@@ -0,0 +1,53 @@
+use anyhow::Result;
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::{
+    io::{stdout, Stdout},
+    panic,
+};
+use tui::{backend::CrosstermBackend, Terminal};
+
+// The terminal the app draws to for as long as it runs. It's only
+// ever set up and restored here, so the user's terminal is given
+// back as it was however the app exits.
+pub type AppTerminal = Terminal<CrosstermBackend<Stdout>>;
+
+// Puts the terminal in raw mode on the alternate screen. A panic
+// hook is installed first, so a panic restores the terminal before
+// its message is printed, rather than leaving it unusable. Mouse
+// events are only sent when they're asked for.
+pub fn setup(mouse: bool) -> Result<AppTerminal> {
+    install_panic_hook();
+    enable_raw_mode()?;
+    let mut stdout = stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    if mouse {
+        execute!(stdout, EnableMouseCapture)?;
+    }
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+    terminal.hide_cursor()?;
+    terminal.clear()?;
+    Ok(terminal)
+}
+
+pub fn restore() -> Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        stdout(),
+        DisableMouseCapture,
+        LeaveAlternateScreen,
+        crossterm::cursor::Show
+    )?;
+    Ok(())
+}
+
+fn install_panic_hook() {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore();
+        hook(info);
+    }));
+}